@@ -0,0 +1,9 @@
+CREATE TABLE history (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT UNIQUE,
+    station_uuid TEXT NOT NULL,
+    is_local BOOLEAN NOT NULL DEFAULT FALSE,
+    station_data TEXT,
+    start_time BIGINT NOT NULL,
+    stop_time BIGINT,
+    song_titles TEXT NOT NULL DEFAULT '[]'
+);
@@ -0,0 +1,9 @@
+CREATE TABLE song_log (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT UNIQUE,
+    station_uuid TEXT NOT NULL,
+    station_name TEXT NOT NULL,
+    title TEXT NOT NULL,
+    timestamp BIGINT NOT NULL
+);
+
+CREATE INDEX song_log_timestamp ON song_log (timestamp);
@@ -0,0 +1,10 @@
+CREATE TABLE alarms (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT UNIQUE,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    hour INTEGER NOT NULL,
+    minute INTEGER NOT NULL,
+    weekdays INTEGER NOT NULL DEFAULT 0,
+    station_uuid TEXT NOT NULL,
+    volume DOUBLE NOT NULL DEFAULT 0.5,
+    inhibit BOOLEAN NOT NULL DEFAULT TRUE
+);
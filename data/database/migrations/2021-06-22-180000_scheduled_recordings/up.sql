@@ -0,0 +1,9 @@
+CREATE TABLE scheduled_recordings (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT UNIQUE,
+    station_uuid TEXT NOT NULL,
+    is_local BOOLEAN NOT NULL,
+    station_data TEXT NOT NULL,
+    start_time BIGINT NOT NULL,
+    duration INTEGER NOT NULL,
+    path TEXT NOT NULL
+);
@@ -0,0 +1,4 @@
+CREATE TABLE station_settings (
+    station_uuid TEXT NOT NULL PRIMARY KEY,
+    auto_save BOOLEAN NOT NULL DEFAULT FALSE
+);
@@ -0,0 +1 @@
+DROP TABLE auto_save_rules;
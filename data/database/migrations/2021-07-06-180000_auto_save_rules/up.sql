@@ -0,0 +1,7 @@
+CREATE TABLE auto_save_rules (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT UNIQUE,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    kind TEXT NOT NULL,
+    value TEXT NOT NULL DEFAULT '',
+    min_duration INTEGER NOT NULL DEFAULT 0
+);
@@ -0,0 +1,10 @@
+CREATE TABLE station_settingstmp (
+    station_uuid TEXT NOT NULL PRIMARY KEY,
+    auto_save BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+INSERT INTO station_settingstmp (station_uuid, auto_save)
+    SELECT station_uuid, auto_save FROM station_settings;
+
+DROP TABLE station_settings;
+ALTER TABLE station_settingstmp RENAME TO station_settings;
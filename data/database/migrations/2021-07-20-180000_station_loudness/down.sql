@@ -0,0 +1,11 @@
+CREATE TABLE station_settingstmp (
+    station_uuid TEXT NOT NULL PRIMARY KEY,
+    auto_save BOOLEAN NOT NULL DEFAULT FALSE,
+    equalizer_preset TEXT
+);
+
+INSERT INTO station_settingstmp (station_uuid, auto_save, equalizer_preset)
+    SELECT station_uuid, auto_save, equalizer_preset FROM station_settings;
+
+DROP TABLE station_settings;
+ALTER TABLE station_settingstmp RENAME TO station_settings;
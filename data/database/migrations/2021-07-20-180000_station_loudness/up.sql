@@ -0,0 +1 @@
+ALTER TABLE station_settings ADD COLUMN loudness_gain DOUBLE;
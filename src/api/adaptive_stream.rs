@@ -0,0 +1,191 @@
+// Shortwave - adaptive_stream.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use futures::io::AsyncReadExt;
+use isahc::prelude::*;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use url::Url;
+
+use std::path::Path;
+
+use crate::api::client::HTTP_CLIENT;
+use crate::api::Error;
+
+/// HLS playlists and DASH manifests are small, so there's no reason to read more than this.
+const MAX_MANIFEST_SIZE: u64 = 1024 * 1024;
+
+static HLS_ATTRIBUTE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"([A-Z0-9-]+)=("[^"]*"|[^,]*)"#).unwrap());
+static DASH_REPRESENTATION: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<Representation\b([^>]*)>").unwrap());
+static DASH_BANDWIDTH: Lazy<Regex> = Lazy::new(|| Regex::new(r#"\bbandwidth="(\d+)""#).unwrap());
+static DASH_CODECS: Lazy<Regex> = Lazy::new(|| Regex::new(r#"\bcodecs="([^"]*)""#).unwrap());
+static DASH_MIME_TYPE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"\bmimeType="([^"]*)""#).unwrap());
+
+#[derive(Copy, Debug, Clone, PartialEq)]
+pub enum StreamProtocol {
+    Hls,
+    Dash,
+}
+
+impl StreamProtocol {
+    /// Determines the protocol based on the file extension of the URL.
+    pub fn for_url(url: &Url) -> Option<Self> {
+        let extension = Path::new(url.path()).extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "m3u8" => Some(Self::Hls),
+            "mpd" => Some(Self::Dash),
+            _ => None,
+        }
+    }
+
+    /// Determines the protocol based on the HTTP content type.
+    /// Plain M3U playlists are using the same content types as HLS, so they have to be checked
+    /// by their content afterwards.
+    pub fn for_content_type(content_type: &str) -> Option<Self> {
+        let content_type = content_type.split(';').next().unwrap_or_default().trim().to_lowercase();
+        match content_type.as_str() {
+            "application/vnd.apple.mpegurl" | "application/x-mpegurl" | "audio/mpegurl" | "audio/x-mpegurl" => Some(Self::Hls),
+            "application/dash+xml" => Some(Self::Dash),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Hls => "HLS",
+            Self::Dash => "DASH",
+        }
+    }
+}
+
+/// A single variant (or representation) of an adaptive stream.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamVariant {
+    pub protocol: StreamProtocol,
+    pub url: Url,
+    /// Bandwidth in bits per second
+    pub bandwidth: u32,
+    pub codecs: Option<String>,
+}
+
+/// A HLS or DASH stream, which offers the same audio in different variants.
+#[derive(Debug, Clone)]
+pub struct AdaptiveStream {
+    pub protocol: StreamProtocol,
+    pub url: Url,
+    /// All variants, sorted by bandwidth in ascending order
+    pub variants: Vec<StreamVariant>,
+}
+
+impl AdaptiveStream {
+    /// Checks whether the URL points to a HLS or DASH stream, and fetches the available variants.
+    /// `hls` forces the detection, otherwise the content type / file extension gets used.
+    /// Returns `None` for regular streams, without downloading any of the audio data.
+    pub async fn fetch(url: &Url, hls: bool) -> Result<Option<Self>, Error> {
+        let mut response = HTTP_CLIENT.get_async(url.as_str()).await?;
+
+        let content_type = response.headers().get("content-type").and_then(|value| value.to_str().ok()).unwrap_or_default();
+        let protocol = match StreamProtocol::for_content_type(content_type) {
+            Some(protocol) => protocol,
+            // The URL points to the audio stream itself, even if the station claims to be HLS
+            None if is_media_type(content_type) => return Ok(None),
+            None => match StreamProtocol::for_url(url) {
+                Some(protocol) => protocol,
+                None if hls => StreamProtocol::Hls,
+                None => return Ok(None),
+            },
+        };
+
+        // Redirects are followed by the client, relative URLs have to be resolved against the final location
+        let base_url = response.effective_uri().and_then(|uri| Url::parse(&uri.to_string()).ok()).unwrap_or_else(|| url.clone());
+
+        // Never read endlessly, in case it's an audio stream with a wrong content type after all
+        let mut data = Vec::new();
+        response.body_mut().take(MAX_MANIFEST_SIZE).read_to_end(&mut data).await?;
+        let data = String::from_utf8_lossy(&data);
+
+        let variants = match protocol {
+            StreamProtocol::Hls => {
+                // Plain M3U playlists don't use any of the HLS tags
+                if !data.contains("#EXT-X-") {
+                    return Ok(None);
+                }
+                Self::parse_hls(&data, &base_url)
+            }
+            StreamProtocol::Dash => Self::parse_dash(&data, url),
+        };
+
+        debug!("Found {} stream with {} variants", protocol.name(), variants.len());
+        Ok(Some(Self { protocol, url: url.clone(), variants }))
+    }
+
+    /// Parses a HLS master playlist. Media playlists don't have any variants.
+    fn parse_hls(data: &str, base_url: &Url) -> Vec<StreamVariant> {
+        let mut variants = Vec::new();
+        let mut attributes = None;
+
+        for line in data.lines().map(str::trim) {
+            if let Some(info) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+                attributes = Some(info.to_string());
+            } else if line.is_empty() || line.starts_with('#') {
+                continue;
+            } else if let Some(info) = attributes.take() {
+                let attribute = |name: &str| HLS_ATTRIBUTE.captures_iter(&info).find(|c| &c[1] == name).map(|c| c[2].trim_matches('"').to_string());
+
+                let bandwidth = attribute("BANDWIDTH").and_then(|b| b.parse().ok());
+                match (base_url.join(line), bandwidth) {
+                    (Ok(url), Some(bandwidth)) => variants.push(StreamVariant {
+                        protocol: StreamProtocol::Hls,
+                        url,
+                        bandwidth,
+                        codecs: attribute("CODECS"),
+                    }),
+                    _ => warn!("Ignoring invalid HLS variant: {}", line),
+                }
+            }
+        }
+
+        variants.sort_by_key(|v| v.bandwidth);
+        variants
+    }
+
+    /// Parses the representations of a DASH manifest. They can't be addressed by their own URL,
+    /// so all of them are using the manifest URL.
+    fn parse_dash(data: &str, url: &Url) -> Vec<StreamVariant> {
+        let mut variants: Vec<StreamVariant> = DASH_REPRESENTATION
+            .captures_iter(data)
+            .filter(|c| DASH_MIME_TYPE.captures(&c[1]).map_or(true, |m| !m[1].starts_with("video")))
+            .filter_map(|c| {
+                let bandwidth = DASH_BANDWIDTH.captures(&c[1])?[1].parse().ok()?;
+                Some(StreamVariant {
+                    protocol: StreamProtocol::Dash,
+                    url: url.clone(),
+                    bandwidth,
+                    codecs: DASH_CODECS.captures(&c[1]).map(|m| m[1].to_string()),
+                })
+            })
+            .collect();
+
+        variants.sort_by_key(|v| v.bandwidth);
+        variants
+    }
+}
+
+/// Whether the content type is the one of an actual audio stream, e.g. `audio/mpeg` of Icecast servers.
+fn is_media_type(content_type: &str) -> bool {
+    let content_type = content_type.split(';').next().unwrap_or_default().trim().to_lowercase();
+    content_type.starts_with("audio/") || content_type == "application/ogg"
+}
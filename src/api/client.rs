@@ -93,6 +93,16 @@ impl Client {
         }
     }
 
+    /// Tries to find a radio-browser.info station which uses the passed stream URL.
+    pub async fn station_metadata_by_url(self, stream_url: &Url) -> Result<Option<StationMetadata>, Error> {
+        let query = serde_urlencoded::to_string(&[("url", stream_url.as_str())]).unwrap();
+        let url = self.build_url(STATION_BY_URL, Some(&query)).await?;
+        debug!("Request station by URL: {}", url);
+
+        let mut metadata: Vec<StationMetadata> = HTTP_CLIENT.get_async(url.as_ref()).await?.json().await?;
+        Ok(metadata.pop())
+    }
+
     async fn build_url(&self, param: &str, options: Option<&str>) -> Result<Url, Error> {
         if self.server.get().is_none() {
             let server_ip = Self::api_server(self.lookup_domain.clone()).await.ok_or(Error::NoServerReachable)?;
@@ -16,22 +16,29 @@
 
 static STATION_SEARCH: &str = "json/stations/search";
 static STATION_BY_UUID: &str = "json/stations/byuuid/";
+static STATION_BY_URL: &str = "json/stations/byurl";
 static STATS: &str = "json/stats";
 
+mod adaptive_stream;
 mod client;
 mod error;
 mod favicon_downloader;
 mod object;
+mod playlist;
+mod playlist_resolver;
 mod station;
 mod station_metadata;
 mod station_request;
 mod station_url;
 mod stats;
 
+pub use adaptive_stream::{AdaptiveStream, StreamProtocol, StreamVariant};
 pub use client::Client;
 pub use error::Error;
 pub use favicon_downloader::FaviconDownloader;
 pub use object::Object;
+pub use playlist::{Playlist, PlaylistEntry, PlaylistFormat};
+pub use playlist_resolver::PlaylistResolver;
 pub use station::SwStation;
 pub use station_metadata::StationMetadata;
 pub use station_request::StationRequest;
@@ -0,0 +1,550 @@
+// Shortwave - playlist.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use url::Url;
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::api::SwStation;
+
+static M3U_LOGO: Lazy<Regex> = Lazy::new(|| Regex::new(r#"(?:tvg-)?logo="([^"]*)""#).unwrap());
+static XSPF_TRACK: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<track>(.*?)</track>").unwrap());
+static XSPF_LOCATION: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<location>(.*?)</location>").unwrap());
+static XSPF_TITLE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<title>(.*?)</title>").unwrap());
+static XSPF_IMAGE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<image>(.*?)</image>").unwrap());
+static ASX_ENTRY: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<entry(?:ref)?\b([^>]*?)(?:/>|>(.*?)</entry>)").unwrap());
+static ASX_HREF: Lazy<Regex> = Lazy::new(|| Regex::new(r#"(?is)\bhref\s*=\s*"([^"]*)""#).unwrap());
+static ASX_REF: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<ref\b([^>]*)>").unwrap());
+static ASX_TITLE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<title>(.*?)</title>").unwrap());
+
+#[derive(Copy, Debug, Clone, PartialEq)]
+pub enum PlaylistFormat {
+    M3u,
+    Pls,
+    Xspf,
+    Asx,
+}
+
+impl PlaylistFormat {
+    /// Determines the playlist format based on the file extension.
+    pub fn for_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "m3u" | "m3u8" => Some(Self::M3u),
+            "pls" => Some(Self::Pls),
+            "xspf" => Some(Self::Xspf),
+            "asx" => Some(Self::Asx),
+            _ => None,
+        }
+    }
+
+    /// Determines the playlist format based on the HTTP content type.
+    pub fn for_content_type(content_type: &str) -> Option<Self> {
+        let content_type = content_type.split(';').next().unwrap_or_default().trim().to_lowercase();
+        match content_type.as_str() {
+            "audio/x-mpegurl" | "audio/mpegurl" | "application/x-mpegurl" | "application/vnd.apple.mpegurl" => Some(Self::M3u),
+            "audio/x-scpls" | "application/pls+xml" => Some(Self::Pls),
+            "application/xspf+xml" => Some(Self::Xspf),
+            "video/x-ms-asx" | "audio/x-ms-asx" | "video/x-ms-wvx" | "audio/x-ms-wax" => Some(Self::Asx),
+            _ => None,
+        }
+    }
+
+    /// Determines the playlist format of a remote URL based on its file extension.
+    /// HLS playlists (m3u8) are streams on their own, so they don't count as playlist.
+    pub fn for_url(url: &Url) -> Option<Self> {
+        let path = Path::new(url.path());
+        if path.extension().map_or(false, |e| e.eq_ignore_ascii_case("m3u8")) {
+            return None;
+        }
+        Self::for_path(path)
+    }
+
+    /// Determines the playlist format based on the content itself.
+    pub fn guess(data: &str) -> Self {
+        let data = data.trim_start().to_lowercase();
+        if data.starts_with("[playlist]") {
+            Self::Pls
+        } else if data.starts_with("<asx") {
+            Self::Asx
+        } else if data.starts_with('<') {
+            Self::Xspf
+        } else {
+            Self::M3u
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::M3u => "m3u",
+            Self::Pls => "pls",
+            Self::Xspf => "xspf",
+            Self::Asx => "asx",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaylistEntry {
+    pub url: Url,
+    pub title: Option<String>,
+    pub image: Option<Url>,
+}
+
+impl PlaylistEntry {
+    pub fn for_station(station: &SwStation) -> Option<Self> {
+        let metadata = station.metadata();
+        let url = metadata.url_resolved.or(metadata.url)?;
+
+        Some(Self {
+            url,
+            title: Some(metadata.name),
+            image: metadata.favicon,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Playlist {
+    pub entries: Vec<PlaylistEntry>,
+}
+
+impl Playlist {
+    /// Parses the playlist data. Relative entries are getting resolved against `base`,
+    /// which is the URL the playlist got downloaded from.
+    pub fn parse(data: &str, format: PlaylistFormat, base: Option<&Url>) -> Self {
+        let entries = match format {
+            PlaylistFormat::M3u => Self::parse_m3u(data, base),
+            PlaylistFormat::Pls => Self::parse_pls(data, base),
+            PlaylistFormat::Xspf => Self::parse_xspf(data, base),
+            PlaylistFormat::Asx => Self::parse_asx(data, base),
+        };
+
+        debug!("Parsed {} playlist entries ({:?})", entries.len(), format);
+        Self { entries }
+    }
+
+    pub fn serialize(&self, format: PlaylistFormat) -> String {
+        match format {
+            PlaylistFormat::M3u => self.serialize_m3u(),
+            PlaylistFormat::Pls => self.serialize_pls(),
+            PlaylistFormat::Xspf => self.serialize_xspf(),
+            PlaylistFormat::Asx => self.serialize_asx(),
+        }
+    }
+
+    fn parse_m3u(data: &str, base: Option<&Url>) -> Vec<PlaylistEntry> {
+        let mut entries = Vec::new();
+        let mut title = None;
+        let mut image = None;
+
+        for line in data.lines().map(str::trim) {
+            if let Some(info) = line.strip_prefix("#EXTINF:") {
+                // #EXTINF:-1 tvg-logo="https://example.org/logo.png",Station Name
+                // The title is everything after the first comma which isn't part of a quoted attribute.
+                let mut quoted = false;
+                let split = info.char_indices().find(|(_, c)| {
+                    if *c == '"' {
+                        quoted = !quoted;
+                    }
+                    *c == ',' && !quoted
+                });
+
+                title = split.map(|(pos, _)| info[pos + 1..].trim().to_string()).filter(|t| !t.is_empty());
+                image = M3U_LOGO.captures(info).and_then(|c| Url::parse(&c[1]).ok());
+            } else if line.is_empty() || line.starts_with('#') {
+                continue;
+            } else if let Some(url) = parse_url(line, base) {
+                entries.push(PlaylistEntry {
+                    url,
+                    title: title.take(),
+                    image: image.take(),
+                });
+            } else {
+                warn!("Ignoring invalid playlist line: {}", line);
+            }
+        }
+
+        entries
+    }
+
+    fn parse_pls(data: &str, base: Option<&Url>) -> Vec<PlaylistEntry> {
+        let mut files: BTreeMap<u32, Url> = BTreeMap::new();
+        let mut titles: BTreeMap<u32, String> = BTreeMap::new();
+
+        for line in data.lines().map(str::trim) {
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim().to_lowercase(), value.trim()),
+                None => continue,
+            };
+
+            if let Some(index) = key.strip_prefix("file").and_then(|i| i.parse().ok()) {
+                match parse_url(value, base) {
+                    Some(url) => {
+                        files.insert(index, url);
+                    }
+                    None => warn!("Ignoring invalid playlist line: {}", line),
+                }
+            } else if let Some(index) = key.strip_prefix("title").and_then(|i| i.parse().ok()) {
+                titles.insert(index, value.to_string());
+            }
+        }
+
+        files
+            .into_iter()
+            .map(|(index, url)| PlaylistEntry {
+                url,
+                title: titles.remove(&index).filter(|t| !t.is_empty()),
+                image: None,
+            })
+            .collect()
+    }
+
+    fn parse_xspf(data: &str, base: Option<&Url>) -> Vec<PlaylistEntry> {
+        let mut entries = Vec::new();
+
+        for track in XSPF_TRACK.captures_iter(data) {
+            let track = &track[1];
+            let value = |regex: &Regex| regex.captures(track).map(|c| xml_unescape(c[1].trim()));
+
+            match value(&XSPF_LOCATION).and_then(|l| parse_url(&l, base)) {
+                Some(url) => entries.push(PlaylistEntry {
+                    url,
+                    title: value(&XSPF_TITLE).filter(|t| !t.is_empty()),
+                    image: value(&XSPF_IMAGE).and_then(|i| parse_url(&i, base)),
+                }),
+                None => warn!("Ignoring playlist track without valid location"),
+            }
+        }
+
+        entries
+    }
+
+    fn parse_asx(data: &str, base: Option<&Url>) -> Vec<PlaylistEntry> {
+        let mut entries = Vec::new();
+
+        for entry in ASX_ENTRY.captures_iter(data) {
+            // <entryref href="..."/> references another playlist, which gets resolved later on.
+            // An <entry> can contain multiple <ref> elements as fallback (often mms:// first, which can't be
+            // played, and http:// second), so all of them are used in the same order.
+            let content = entry.get(2).map_or("", |c| c.as_str());
+            let hrefs: Vec<String> = match ASX_HREF.captures(&entry[1]) {
+                Some(href) => vec![xml_unescape(href[1].trim())],
+                None => ASX_REF
+                    .captures_iter(content)
+                    .filter_map(|r| ASX_HREF.captures(r.get(1).unwrap().as_str()))
+                    .map(|c| xml_unescape(c[1].trim()))
+                    .collect(),
+            };
+
+            if hrefs.is_empty() {
+                warn!("Ignoring playlist entry without reference");
+                continue;
+            }
+
+            let title = ASX_TITLE.captures(content).map(|c| xml_unescape(c[1].trim())).filter(|t| !t.is_empty());
+            for href in hrefs {
+                match parse_url(&href, base) {
+                    Some(url) => entries.push(PlaylistEntry {
+                        url,
+                        title: title.clone(),
+                        image: None,
+                    }),
+                    None => warn!("Ignoring invalid playlist reference: {}", href),
+                }
+            }
+        }
+
+        entries
+    }
+
+    fn serialize_m3u(&self) -> String {
+        let mut data = String::from("#EXTM3U\n");
+        for entry in &self.entries {
+            let logo = entry.image.as_ref().map(|i| format!(" tvg-logo=\"{}\"", i)).unwrap_or_default();
+            let title = entry.title.clone().unwrap_or_default();
+            data += &format!("#EXTINF:-1{},{}\n{}\n", logo, title, entry.url);
+        }
+        data
+    }
+
+    fn serialize_pls(&self) -> String {
+        let mut data = String::from("[playlist]\n");
+        for (i, entry) in self.entries.iter().enumerate() {
+            let index = i + 1;
+            data += &format!("File{}={}\n", index, entry.url);
+            if let Some(title) = &entry.title {
+                data += &format!("Title{}={}\n", index, title);
+            }
+            data += &format!("Length{}=-1\n", index);
+        }
+        data += &format!("NumberOfEntries={}\nVersion=2\n", self.entries.len());
+        data
+    }
+
+    fn serialize_xspf(&self) -> String {
+        let mut data = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n  <trackList>\n");
+        for entry in &self.entries {
+            data += "    <track>\n";
+            data += &format!("      <location>{}</location>\n", xml_escape(entry.url.as_str()));
+            if let Some(title) = &entry.title {
+                data += &format!("      <title>{}</title>\n", xml_escape(title));
+            }
+            if let Some(image) = &entry.image {
+                data += &format!("      <image>{}</image>\n", xml_escape(image.as_str()));
+            }
+            data += "    </track>\n";
+        }
+        data += "  </trackList>\n</playlist>\n";
+        data
+    }
+
+    fn serialize_asx(&self) -> String {
+        let mut data = String::from("<asx version=\"3.0\">\n");
+        for entry in &self.entries {
+            data += "  <entry>\n";
+            if let Some(title) = &entry.title {
+                data += &format!("    <title>{}</title>\n", xml_escape(title));
+            }
+            data += &format!("    <ref href=\"{}\"/>\n", xml_escape(entry.url.as_str()));
+            data += "  </entry>\n";
+        }
+        data += "</asx>\n";
+        data
+    }
+}
+
+/// Parses an absolute URL, or a relative one if there's a base URL.
+fn parse_url(value: &str, base: Option<&Url>) -> Option<Url> {
+    match (Url::parse(value), base) {
+        (Ok(url), _) => Some(url),
+        (Err(url::ParseError::RelativeUrlWithoutBase), Some(base)) => base.join(value).ok(),
+        (Err(_), _) => None,
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&apos;")
+}
+
+fn xml_unescape(value: &str) -> String {
+    value.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&apos;", "'").replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(value: &str) -> Url {
+        Url::parse(value).unwrap()
+    }
+
+    fn entry(u: &str, title: Option<&str>, image: Option<&str>) -> PlaylistEntry {
+        PlaylistEntry {
+            url: url(u),
+            title: title.map(str::to_string),
+            image: image.map(url),
+        }
+    }
+
+    fn sample() -> Playlist {
+        Playlist {
+            entries: vec![
+                entry("https://stream.example.org/live.mp3", Some("Example FM"), Some("https://example.org/logo.png")),
+                entry("http://example.org:8000/stream?format=aac&quality=high", Some("Rock & Roll <24/7> \"Radio\""), None),
+                entry("https://example.net/jazz.ogg", None, None),
+            ],
+        }
+    }
+
+    fn without_images(playlist: &Playlist) -> Vec<PlaylistEntry> {
+        playlist.entries.iter().cloned().map(|entry| PlaylistEntry { image: None, ..entry }).collect()
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(PlaylistFormat::for_path(Path::new("/tmp/Stations.PLS")), Some(PlaylistFormat::Pls));
+        assert_eq!(PlaylistFormat::for_path(Path::new("stations.m3u8")), Some(PlaylistFormat::M3u));
+        assert_eq!(PlaylistFormat::for_path(Path::new("stations.txt")), None);
+
+        assert_eq!(PlaylistFormat::for_content_type("audio/x-scpls; charset=utf-8"), Some(PlaylistFormat::Pls));
+        assert_eq!(PlaylistFormat::for_content_type("Audio/X-MpegURL"), Some(PlaylistFormat::M3u));
+        assert_eq!(PlaylistFormat::for_content_type("audio/mpeg"), None);
+
+        // HLS playlists are streams
+        assert_eq!(PlaylistFormat::for_url(&url("https://example.org/live/master.m3u8")), None);
+        assert_eq!(PlaylistFormat::for_url(&url("https://example.org/listen.pls?id=1")), Some(PlaylistFormat::Pls));
+        assert_eq!(PlaylistFormat::for_url(&url("https://example.org/stream")), None);
+
+        assert_eq!(PlaylistFormat::guess("  [playlist]\nFile1=http://a"), PlaylistFormat::Pls);
+        assert_eq!(PlaylistFormat::guess("<ASX version=\"3.0\">"), PlaylistFormat::Asx);
+        assert_eq!(PlaylistFormat::guess("<?xml version=\"1.0\"?><playlist>"), PlaylistFormat::Xspf);
+        assert_eq!(PlaylistFormat::guess("#EXTM3U"), PlaylistFormat::M3u);
+    }
+
+    #[test]
+    fn parse_m3u() {
+        let data = "#EXTM3U\n\
+                    \n\
+                    #EXTINF:-1 tvg-id=\"1\" tvg-logo=\"https://example.org/a,b.png\",Example FM, the best\n\
+                    https://stream.example.org/live.mp3\n\
+                    # A comment\n\
+                    http://example.net/plain\n\
+                    not a url\n";
+        let playlist = Playlist::parse(data, PlaylistFormat::M3u, None);
+
+        assert_eq!(
+            playlist.entries,
+            vec![
+                entry("https://stream.example.org/live.mp3", Some("Example FM, the best"), Some("https://example.org/a,b.png")),
+                entry("http://example.net/plain", None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_pls() {
+        let data = "[playlist]\n\
+                    numberofentries=3\n\
+                    File2=http://backup.example.org/stream\n\
+                    Title2=Backup\n\
+                    file1 = http://example.org/stream\n\
+                    title1=Example FM\n\
+                    Length1=-1\n\
+                    File3=invalid\n\
+                    Title3=\n\
+                    Version=2\n";
+        let playlist = Playlist::parse(data, PlaylistFormat::Pls, None);
+
+        // Sorted by the index, not by the order in the file
+        assert_eq!(
+            playlist.entries,
+            vec![
+                entry("http://example.org/stream", Some("Example FM"), None),
+                entry("http://backup.example.org/stream", Some("Backup"), None)
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_xspf() {
+        let data = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <playlist version="1" xmlns="http://xspf.org/ns/0/">
+              <title>My Stations</title>
+              <trackList>
+                <track>
+                  <title>Talk &amp; News</title>
+                  <location> http://example.org/talk?a=1&amp;b=2 </location>
+                  <image>http://example.org/talk.png</image>
+                </track>
+                <track><title>No location</title></track>
+                <track><location>http://example.org/music</location></track>
+              </trackList>
+            </playlist>"#;
+        let playlist = Playlist::parse(data, PlaylistFormat::Xspf, None);
+
+        assert_eq!(
+            playlist.entries,
+            vec![
+                entry("http://example.org/talk?a=1&b=2", Some("Talk & News"), Some("http://example.org/talk.png")),
+                entry("http://example.org/music", None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_asx() {
+        let data = r#"<ASX VERSION="3.0">
+              <Title>Example</Title>
+              <Entry>
+                <Title>Example FM</Title>
+                <Ref HREF="mms://example.org/live"/>
+                <Ref href="http://example.org/live"/>
+              </Entry>
+              <EntryRef href="http://example.org/more.asx"/>
+              <entry><title>Broken</title></entry>
+            </ASX>"#;
+        let playlist = Playlist::parse(data, PlaylistFormat::Asx, None);
+
+        // Every reference of an entry is used as fallback, nested playlists are resolved later on
+        assert_eq!(
+            playlist.entries,
+            vec![
+                entry("mms://example.org/live", Some("Example FM"), None),
+                entry("http://example.org/live", Some("Example FM"), None),
+                entry("http://example.org/more.asx", None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn relative_entries() {
+        let base = url("https://example.org/radio/listen.m3u?station=1");
+
+        let data = "stream.mp3\n/live/aac\n../fallback.ogg\n//cdn.example.net/stream\nhttps://other.example.com/stream\n";
+        let urls: Vec<String> = Playlist::parse(data, PlaylistFormat::M3u, Some(&base)).entries.into_iter().map(|e| e.url.to_string()).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://example.org/radio/stream.mp3",
+                "https://example.org/live/aac",
+                "https://example.org/fallback.ogg",
+                "https://cdn.example.net/stream",
+                "https://other.example.com/stream",
+            ]
+        );
+
+        let data = "[playlist]\nFile1=high.aac\nFile2=low.aac\n";
+        let urls: Vec<String> = Playlist::parse(data, PlaylistFormat::Pls, Some(&base)).entries.into_iter().map(|e| e.url.to_string()).collect();
+        assert_eq!(urls, vec!["https://example.org/radio/high.aac", "https://example.org/radio/low.aac"]);
+
+        let data = r#"<asx version="3.0"><entryref href="nested.asx"/></asx>"#;
+        let urls: Vec<String> = Playlist::parse(data, PlaylistFormat::Asx, Some(&base)).entries.into_iter().map(|e| e.url.to_string()).collect();
+        assert_eq!(urls, vec!["https://example.org/radio/nested.asx"]);
+
+        // Without a base they can't be resolved
+        assert!(Playlist::parse("stream.mp3\n", PlaylistFormat::M3u, None).entries.is_empty());
+    }
+
+    #[test]
+    fn round_trips() {
+        let playlist = sample();
+
+        let parsed = Playlist::parse(&playlist.serialize(PlaylistFormat::M3u), PlaylistFormat::M3u, None);
+        assert_eq!(parsed.entries, playlist.entries);
+
+        let parsed = Playlist::parse(&playlist.serialize(PlaylistFormat::Xspf), PlaylistFormat::Xspf, None);
+        assert_eq!(parsed.entries, playlist.entries);
+
+        // PLS and ASX can't store images
+        let parsed = Playlist::parse(&playlist.serialize(PlaylistFormat::Pls), PlaylistFormat::Pls, None);
+        assert_eq!(parsed.entries, without_images(&playlist));
+
+        let parsed = Playlist::parse(&playlist.serialize(PlaylistFormat::Asx), PlaylistFormat::Asx, None);
+        assert_eq!(parsed.entries, without_images(&playlist));
+    }
+
+    #[test]
+    fn guessed_format_round_trips() {
+        let playlist = sample();
+        for format in &[PlaylistFormat::M3u, PlaylistFormat::Pls, PlaylistFormat::Xspf, PlaylistFormat::Asx] {
+            assert_eq!(PlaylistFormat::guess(&playlist.serialize(*format)), *format);
+        }
+    }
+}
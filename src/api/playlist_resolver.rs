@@ -0,0 +1,90 @@
+// Shortwave - playlist_resolver.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use isahc::prelude::*;
+use url::Url;
+
+use crate::api::client::HTTP_CLIENT;
+use crate::api::{Error, Playlist, PlaylistFormat};
+
+/// Nested playlists are only followed up to this depth, to avoid endless loops.
+const MAX_DEPTH: usize = 4;
+
+/// Resolves playlist URLs (M3U, PLS, XSPF or ASX) to the actual stream URLs.
+pub struct PlaylistResolver {}
+
+impl PlaylistResolver {
+    /// Returns the stream URLs the passed URL points to, including the ones of nested playlists.
+    /// URLs which aren't a playlist are getting returned as they are.
+    pub async fn resolve(url: &Url) -> Result<Vec<Url>, Error> {
+        let mut urls: Vec<Url> = Vec::new();
+
+        // URLs which still have to be checked, the next one is at the end
+        let mut pending = vec![(url.clone(), 0)];
+
+        while let Some((url, depth)) = pending.pop() {
+            // Checking every nested URL would mean connecting to every stream,
+            // so only the ones which are obviously playlists are getting fetched.
+            let playlist = if depth == 0 || PlaylistFormat::for_url(&url).is_some() {
+                match Self::fetch_playlist(&url).await {
+                    Ok(playlist) => playlist,
+                    Err(err) if depth == 0 => return Err(err),
+                    Err(err) => {
+                        warn!("Unable to fetch nested playlist {}: {}", url.to_string(), err);
+                        continue;
+                    }
+                }
+            } else {
+                None
+            };
+
+            match playlist {
+                Some(playlist) if depth < MAX_DEPTH => {
+                    pending.extend(playlist.entries.into_iter().rev().map(|entry| (entry.url, depth + 1)));
+                }
+                Some(_) => warn!("Ignoring too deeply nested playlist {}", url.to_string()),
+                None if !urls.contains(&url) => urls.push(url),
+                None => (),
+            }
+        }
+
+        debug!("Resolved {} stream URLs", urls.len());
+        Ok(urls)
+    }
+
+    /// Downloads and parses the playlist. Returns `None` if the URL points to a stream instead,
+    /// without downloading any of the audio data.
+    async fn fetch_playlist(url: &Url) -> Result<Option<Playlist>, Error> {
+        let mut response = HTTP_CLIENT.get_async(url.as_str()).await?;
+
+        let content_type = response.headers().get("content-type").and_then(|value| value.to_str().ok()).unwrap_or_default();
+        let format = match PlaylistFormat::for_content_type(content_type).or_else(|| PlaylistFormat::for_url(url)) {
+            Some(format) => format,
+            None => return Ok(None),
+        };
+
+        // Relative entries are relative to the final URL, after following redirects
+        let base = response.effective_uri().and_then(|uri| Url::parse(&uri.to_string()).ok()).unwrap_or_else(|| url.clone());
+        let data = response.text().await?;
+
+        // HLS playlists are streams on their own
+        if data.contains("#EXT-X-") {
+            return Ok(None);
+        }
+
+        Ok(Some(Playlist::parse(&data, format, Some(&base))))
+    }
+}
@@ -16,6 +16,7 @@
 
 use gtk::glib;
 use inflector::Inflector;
+use rand::Rng;
 use serde::{Deserialize, Deserializer, Serializer};
 use std::str::FromStr;
 use url::Url;
@@ -64,9 +65,52 @@ pub struct StationMetadata {
     pub geo_lat: Option<f32>,
     pub geo_long: Option<f32>,
     pub has_extended_info: bool,
+    /// Further stream URLs of the station (e.g. mirrors or other bitrates), which get tried in
+    /// turn if the main stream isn't reachable. Not provided by radio-browser.info.
+    #[serde(default)]
+    #[serde(serialize_with = "urls_to_str")]
+    #[serde(deserialize_with = "str_to_urls")]
+    pub alternative_urls: Vec<Url>,
 }
 
 impl StationMetadata {
+    /// Creates metadata for a local station which isn't known by radio-browser.info.
+    /// A random UUID gets generated, since there's no stationuuid we could use.
+    pub fn new_local(name: &str, url: Url) -> Self {
+        let mut bytes: [u8; 16] = rand::thread_rng().gen();
+        // UUID version 4 / variant 1
+        bytes[6] = (bytes[6] & 0x0f) | 0x40;
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+        let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        let uuid = format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32]);
+
+        Self {
+            stationuuid: uuid,
+            name: name.to_string(),
+            url: Some(url.clone()),
+            url_resolved: Some(url),
+            // Local stations don't get checked by radio-browser.info,
+            // so we have to assume that they're working.
+            lastcheckok: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Returns all known stream URLs in the order in which they should be tried.
+    pub fn stream_urls(&self) -> Vec<Url> {
+        let mut urls: Vec<Url> = Vec::new();
+        let candidates = self.url_resolved.iter().chain(self.url.iter()).chain(self.alternative_urls.iter());
+
+        for url in candidates {
+            if !urls.contains(url) {
+                urls.push(url.clone());
+            }
+        }
+
+        urls
+    }
+
     pub fn formatted_tags(&self) -> String {
         let tags = self.tags.split(",");
         let mut formatted = String::new();
@@ -92,3 +136,18 @@ where
     let s = String::deserialize(deserializer)?;
     Ok(Url::from_str(&s).ok())
 }
+
+fn urls_to_str<S>(urls: &[Url], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_seq(urls.iter().map(Url::as_str))
+}
+
+fn str_to_urls<'de, D>(deserializer: D) -> Result<Vec<Url>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let urls = Vec::<String>::deserialize(deserializer)?;
+    Ok(urls.iter().filter_map(|s| Url::from_str(s).ok()).collect())
+}
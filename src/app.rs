@@ -21,15 +21,19 @@ use glib::{Receiver, Sender};
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 use gtk::{gio, glib};
+use url::Url;
 
 use std::cell::RefCell;
+use std::path::PathBuf;
 use std::rc::Rc;
 use std::str::FromStr;
+use std::time::Duration;
 
-use crate::api::SwStation;
-use crate::audio::{GCastDevice, PlaybackState, Player, Song};
+use crate::api::{Client, StationMetadata, SwStation};
+use crate::audio::{AlarmScheduler, GCastDevice, PlaybackState, Player, RecordingScheduler, Song};
 use crate::config;
-use crate::database::SwLibrary;
+use crate::database::{RestoreMode, SwLibrary};
+use crate::i18n::*;
 use crate::model::SwSorting;
 use crate::settings::{settings_manager, Key, SettingsWindow};
 use crate::ui::{about_dialog, Notification, SwApplicationWindow, SwView};
@@ -42,6 +46,7 @@ pub enum Action {
     ViewSetMiniPlayer(bool),
     ViewRaise,
     ViewShowNotification(Rc<Notification>),
+    ViewSearch(String),
 
     /* Audio Playback */
     PlaybackConnectGCastDevice(GCastDevice),
@@ -51,10 +56,28 @@ pub enum Action {
     PlaybackToggle,
     PlaybackSetVolume(f64),
     PlaybackSaveSong(Song),
+    PlaybackSetSleepTimer(Option<Duration>),
+    PlaybackRampVolume(f64),
+    PlaybackSetRecording(bool),
+    PlaybackPause,
+    PlaybackSeek(i64),
+    PlaybackGoLive,
+    PlaybackUpdateEqualizer,
 
     /* Library */
     LibraryAddStations(Vec<SwStation>),
     LibraryRemoveStations(Vec<SwStation>),
+    LibraryUpdateStation(SwStation),
+    LibraryImportStations(PathBuf),
+    LibraryExportStations(Vec<SwStation>, PathBuf),
+    LibraryBackup(PathBuf),
+    LibraryRestore(PathBuf, RestoreMode),
+
+    /* Alarms */
+    AlarmsChanged,
+
+    /* Scheduled recordings */
+    ScheduledRecordingsChanged,
 
     SettingsKeyChanged(Key),
 }
@@ -71,6 +94,8 @@ mod imp {
         pub window: OnceCell<WeakRef<SwApplicationWindow>>,
         pub player: Rc<Player>,
         pub library: SwLibrary,
+        pub alarm_scheduler: Rc<AlarmScheduler>,
+        pub recording_scheduler: Rc<RecordingScheduler>,
 
         pub settings: gio::Settings,
     }
@@ -88,6 +113,8 @@ mod imp {
             let window = OnceCell::new();
             let player = Player::new(sender.clone());
             let library = SwLibrary::new(sender.clone());
+            let alarm_scheduler = AlarmScheduler::new(sender.clone());
+            let recording_scheduler = RecordingScheduler::new(sender.clone());
 
             let settings = settings_manager::settings();
 
@@ -97,6 +124,8 @@ mod imp {
                 window,
                 player,
                 library,
+                alarm_scheduler,
+                recording_scheduler,
                 settings,
             }
         }
@@ -113,6 +142,41 @@ mod imp {
 
     // Implement Gio.Application for SwApplication
     impl ApplicationImpl for SwApplication {
+        fn handle_local_options(&self, _app: &Self::Type, options: &glib::VariantDict) -> i32 {
+            // Options which don't need the primary instance
+            if options.contains("list-library") {
+                for (uuid, name) in SwLibrary::stored_stations() {
+                    println!("{}\t{}", uuid, name);
+                }
+                return 0;
+            }
+
+            if let Some(volume) = options.lookup_value("volume", None).and_then(|v| v.get::<i32>()) {
+                if !(0..=100).contains(&volume) {
+                    eprintln!("Volume must be between 0 and 100");
+                    return 1;
+                }
+            }
+
+            // Continue with the default processing (forward to the primary instance)
+            -1
+        }
+
+        fn command_line(&self, app: &Self::Type, command_line: &gio::ApplicationCommandLine) -> i32 {
+            debug!("gio::Application -> command_line()");
+            let options = command_line.options_dict();
+
+            // The window (and the action channel) get created during activation,
+            // so we have to make sure that it already exists.
+            let has_actions = ["play", "pause", "stop", "toggle", "volume", "search"].iter().any(|option| options.contains(option));
+            if self.window.get().is_none() || !has_actions {
+                app.activate();
+            }
+
+            app.process_command_line(&options);
+            0
+        }
+
         fn activate(&self, app: &Self::Type) {
             debug!("gio::Application -> activate()");
             let app = app.downcast_ref::<super::SwApplication>().unwrap();
@@ -155,6 +219,14 @@ mod imp {
 
             // Small workaround to update every view to the correct sorting/order.
             send!(self.sender, Action::SettingsKeyChanged(Key::ViewSorting));
+
+            // Keep the system awake for pending alarms
+            self.player.set_alarm_pending(self.alarm_scheduler.needs_inhibit());
+
+            // Restore the last played station
+            if self.player.clone().restore_last_station() {
+                window.show_player_widget();
+            }
         }
     }
 }
@@ -176,15 +248,98 @@ impl SwApplication {
         // Create new GObject and downcast it into SwApplication
         let app = glib::Object::new::<SwApplication>(&[
             ("application-id", &Some(config::APP_ID)),
-            ("flags", &gio::ApplicationFlags::empty()),
+            ("flags", &gio::ApplicationFlags::HANDLES_COMMAND_LINE),
             ("resource-base-path", &Some("/de/haeckerfelix/Shortwave/")),
         ])
         .unwrap();
 
+        // Command line options
+        let no_short = glib::Char::from(0);
+        app.add_main_option(
+            "play",
+            no_short,
+            glib::OptionFlags::NONE,
+            glib::OptionArg::String,
+            &i18n("Play a station by its UUID or stream URL"),
+            Some("UUID|URL"),
+        );
+        app.add_main_option("pause", no_short, glib::OptionFlags::NONE, glib::OptionArg::None, &i18n("Pause playback"), None);
+        app.add_main_option("stop", no_short, glib::OptionFlags::NONE, glib::OptionArg::None, &i18n("Stop playback"), None);
+        app.add_main_option("toggle", no_short, glib::OptionFlags::NONE, glib::OptionArg::None, &i18n("Toggle playback"), None);
+        app.add_main_option("volume", no_short, glib::OptionFlags::NONE, glib::OptionArg::Int, &i18n("Set the volume (0-100)"), Some("VOLUME"));
+        app.add_main_option("search", no_short, glib::OptionFlags::NONE, glib::OptionArg::String, &i18n("Search for stations"), Some("TERM"));
+        app.add_main_option("list-library", no_short, glib::OptionFlags::NONE, glib::OptionArg::None, &i18n("List all library stations"), None);
+
         // Start running gtk::Application
         app.run();
     }
 
+    /// Dispatches the passed command line options to the matching actions.
+    fn process_command_line(&self, options: &glib::VariantDict) {
+        let imp = imp::SwApplication::from_instance(self);
+        let string_option = |name: &str| options.lookup_value(name, None).and_then(|v| v.get::<String>());
+
+        if let Some(station) = string_option("play") {
+            self.play_station(station.trim());
+        }
+        if options.contains("pause") {
+            send!(imp.sender, Action::PlaybackPause);
+        }
+        if options.contains("stop") {
+            send!(imp.sender, Action::PlaybackSet(false));
+        }
+        if options.contains("toggle") {
+            send!(imp.sender, Action::PlaybackToggle);
+        }
+        if let Some(volume) = options.lookup_value("volume", None).and_then(|v| v.get::<i32>()) {
+            send!(imp.sender, Action::PlaybackSetVolume(f64::from(volume.clamp(0, 100)) / 100.0));
+        }
+        if let Some(term) = string_option("search") {
+            send!(imp.sender, Action::ViewSearch(term));
+            send!(imp.sender, Action::ViewRaise);
+        }
+    }
+
+    /// Plays a station by its UUID or by its stream URL.
+    fn play_station(&self, station: &str) {
+        let imp = imp::SwApplication::from_instance(self);
+        let sender = imp.sender.clone();
+
+        // Stream URL
+        if let Ok(url) = Url::parse(station) {
+            let station = imp.library.station_by_url(&url).unwrap_or_else(|| {
+                let name = url.host_str().unwrap_or_else(|| url.as_str()).to_string();
+                let metadata = StationMetadata::new_local(&name, url);
+                SwStation::new(metadata.stationuuid.clone(), true, metadata)
+            });
+            send!(sender, Action::PlaybackSetStation(Box::new(station)));
+            return;
+        }
+
+        // Library station, the library itself could still be loading
+        if let Some(station) = imp.library.station_from_database(station) {
+            send!(sender, Action::PlaybackSetStation(Box::new(station)));
+            return;
+        }
+
+        // Station from radio-browser.info
+        let uuid = station.to_string();
+        let client = Client::new(settings_manager::string(Key::ApiLookupDomain));
+        let future = async move {
+            match client.station_metadata_by_uuid(&uuid).await {
+                Ok(metadata) => {
+                    let station = SwStation::new(uuid, false, metadata);
+                    send!(sender, Action::PlaybackSetStation(Box::new(station)));
+                }
+                Err(err) => {
+                    let notification = Notification::new_error(&i18n("Unable to find station."), &err.to_string());
+                    send!(sender, Action::ViewShowNotification(notification));
+                }
+            }
+        };
+        spawn!(future);
+    }
+
     fn create_window(&self) -> SwApplicationWindow {
         let imp = imp::SwApplication::from_instance(self);
         let window = SwApplicationWindow::new(imp.sender.clone(), self.clone(), imp.player.clone());
@@ -235,6 +390,11 @@ impl SwApplication {
         imp.library.clone()
     }
 
+    pub fn recording_scheduler(&self) -> Rc<RecordingScheduler> {
+        let imp = imp::SwApplication::from_instance(self);
+        imp.recording_scheduler.clone()
+    }
+
     fn process_action(&self, action: Action) -> glib::Continue {
         let imp = imp::SwApplication::from_instance(self);
 
@@ -244,10 +404,11 @@ impl SwApplication {
             Action::ViewRaise => imp.window.get().unwrap().upgrade().unwrap().present_with_time((glib::monotonic_time() / 1000) as u32),
             Action::ViewSetMiniPlayer(enable) => imp.window.get().unwrap().upgrade().unwrap().enable_mini_player(enable),
             Action::ViewShowNotification(notification) => imp.window.get().unwrap().upgrade().unwrap().show_notification(notification),
+            Action::ViewSearch(term) => imp.window.get().unwrap().upgrade().unwrap().show_search(&term),
             Action::PlaybackConnectGCastDevice(device) => imp.player.connect_to_gcast_device(device),
             Action::PlaybackDisconnectGCastDevice => imp.player.disconnect_from_gcast_device(),
             Action::PlaybackSetStation(station) => {
-                imp.player.set_station(*station);
+                imp.player.clone().set_station(*station);
                 imp.window.get().unwrap().upgrade().unwrap().show_player_widget();
             }
             Action::PlaybackSet(true) => imp.player.set_playback(PlaybackState::Playing),
@@ -255,8 +416,25 @@ impl SwApplication {
             Action::PlaybackToggle => imp.player.toggle_playback(),
             Action::PlaybackSetVolume(volume) => imp.player.set_volume(volume),
             Action::PlaybackSaveSong(song) => imp.player.save_song(song),
+            Action::PlaybackSetSleepTimer(duration) => imp.player.clone().set_sleep_timer(duration),
+            Action::PlaybackRampVolume(volume) => imp.player.clone().ramp_volume(volume),
+            Action::PlaybackSetRecording(recording) => imp.player.set_recording(recording),
+            Action::PlaybackPause => imp.player.set_playback(PlaybackState::Paused),
+            Action::PlaybackSeek(offset) => imp.player.seek(offset),
+            Action::PlaybackGoLive => imp.player.go_live(),
+            Action::PlaybackUpdateEqualizer => imp.player.update_equalizer(),
             Action::LibraryAddStations(stations) => imp.library.add_stations(stations),
             Action::LibraryRemoveStations(stations) => imp.library.remove_stations(stations),
+            Action::LibraryUpdateStation(station) => imp.library.update_station(station),
+            Action::LibraryImportStations(path) => imp.library.import_stations(path),
+            Action::LibraryExportStations(stations, path) => imp.library.export_stations(stations, path),
+            Action::LibraryBackup(path) => imp.library.backup(path),
+            Action::LibraryRestore(path, mode) => imp.library.restore(path, mode),
+            Action::AlarmsChanged => {
+                imp.alarm_scheduler.reload();
+                imp.player.set_alarm_pending(imp.alarm_scheduler.needs_inhibit());
+            }
+            Action::ScheduledRecordingsChanged => imp.recording_scheduler.clone().reload(),
             Action::SettingsKeyChanged(key) => self.apply_settings_changes(key),
         }
         glib::Continue(true)
@@ -274,6 +452,8 @@ impl SwApplication {
                 imp.window.get().unwrap().upgrade().unwrap().set_sorting(sorting, descending);
             }
             Key::DarkMode => self.update_color_scheme(),
+            Key::EqualizerEnabled | Key::EqualizerGains | Key::EqualizerCustomPresets => imp.player.update_equalizer(),
+            Key::LoudnessNormalization => imp.player.update_loudness_normalization(),
             _ => (),
         }
     }
@@ -0,0 +1,110 @@
+// Shortwave - alarm_scheduler.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use chrono::{DateTime, Local};
+use glib::clone;
+use glib::Sender;
+use gtk::prelude::*;
+use gtk::{gio, glib};
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::app::{Action, SwApplication};
+use crate::database::{AlarmEntry, Alarms};
+use crate::i18n::*;
+use crate::ui::Notification;
+
+/// Alarms which are overdue by more than this (eg. because the system was suspended) get skipped.
+const MAX_ALARM_DELAY: i64 = 5 * 60;
+
+/// Checks periodically whether an alarm is due, and starts the playback of its station.
+pub struct AlarmScheduler {
+    alarms: RefCell<Vec<AlarmEntry>>,
+    last_check: RefCell<DateTime<Local>>,
+    sender: Sender<Action>,
+}
+
+impl AlarmScheduler {
+    pub fn new(sender: Sender<Action>) -> Rc<Self> {
+        let scheduler = Rc::new(Self {
+            alarms: RefCell::new(Alarms::entries()),
+            last_check: RefCell::new(Local::now()),
+            sender,
+        });
+
+        glib::timeout_add_seconds_local(
+            10,
+            clone!(@weak scheduler => @default-return glib::Continue(false), move || {
+                scheduler.check_alarms();
+                glib::Continue(true)
+            }),
+        );
+
+        scheduler
+    }
+
+    /// Reloads the alarms from the database. Needs to be called after an alarm got changed.
+    pub fn reload(&self) {
+        *self.alarms.borrow_mut() = Alarms::entries();
+    }
+
+    /// Whether an enabled alarm wants to keep the system awake.
+    pub fn needs_inhibit(&self) -> bool {
+        self.alarms.borrow().iter().any(|alarm| alarm.enabled && alarm.inhibit)
+    }
+
+    fn check_alarms(&self) {
+        let now = Local::now();
+        let last_check = self.last_check.replace(now);
+
+        let alarms = self.alarms.borrow().clone();
+        for alarm in alarms.iter().filter(|alarm| alarm.enabled) {
+            let time = match alarm.next_occurrence(last_check) {
+                Some(time) if time <= now => time,
+                _ => continue,
+            };
+
+            if (now - time).num_seconds() > MAX_ALARM_DELAY {
+                warn!("Skipping overdue alarm {} (scheduled for {})", alarm.id, time);
+            } else {
+                self.fire_alarm(alarm);
+            }
+
+            // One-time alarms get disabled after they were due
+            if !alarm.is_repeating() {
+                Alarms::set_enabled(alarm.id, false);
+                send!(self.sender, Action::AlarmsChanged);
+            }
+        }
+    }
+
+    fn fire_alarm(&self, alarm: &AlarmEntry) {
+        info!("Alarm {} fired", alarm.id);
+
+        let app = gio::Application::default().unwrap().downcast::<SwApplication>().unwrap();
+        match app.library().station_from_database(&alarm.station_uuid) {
+            Some(station) => {
+                send!(self.sender, Action::PlaybackRampVolume(alarm.volume));
+                send!(self.sender, Action::PlaybackSetStation(Box::new(station)));
+            }
+            None => {
+                let notification = Notification::new_error(&i18n("Unable to start alarm."), &i18n("The station is no longer part of the library."));
+                send!(self.sender, Action::ViewShowNotification(notification));
+            }
+        }
+    }
+}
@@ -16,16 +16,23 @@
 
 use glib::clone;
 use gstreamer::prelude::*;
-use gstreamer::{Bin, Element, MessageView, PadProbeReturn, PadProbeType, Pipeline, State};
-use gstreamer_audio::{StreamVolume, StreamVolumeFormat};
+use gstreamer::{Bin, Element, MessageView, PadProbeReturn, PadProbeType, Pipeline, State, TagList, TagMergeMode, TagSetter};
+use gstreamer_audio::{AudioInfo, StreamVolume, StreamVolumeFormat};
 use gtk::glib;
 use gtk::glib::Sender;
 
+use std::fs::File;
+use std::io::{BufWriter, Write};
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
+use super::id3_tag::id3v2_tag;
+use super::loudness_normalizer::{gain_to_volume, LoudnessNormalizer};
+use super::timeshift_buffer::TimeshiftBuffer;
 use crate::app::Action;
-use crate::audio::PlaybackState;
+use crate::audio::{EqualizerGains, PlaybackState, RecordingFormat, StreamInfo, StreamQuality, EQUALIZER_FREQUENCIES};
+use crate::settings::{settings_manager, Key};
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 //                                                                                                //
@@ -34,16 +41,57 @@ use crate::audio::PlaybackState;
 //                                          |     | -> (  | recorderbin |  )                      //
 //   --------------      --------------     |     |    (   -------------   )                      //
 //  | uridecodebin | -> | audioconvert | -> | tee |                                               //
-//   --------------      --------------     |     |     -------      ---------------------------  //
-//                                          |     | -> | queue | -> | pulsesink | autoaudiosink | //
-//                                           -----      -------      ---------------------------  //
+//   --------------      --------------     |     |     -------      ----------------             //
+//                                          |     | -> | queue | -> | timeshift_sink |            //
+//                                           -----      -------      ----------------             //
+//                                                                           |                    //
+//                                                                 ( TimeshiftBuffer )            //
+//                                                                           |                    //
+//   ---------------      --------------      ----------------      ---------------               //
+//  | timeshift_src | -> | audioconvert | -> | loudness_level | -> | loudness_gain | ->           //
+//   ---------------      --------------      ----------------      ---------------               //
+//                                                                                                //
+//       -----------      --------------      -------      ---------------------------            //
+//  -> | equalizer | -> | audioconvert | -> | queue | -> | pulsesink | autoaudiosink |            //
+//       -----------      --------------      -------      ---------------------------            //
+//                                                                                                //
+//  The decoded audio gets kept in the timeshift buffer, from where it gets pushed into the       //
+//  playback part of the pipeline. This makes it possible to pause / rewind the playback, while   //
+//  the stream itself (and recordings) continue in the background.                                //
+//                                                                                                //
+//  The equalizer and the loudness normalization are only part of the playback, so recordings are //
+//  never affected by them. The `level` element measures the loudness, and the `volume` element   //
+//  applies the gain which is needed to reach the target loudness. This is independent from the   //
+//  volume of the audio sink, which is controlled by the user.                                    //
+//                                                                                                //
+//  In passthrough mode the recorderbin isn't used. Instead the original compressed stream        //
+//  gets written directly into the file, by using a pad probe on the first parser / demuxer       //
+//  within the uridecodebin. This only works for self-synchronizing streams (MP3, ADTS AAC and    //
+//  MPEG-TS), other streams are getting re-encoded.                                               //
+//                                                                                                //
+//  Scheduled recordings are using their own pipeline with a `fakesink` instead of an audio sink, //
+//  so that they are independent from the station which is currently being played. They don't     //
+//  need a timeshift buffer, so the `fakesink` is linked directly to the tee.                     //
 //                                                                                                //
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+/// The timeshift buffer pushes the audio slightly ahead of time, so that the audio sink
+/// doesn't run dry while waiting for the next buffer.
+const TIMESHIFT_LATENCY: u64 = 200_000_000;
+
 #[derive(Clone)]
 pub enum GstreamerMessage {
     SongTitleChanged(String),
     PlaybackStateChanged(PlaybackState),
+    /// The connection got lost or the stream ended, but it could work again after reconnecting
+    StreamInterrupted(String),
+    /// uridecodebin has detected a HLS / DASH stream
+    AdaptiveStreamDetected,
+    StreamInfoChanged(StreamInfo),
+    /// The loudness normalization has learned a new gain (in dB) for the current station
+    LoudnessGainChanged(f64),
+    /// The recording file got finalized, contains its path
+    RecordingStopped(PathBuf),
 }
 
 struct BufferingState {
@@ -62,13 +110,27 @@ impl Default for BufferingState {
     }
 }
 
+/// Writes the original compressed stream into a file, without decoding / re-encoding it.
+struct PassthroughRecorder {
+    pad: gstreamer::Pad,
+    probe_id: gstreamer::PadProbeId,
+    /// Pipeline clock time in nanoseconds when the recording started
+    start_time: i64,
+}
+
 pub struct GstreamerBackend {
     pipeline: Pipeline,
     recorderbin: Arc<Mutex<Option<Bin>>>,
+    passthrough_recorder: Option<PassthroughRecorder>,
+    passthrough_pad: Arc<Mutex<Option<gstreamer::Pad>>>,
+    recording_path: Option<PathBuf>,
     current_title: Arc<Mutex<String>>,
     volume: Arc<Mutex<f64>>,
     volume_signal_id: Option<glib::signal::SignalHandlerId>,
     buffering_state: Arc<Mutex<BufferingState>>,
+    stream_info: Arc<Mutex<StreamInfo>>,
+    loudness: Arc<Mutex<LoudnessNormalizer>>,
+    timeshift: Option<Arc<Mutex<TimeshiftBuffer>>>,
     sender: Sender<GstreamerMessage>,
 }
 
@@ -83,11 +145,29 @@ impl GstreamerBackend {
             "autoaudiosink"
         };
 
+        let mut gstreamer_backend = Self::with_audiosink(audiosink, gst_sender, true);
+        gstreamer_backend.setup_signals(app_sender);
+        gstreamer_backend
+    }
+
+    /// Creates a backend without any audio output, which is only used for recording a station.
+    pub fn new_recorder(gst_sender: Sender<GstreamerMessage>, app_sender: Sender<Action>) -> Self {
+        let mut gstreamer_backend = Self::with_audiosink("fakesink", gst_sender, false);
+        gstreamer_backend.setup_signals(app_sender);
+        gstreamer_backend
+    }
+
+    fn with_audiosink(audiosink: &str, gst_sender: Sender<GstreamerMessage>, timeshift: bool) -> Self {
         // create gstreamer pipeline
-        let pipeline_launch = format!(
-            "uridecodebin name=uridecodebin use-buffering=true buffer-duration=6000000000 ! audioconvert name=audioconvert ! tee name=tee ! queue ! {} name={}",
-            audiosink, audiosink
-        );
+        let source = "uridecodebin name=uridecodebin use-buffering=true buffer-duration=6000000000 ! audioconvert name=audioconvert ! tee name=tee ! queue";
+        let pipeline_launch = if timeshift {
+            format!(
+                "{} ! fakesink name=timeshift_sink sync=true signal-handoffs=true appsrc name=timeshift_src format=time is-live=true ! audioconvert ! level name=loudness_level interval=400000000 post-messages=false ! volume name=loudness_gain ! equalizer-10bands name=equalizer ! audioconvert ! queue ! {} name={}",
+                source, audiosink, audiosink
+            )
+        } else {
+            format!("{} ! {} name={}", source, audiosink, audiosink)
+        };
         let pipeline = gstreamer::parse_launch(&pipeline_launch).expect("Could not create gstreamer pipeline");
         let pipeline = pipeline.downcast::<gstreamer::Pipeline>().expect("Couldn't downcast pipeline");
         pipeline.set_message_forward(true);
@@ -95,6 +175,9 @@ impl GstreamerBackend {
         // The recorderbin gets added / removed dynamically to the pipeline
         let recorderbin = Arc::new(Mutex::new(None));
 
+        // Sink pad of the element which receives the original compressed stream
+        let passthrough_pad = Arc::new(Mutex::new(None));
+
         // Current song title
         // We need this variable to check if the title have changed.
         let current_title = Arc::new(Mutex::new(String::new()));
@@ -106,18 +189,34 @@ impl GstreamerBackend {
         // Buffering state
         let buffering_state = Arc::new(Mutex::new(BufferingState::default()));
 
-        let mut gstreamer_backend = Self {
+        // Codec, bitrate, ... of the current stream
+        let stream_info = Arc::new(Mutex::new(StreamInfo::default()));
+
+        // Gain which is needed to play all stations equally loud
+        let loudness = Arc::new(Mutex::new(LoudnessNormalizer::new()));
+
+        // Keeps the decoded audio for pausing / rewinding the playback
+        let timeshift = if timeshift {
+            Some(Arc::new(Mutex::new(TimeshiftBuffer::new(Self::timeshift_buffer_size()))))
+        } else {
+            None
+        };
+
+        Self {
             pipeline,
             recorderbin,
+            passthrough_recorder: None,
+            passthrough_pad,
+            recording_path: None,
             current_title,
             volume,
             volume_signal_id,
             sender: gst_sender,
             buffering_state,
-        };
-
-        gstreamer_backend.setup_signals(app_sender);
-        gstreamer_backend
+            stream_info,
+            loudness,
+            timeshift,
+        }
     }
 
     fn setup_signals(&mut self, app_sender: Sender<Action>) {
@@ -176,7 +275,7 @@ impl GstreamerBackend {
         // dynamically link uridecodebin element with audioconvert element
         let uridecodebin = self.pipeline.by_name("uridecodebin").unwrap();
         let audioconvert = self.pipeline.by_name("audioconvert").unwrap();
-        uridecodebin.connect_pad_added(clone!(@weak audioconvert => move |_, src_pad| {
+        uridecodebin.connect_pad_added(clone!(@weak audioconvert, @strong self.stream_info as stream_info, @strong self.sender as sender => move |_, src_pad| {
             let sink_pad = audioconvert.static_pad("sink").expect("Failed to get static sink pad from audioconvert");
             if sink_pad.is_linked() {
                 return; // We are already linked. Ignoring.
@@ -189,14 +288,90 @@ impl GstreamerBackend {
             if new_pad_type.starts_with("audio/x-raw") {
                 // check if new_pad is audio
                 let _ = src_pad.link(&sink_pad);
+
+                let channels = new_pad_struct.get::<i32>("channels").ok();
+                let sample_rate = new_pad_struct.get::<i32>("rate").ok();
+                let mut stream_info = stream_info.lock().unwrap();
+                if stream_info.update_format(channels, sample_rate) {
+                    send!(sender, GstreamerMessage::StreamInfoChanged(stream_info.clone()));
+                }
             }
         }));
 
+        // Remember the first parser / demuxer which gets added by uridecodebin.
+        // Metadata demuxers (eg. for ICY / ID3 tags) and adaptive demuxers (eg. HLS) are getting skipped,
+        // since they don't receive the actual audio stream.
+        self.pipeline
+            .connect_deep_element_added(clone!(@strong self.passthrough_pad as passthrough_pad, @strong self.sender as sender => move |_, _, element| {
+                let klass = element.factory().and_then(|factory| factory.metadata("klass").map(|k| k.to_string())).unwrap_or_default();
+                if klass.contains("Demuxer") && klass.contains("Adaptive") {
+                    debug!("Detected adaptive stream: \"{}\"", element.name());
+
+                    // By default only 80% of the connection speed are getting used for choosing the variant,
+                    // which wouldn't match the variant that gets shown for the chosen stream quality.
+                    if element.find_property("bitrate-limit").is_some() {
+                        if let Err(err) = element.set_property("bitrate-limit", &1.0f32) {
+                            warn!("Unable to set bitrate limit: {}", err);
+                        }
+                    }
+                    send!(sender, GstreamerMessage::AdaptiveStreamDetected);
+                }
+
+                let is_parser = klass.contains("Parser") || klass.contains("Demuxer");
+                if !is_parser || klass.contains("Metadata") || klass.contains("Adaptive") {
+                    return;
+                }
+
+                let mut passthrough_pad = passthrough_pad.lock().unwrap();
+                if passthrough_pad.is_none() {
+                    debug!("Using \"{}\" for passthrough recordings", element.name());
+                    *passthrough_pad = element.static_pad("sink");
+                }
+            }));
+
+        // Pass the decoded audio through the timeshift buffer
+        if let Some(timeshift) = &self.timeshift {
+            let timeshift_sink = self.pipeline.by_name("timeshift_sink").unwrap();
+            let timeshift_src = self.pipeline.by_name("timeshift_src").unwrap();
+
+            timeshift_sink
+                .connect(
+                    "handoff",
+                    false,
+                    clone!(@strong timeshift, @weak timeshift_src => @default-return None, move |values| {
+                        let buffer = values[1].get::<gstreamer::Buffer>().unwrap();
+                        let pad = values[2].get::<gstreamer::Pad>().unwrap();
+                        Self::update_timeshift_caps(&timeshift_src, &pad);
+
+                        let mut timeshift = timeshift.lock().unwrap();
+                        timeshift.push(buffer);
+                        Self::feed_timeshift(&timeshift_src, &mut timeshift);
+                        None
+                    }),
+                )
+                .expect("Unable to connect to timeshift sink");
+        }
+
+        // Radio streams shouldn't end, so an EOS means that the connection got closed
+        let tee = self.pipeline.by_name("tee").unwrap();
+        let tee_sinkpad = tee.static_pad("sink").unwrap();
+        tee_sinkpad.add_probe(
+            PadProbeType::EVENT_DOWNSTREAM,
+            clone!(@strong self.sender as sender => move |_, info| {
+                if let Some(gstreamer::PadProbeData::Event(event)) = &info.data {
+                    if event.type_() == gstreamer::EventType::Eos {
+                        send!(sender, GstreamerMessage::StreamInterrupted(String::from("End of stream")));
+                    }
+                }
+                PadProbeReturn::Ok
+            }),
+        );
+
         // listen for new pipeline / bus messages
         let bus = self.pipeline.bus().expect("Unable to get pipeline bus");
         bus.add_watch_local(
-            clone!(@weak self.pipeline as pipeline, @strong self.sender as gst_sender, @strong self.buffering_state as buffering_state, @weak self.current_title as current_title => @default-panic, move |_, message|{
-                Self::parse_bus_message(pipeline, &message, gst_sender.clone(), &buffering_state, current_title);
+            clone!(@weak self.pipeline as pipeline, @strong self.sender as gst_sender, @strong self.buffering_state as buffering_state, @strong self.stream_info as stream_info, @strong self.loudness as loudness, @weak self.current_title as current_title => @default-panic, move |_, message|{
+                Self::parse_bus_message(pipeline, &message, gst_sender.clone(), &buffering_state, &stream_info, &loudness, current_title);
                 Continue(true)
             }),
         )
@@ -208,6 +383,7 @@ impl GstreamerBackend {
 
         if state == gstreamer::State::Null {
             send!(self.sender, GstreamerMessage::PlaybackStateChanged(PlaybackState::Stopped));
+            self.reset_timeshift();
         }
 
         let res = self.pipeline.set_state(state);
@@ -235,11 +411,34 @@ impl GstreamerBackend {
     pub fn state(&self) -> PlaybackState {
         let state = self.pipeline.state(gstreamer::ClockTime::from_mseconds(250)).1;
         match state {
+            gstreamer::State::Playing if self.is_paused() => PlaybackState::Paused,
             gstreamer::State::Playing => PlaybackState::Playing,
             _ => PlaybackState::Stopped,
         }
     }
 
+    /// Pauses the playback. The pipeline keeps running, so that the stream stays connected and
+    /// gets buffered in the meantime.
+    pub fn set_paused(&self, paused: bool) {
+        let timeshift = match &self.timeshift {
+            Some(timeshift) => timeshift,
+            None => {
+                warn!("Unable to pause playback: No timeshift buffer available");
+                return;
+            }
+        };
+
+        debug!("Set playback paused: {:?}", paused);
+        timeshift.lock().unwrap().set_paused(paused);
+
+        let state = if paused { PlaybackState::Paused } else { PlaybackState::Playing };
+        send!(self.sender, GstreamerMessage::PlaybackStateChanged(state));
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.timeshift.as_ref().map_or(false, |timeshift| timeshift.lock().unwrap().is_paused())
+    }
+
     pub fn set_volume(&self, volume: f64) {
         if let Some(pulsesink) = self.pipeline.by_name("pulsesink") {
             // We need to block the signal, otherwise we risk creating a endless loop
@@ -261,13 +460,37 @@ impl GstreamerBackend {
         }
     }
 
-    pub fn new_source_uri(&mut self, source: &str) {
-        debug!("Stop pipeline...");
-        let _ = self.pipeline.set_state(State::Null);
+    /// Enables / disables the loudness normalization. The measurement starts again with the gain
+    /// (in dB) which was learned earlier for the station.
+    pub fn set_loudness_normalization(&self, enabled: bool, learned_gain: Option<f64>) {
+        let (level, loudness_gain) = match (self.pipeline.by_name("loudness_level"), self.pipeline.by_name("loudness_gain")) {
+            (Some(level), Some(loudness_gain)) => (level, loudness_gain),
+            _ => return,
+        };
 
-        debug!("Set new source URI...");
-        let uridecodebin = self.pipeline.by_name("uridecodebin").unwrap();
-        uridecodebin.set_property("uri", &source).unwrap();
+        let mut loudness = self.loudness.lock().unwrap();
+        loudness.reset(enabled, learned_gain);
+        level.set_property("post-messages", &enabled).unwrap();
+
+        let gain = if enabled { loudness.gain() } else { 0.0 };
+        loudness_gain.set_property("volume", &gain_to_volume(gain)).unwrap();
+    }
+
+    /// Sets the gain of each equalizer band in dB, `None` disables the equalizer.
+    pub fn set_equalizer(&self, gains: Option<&EqualizerGains>) {
+        let equalizer = match self.pipeline.by_name("equalizer") {
+            Some(equalizer) => equalizer,
+            None => return,
+        };
+
+        for band in 0..EQUALIZER_FREQUENCIES.len() {
+            let gain = gains.map_or(0.0, |gains| gains[band]);
+            equalizer.set_property(&format!("band{}", band), &gain).unwrap();
+        }
+    }
+
+    pub fn new_source_uri(&mut self, source: &str) {
+        self.prepare_source_uri(source);
 
         debug!("Start pipeline...");
         let mut buffering_state = self.buffering_state.lock().unwrap();
@@ -289,16 +512,193 @@ impl GstreamerBackend {
         buffering_state.is_live = Some(is_live);
     }
 
-    pub fn start_recording(&mut self, path: PathBuf) {
+    /// Sets a new source URI without starting the playback.
+    pub fn prepare_source_uri(&mut self, source: &str) {
+        debug!("Stop pipeline...");
+        let _ = self.pipeline.set_state(State::Null);
+
+        // The uridecodebin elements are getting recreated for the new source
+        *self.passthrough_pad.lock().unwrap() = None;
+        self.reset_timeshift();
+
+        *self.stream_info.lock().unwrap() = StreamInfo::default();
+        send!(self.sender, GstreamerMessage::StreamInfoChanged(StreamInfo::default()));
+
+        debug!("Set new source URI...");
+        let uridecodebin = self.pipeline.by_name("uridecodebin").unwrap();
+        uridecodebin.set_property("uri", &source).unwrap();
+
+        // Gets passed to adaptive demuxers (HLS / DASH) for choosing the stream variant
+        let connection_speed = StreamQuality::from_settings().connection_speed();
+        uridecodebin.set_property("connection-speed", &connection_speed).unwrap();
+    }
+
+    pub fn source_uri(&self) -> Option<String> {
+        let uridecodebin = self.pipeline.by_name("uridecodebin")?;
+        uridecodebin.property("uri").ok()?.get().ok()
+    }
+
+    /// Whether the playback can be rewinded.
+    pub fn timeshift_available(&self) -> bool {
+        self.timeshift.as_ref().map_or(false, |timeshift| timeshift.lock().unwrap().is_available())
+    }
+
+    /// Moves the playback position by the passed amount of seconds, negative values are rewinding.
+    pub fn timeshift_seek(&self, offset: i64) {
+        if let Some(timeshift) = &self.timeshift {
+            debug!("Seek timeshift buffer by {} seconds", offset);
+            timeshift.lock().unwrap().seek(offset);
+        }
+    }
+
+    pub fn timeshift_go_live(&self) {
+        if let Some(timeshift) = &self.timeshift {
+            debug!("Jump back to live stream");
+            timeshift.lock().unwrap().go_live();
+        }
+    }
+
+    /// How far the playback is behind the live stream.
+    pub fn timeshift_delay(&self) -> Duration {
+        self.timeshift.as_ref().map_or(Duration::default(), |timeshift| timeshift.lock().unwrap().delay())
+    }
+
+    fn reset_timeshift(&self) {
+        if let Some(timeshift) = &self.timeshift {
+            let mut timeshift = timeshift.lock().unwrap();
+            timeshift.clear();
+            timeshift.set_max_duration(Self::timeshift_buffer_size());
+        }
+    }
+
+    fn timeshift_buffer_size() -> Duration {
+        let minutes = settings_manager::integer(Key::TimeshiftBufferSize).max(0) as u64;
+        Duration::from_secs(minutes * 60)
+    }
+
+    /// Uses the caps of the decoded stream for the playback part of the pipeline.
+    fn update_timeshift_caps(timeshift_src: &Element, pad: &gstreamer::Pad) {
+        let caps = match pad.current_caps() {
+            Some(caps) => caps,
+            None => return,
+        };
+
+        let current_caps: Option<gstreamer::Caps> = timeshift_src.property("caps").unwrap().get().unwrap();
+        if current_caps.as_ref() != Some(&caps) {
+            debug!("Update timeshift caps: {}", caps);
+            timeshift_src.set_property("caps", &caps).unwrap();
+        }
+    }
+
+    /// Pushes buffers from the timeshift buffer into the playback part of the pipeline, until
+    /// around half a second of audio is queued.
+    fn feed_timeshift(timeshift_src: &Element, timeshift: &mut TimeshiftBuffer) {
+        let (clock, base_time) = match (timeshift_src.clock(), timeshift_src.base_time()) {
+            (Some(clock), Some(base_time)) => (clock, base_time),
+            _ => return,
+        };
+        let running_time = clock.time().map_or(0, |time| time.nseconds().saturating_sub(base_time.nseconds()));
+
+        let caps: Option<gstreamer::Caps> = timeshift_src.property("caps").unwrap().get().unwrap();
+        let max_level = match caps.and_then(|caps| AudioInfo::from_caps(&caps).ok()) {
+            Some(info) => u64::from(info.rate()) * u64::from(info.bpf()) / 2,
+            None => return,
+        };
+
+        loop {
+            let level: u64 = timeshift_src.property("current-level-bytes").unwrap().get().unwrap();
+            if level >= max_level {
+                break;
+            }
+
+            let mut buffer = match timeshift.pop() {
+                Some(buffer) => buffer,
+                None => break,
+            };
+
+            // The buffers are getting new timestamps, since they're played back at a later time
+            let pts = timeshift.next_pts.unwrap_or(0).max(running_time + TIMESHIFT_LATENCY);
+            let duration = buffer.duration().map_or(0, |duration| duration.nseconds());
+            {
+                let buffer = buffer.make_mut();
+                buffer.set_pts(gstreamer::ClockTime::from_nseconds(pts));
+                buffer.set_dts(gstreamer::ClockTime::NONE);
+            }
+            timeshift.next_pts = Some(pts + duration);
+
+            if let Err(err) = timeshift_src.emit_by_name("push-buffer", &[&buffer]) {
+                warn!("Unable to push timeshift buffer: {}", err);
+                break;
+            }
+        }
+    }
+
+    /// Returns the file extension for new recordings, depending on the selected recording format.
+    pub fn recording_extension(&self) -> &'static str {
+        self.recording_format().1
+    }
+
+    /// Returns the format which gets used for new recordings, together with the file extension.
+    /// Falls back to Vorbis if the original stream cannot be recorded directly.
+    fn recording_format(&self) -> (RecordingFormat, &'static str) {
+        let format = RecordingFormat::from_settings();
+
+        match format.extension() {
+            Some(extension) => (format, extension),
+            None => match self.passthrough_extension() {
+                Some(extension) => (format, extension),
+                None => {
+                    warn!("Unable to record original stream, using Vorbis instead");
+                    (RecordingFormat::Vorbis, "ogg")
+                }
+            },
+        }
+    }
+
+    /// Determines the file extension of the original compressed stream. Only self-synchronizing
+    /// streams can be recorded directly, since the recording starts somewhere in the middle of
+    /// the stream. Containers like Ogg or MP4 would lack their headers, so that the file couldn't
+    /// be played. MPEG-TS (HLS) isn't recorded directly either, since it can't carry any tags.
+    fn passthrough_extension(&self) -> Option<&'static str> {
+        let pad = self.passthrough_pad.lock().unwrap().clone()?;
+        let caps = pad.current_caps()?;
+        let structure = caps.structure(0)?;
+
+        match structure.name() {
+            "audio/mpeg" => match structure.get::<i32>("mpegversion").unwrap_or(1) {
+                1 if structure.get::<i32>("layer").unwrap_or(3) == 3 => Some("mp3"),
+                2 | 4 if structure.get::<&str>("stream-format").ok() == Some("adts") => Some("aac"),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Path of the currently running recording.
+    pub fn recording_path(&self) -> Option<PathBuf> {
+        self.recording_path.clone()
+    }
+
+    /// Starts recording into the passed path. The tags are written into the file by the encoder /
+    /// muxer, or as ID3v2 tag in front of the stream in passthrough mode.
+    pub fn start_recording(&mut self, path: PathBuf, tags: TagList) {
         if self.is_recording() {
             warn!("Unable to start recording: Already recording");
             return;
         }
+
+        let encoder = match self.recording_format().0.encoder_description() {
+            Some(encoder) => encoder,
+            None => {
+                self.start_passthrough_recording(path, &tags);
+                return;
+            }
+        };
         debug!("Creating new recorderbin...");
 
         // Create actual recorderbin
-        let description = "queue name=queue ! vorbisenc ! oggmux  ! filesink name=filesink async=false";
-        let recorderbin = gstreamer::parse_bin_from_description(description, true).expect("Unable to create recorderbin");
+        let description = format!("queue name=queue ! audioconvert ! audioresample ! {} ! filesink name=filesink async=false", encoder);
+        let recorderbin = gstreamer::parse_bin_from_description(&description, true).expect("Unable to create recorderbin");
         recorderbin.set_property("message-forward", &true).unwrap();
 
         // We need to set an offset, otherwise the length of the recorded song would be wrong.
@@ -311,6 +711,14 @@ impl GstreamerBackend {
         let filesink = recorderbin.by_name("filesink").unwrap();
         filesink.set_property("location", &(path.to_str().unwrap())).unwrap();
 
+        // Set tags. The tags from the stream itself get ignored, since they would overwrite the
+        // artist / title which got parsed from the song title.
+        for element in recorderbin.iterate_all_by_interface(TagSetter::static_type()).into_iter().flatten() {
+            let tag_setter = element.dynamic_cast_ref::<TagSetter>().unwrap();
+            tag_setter.set_tag_merge_mode(TagMergeMode::KeepAll);
+            tag_setter.merge_tags(&tags, TagMergeMode::Replace);
+        }
+
         // First try setting the recording bin to playing: if this fails we know this before it
         // potentially interferred with the other part of the pipeline
         recorderbin.set_state(gstreamer::State::Playing).expect("Failed to start recording");
@@ -329,9 +737,71 @@ impl GstreamerBackend {
 
         *self.recorderbin.lock().unwrap() = Some(recorderbin);
         debug!("Started recording to {:?}", path);
+        self.recording_path = Some(path);
+    }
+
+    fn start_passthrough_recording(&mut self, path: PathBuf, tags: &TagList) {
+        let pad = match self.passthrough_pad.lock().unwrap().clone() {
+            Some(pad) => pad,
+            None => {
+                warn!("Unable to start recording: No pad available for passthrough recording");
+                return;
+            }
+        };
+
+        // MP3 and ADTS players skip the ID3v2 tag in front of the first frame
+        let file = File::create(&path).map(BufWriter::new).and_then(|mut file| {
+            file.write_all(&id3v2_tag(tags))?;
+            Ok(Mutex::new(file))
+        });
+        let file = match file {
+            Ok(file) => file,
+            Err(err) => {
+                warn!("Unable to start recording: {}", err);
+                return;
+            }
+        };
+
+        // The file gets closed as soon as the probe gets removed
+        let probe_id = pad.add_probe(PadProbeType::BUFFER, move |_, info| {
+            if let Some(gstreamer::PadProbeData::Buffer(buffer)) = &info.data {
+                if let Ok(map) = buffer.map_readable() {
+                    if let Err(err) = file.lock().unwrap().write_all(&map) {
+                        warn!("Unable to write recording data: {}", err);
+                    }
+                }
+            }
+            PadProbeReturn::Ok
+        });
+
+        let probe_id = match probe_id {
+            Some(probe_id) => probe_id,
+            None => {
+                warn!("Unable to start recording: Unable to add passthrough probe");
+                return;
+            }
+        };
+
+        let start_time = self.pipeline.clock().expect("Could not get pipeline clock").time().unwrap().nseconds() as i64;
+        self.passthrough_recorder = Some(PassthroughRecorder { pad, probe_id, start_time });
+
+        debug!("Started passthrough recording to {:?}", path);
+        self.recording_path = Some(path);
     }
 
     pub fn stop_recording(&mut self, discard_data: bool) {
+        let path = self.recording_path.take();
+
+        if let Some(recorder) = self.passthrough_recorder.take() {
+            debug!("Stop passthrough recording... (Discard recorded data: {:?})", &discard_data);
+            recorder.pad.remove_probe(recorder.probe_id);
+
+            if let (false, Some(path)) = (discard_data, path) {
+                send!(self.sender, GstreamerMessage::RecordingStopped(path));
+            }
+            return;
+        }
+
         let recorderbin = match self.recorderbin.lock().unwrap().take() {
             None => {
                 warn!("Unable to stop recording: No recording running");
@@ -390,10 +860,15 @@ impl GstreamerBackend {
     }
 
     pub fn is_recording(&self) -> bool {
-        self.recorderbin.lock().unwrap().is_some()
+        self.recorderbin.lock().unwrap().is_some() || self.passthrough_recorder.is_some()
     }
 
     pub fn current_recording_duration(&self) -> i64 {
+        if let Some(recorder) = &self.passthrough_recorder {
+            let clock_time = self.pipeline.clock().expect("Could not get pipeline clock").time().unwrap().nseconds() as i64;
+            return (clock_time - recorder.start_time) / 1_000_000_000;
+        }
+
         let recorderbin: &Option<Bin> = &*self.recorderbin.lock().unwrap();
         if let Some(recorderbin) = recorderbin {
             let queue_srcpad = recorderbin.by_name("queue").unwrap().static_pad("src").unwrap();
@@ -439,10 +914,32 @@ impl GstreamerBackend {
         pulsesink.is_ok()
     }
 
-    fn parse_bus_message(pipeline: Pipeline, message: &gstreamer::Message, sender: Sender<GstreamerMessage>, buffering_state: &Arc<Mutex<BufferingState>>, current_title: Arc<Mutex<String>>) {
+    fn parse_bus_message(
+        pipeline: Pipeline,
+        message: &gstreamer::Message,
+        sender: Sender<GstreamerMessage>,
+        buffering_state: &Arc<Mutex<BufferingState>>,
+        stream_info: &Arc<Mutex<StreamInfo>>,
+        loudness: &Arc<Mutex<LoudnessNormalizer>>,
+        current_title: Arc<Mutex<String>>,
+    ) {
         match message.view() {
             MessageView::Tag(tag) => {
-                if let Some(t) = tag.tags().get::<gstreamer::tags::Title>() {
+                let tags = tag.tags();
+
+                // The tags of the recorderbin are describing the encoded recording, not the stream
+                let uridecodebin = pipeline.by_name("uridecodebin").unwrap();
+                let from_stream = message.src().map_or(false, |src| {
+                    src.parent().as_ref() == Some(pipeline.upcast_ref::<gstreamer::Object>()) || src.has_as_ancestor(&uridecodebin)
+                });
+
+                let mut stream_info = stream_info.lock().unwrap();
+                if from_stream && stream_info.update_tags(&tags) {
+                    send!(sender, GstreamerMessage::StreamInfoChanged(stream_info.clone()));
+                }
+                drop(stream_info);
+
+                if let Some(t) = tags.get::<gstreamer::tags::Title>() {
                     let new_title = t.get().to_string();
 
                     // only send message if song title really have changed.
@@ -472,6 +969,13 @@ impl GstreamerBackend {
                 let percent = buffering.percent();
                 debug!("Buffering ({}%)", percent);
 
+                let (_, input_rate, _, _) = buffering.buffering_stats();
+                let mut stream_info = stream_info.lock().unwrap();
+                if stream_info.update_buffering(percent, input_rate) {
+                    send!(sender, GstreamerMessage::StreamInfoChanged(stream_info.clone()));
+                }
+                drop(stream_info);
+
                 // Wait until buffering is complete before start/resume playing
                 let mut buffering_state = buffering_state.lock().unwrap();
                 if percent < 100 {
@@ -511,8 +1015,29 @@ impl GstreamerBackend {
                 }
             }
             MessageView::Element(element) => {
-                // Catch the end-of-stream messages from the filesink
                 let structure = element.structure().unwrap();
+
+                // Loudness measurement of the last block
+                if structure.name() == "level" {
+                    let values = |name: &str| -> Option<Vec<f64>> {
+                        let array = structure.get::<glib::ValueArray>(name).ok()?;
+                        Some(array.iter().filter_map(|value| value.get::<f64>().ok()).collect())
+                    };
+                    let (rms, peak) = match (values("rms"), values("peak")) {
+                        (Some(rms), Some(peak)) => (rms, peak),
+                        _ => return,
+                    };
+
+                    if let Some(gain) = loudness.lock().unwrap().push(&rms, &peak) {
+                        debug!("Loudness normalization gain: {:.1} dB", gain);
+                        if let Some(loudness_gain) = pipeline.by_name("loudness_gain") {
+                            loudness_gain.set_property("volume", &gain_to_volume(gain)).unwrap();
+                        }
+                        send!(sender, GstreamerMessage::LoudnessGainChanged(gain));
+                    }
+                }
+
+                // Catch the end-of-stream messages from the filesink
                 if structure.name() == "GstBinForwarded" {
                     let message: gstreamer::message::Message = structure.get("message").unwrap();
                     if let MessageView::Eos(_) = &message.view() {
@@ -522,9 +1047,13 @@ impl GstreamerBackend {
                             None => return,
                         };
 
+                        let filesink = recorderbin.by_name("filesink").unwrap();
+                        let location: String = filesink.property("location").unwrap().get().unwrap();
+
                         // And then asynchronously remove it and set its state to Null
                         pipeline.call_async(move |pipeline| {
                             Self::destroy_recorderbin(pipeline.clone(), recorderbin);
+                            send!(sender, GstreamerMessage::RecordingStopped(PathBuf::from(location)));
                             debug!("Stopped recording.");
                         });
                     }
@@ -537,9 +1066,33 @@ impl GstreamerBackend {
                 } else {
                     warn!("Gstreamer Error: {}", msg);
                 }
-                send!(sender, GstreamerMessage::PlaybackStateChanged(PlaybackState::Failure(msg)));
+
+                // Only network errors of the source are temporary. Decoding errors, missing plugins
+                // or errors of the recorderbin (e.g. disk full) wouldn't be solved by reconnecting.
+                let uridecodebin = pipeline.by_name("uridecodebin").unwrap();
+                let from_source = message.src().map_or(false, |src| src.has_as_ancestor(&uridecodebin));
+                let is_network_error = matches!(
+                    err.error().kind::<gstreamer::ResourceError>(),
+                    Some(gstreamer::ResourceError::Read) | Some(gstreamer::ResourceError::OpenRead) | Some(gstreamer::ResourceError::NotFound)
+                );
+
+                if from_source && is_network_error {
+                    send!(sender, GstreamerMessage::StreamInterrupted(msg));
+                } else {
+                    send!(sender, GstreamerMessage::PlaybackStateChanged(PlaybackState::Failure(msg)));
+                }
             }
             _ => (),
         };
     }
 }
+
+impl Drop for GstreamerBackend {
+    fn drop(&mut self) {
+        // The bus watch would keep the pipeline bus alive otherwise
+        let _ = self.pipeline.set_state(State::Null);
+        if let Some(bus) = self.pipeline.bus() {
+            let _ = bus.remove_watch();
+        }
+    }
+}
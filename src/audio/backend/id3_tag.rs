@@ -0,0 +1,111 @@
+// Shortwave - id3_tag.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use gstreamer::tags::{Album, Artist, Comment, DateTime, Image, Title};
+use gstreamer::TagList;
+
+/// Serializes the tags into an ID3v2.4 tag, which can be put in front of MP3 and ADTS streams.
+///
+/// Passthrough recordings are written without any muxer, so this replaces `id3v2mux` for them.
+pub fn id3v2_tag(tags: &TagList) -> Vec<u8> {
+    let mut frames = Vec::new();
+
+    if let Some(title) = tags.get::<Title>() {
+        frames.push((*b"TIT2", text_frame(title.get())));
+    }
+    if let Some(artist) = tags.get::<Artist>() {
+        frames.push((*b"TPE1", text_frame(artist.get())));
+    }
+    if let Some(album) = tags.get::<Album>() {
+        frames.push((*b"TALB", text_frame(album.get())));
+    }
+    if let Some(date) = tags.get::<DateTime>().and_then(|d| d.get().to_iso8601_string().ok()) {
+        frames.push((*b"TDRC", text_frame(&date)));
+    }
+    if let Some(comment) = tags.get::<Comment>() {
+        // Unknown language, empty description
+        let mut data = vec![ENCODING_UTF8];
+        data.extend_from_slice(b"XXX\0");
+        data.extend_from_slice(comment.get().as_bytes());
+        frames.push((*b"COMM", data));
+    }
+    if let Some(image) = tags.get::<Image>() {
+        let sample = image.get();
+        let mime = sample.caps().and_then(|c| c.structure(0)).map(|s| s.name().to_string());
+
+        if let (Some(mime), Some(map)) = (mime, sample.buffer().and_then(|b| b.map_readable().ok())) {
+            // Front cover, empty description
+            let mut data = vec![ENCODING_UTF8];
+            data.extend_from_slice(mime.as_bytes());
+            data.extend_from_slice(&[0, 0x03, 0]);
+            data.extend_from_slice(&map);
+            frames.push((*b"APIC", data));
+        }
+    }
+
+    encode(&frames)
+}
+
+const ENCODING_UTF8: u8 = 0x03;
+
+fn text_frame(text: &str) -> Vec<u8> {
+    let mut data = vec![ENCODING_UTF8];
+    data.extend_from_slice(text.as_bytes());
+    data
+}
+
+fn encode(frames: &[([u8; 4], Vec<u8>)]) -> Vec<u8> {
+    let mut body = Vec::new();
+    for (id, data) in frames {
+        body.extend_from_slice(id);
+        body.extend_from_slice(&syncsafe(data.len()));
+        body.extend_from_slice(&[0, 0]);
+        body.extend_from_slice(data);
+    }
+
+    // Version 2.4.0, no flags
+    let mut tag = b"ID3\x04\x00\x00".to_vec();
+    tag.extend_from_slice(&syncsafe(body.len()));
+    tag.append(&mut body);
+    tag
+}
+
+/// ID3v2 sizes only use the lower seven bits of every byte, so that they can't be mistaken for
+/// an MPEG frame sync.
+fn syncsafe(size: usize) -> [u8; 4] {
+    [(size >> 21) as u8 & 0x7f, (size >> 14) as u8 & 0x7f, (size >> 7) as u8 & 0x7f, size as u8 & 0x7f]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn syncsafe_size() {
+        assert_eq!(syncsafe(0x7f), [0, 0, 0, 0x7f]);
+        assert_eq!(syncsafe(0x80), [0, 0, 1, 0]);
+        assert_eq!(syncsafe(300_000), [0, 0x12, 0x27, 0x60]);
+    }
+
+    #[test]
+    fn tag_layout() {
+        let tag = encode(&[(*b"TIT2", text_frame("Song"))]);
+
+        assert_eq!(&tag[..10], b"ID3\x04\x00\x00\x00\x00\x00\x0f");
+        assert_eq!(&tag[10..20], b"TIT2\x00\x00\x00\x05\x00\x00");
+        assert_eq!(&tag[20..], b"\x03Song");
+    }
+}
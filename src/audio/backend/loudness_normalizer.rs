@@ -0,0 +1,318 @@
+// Shortwave - loudness_normalizer.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::VecDeque;
+
+/// Loudness in LUFS which all stations get adjusted to. This is the same reference level as
+/// ReplayGain 2.0 uses, the EBU R128 level of -23 LUFS would be too quiet for most setups.
+const TARGET_LOUDNESS: f64 = -18.0;
+
+/// Blocks which are quieter than this are considered as silence (EBU R128 absolute gate).
+const ABSOLUTE_GATE: f64 = -70.0;
+
+/// Blocks which are this much quieter than the average are ignored, so that quiet passages
+/// don't raise the gain (EBU R128 relative gate).
+const RELATIVE_GATE: f64 = -10.0;
+
+/// Quiet stations are only boosted moderately. Additionally the gain is limited by the peaks of
+/// the measured blocks, so that the boosted audio never clips.
+const MIN_GAIN: f64 = -20.0;
+const MAX_GAIN: f64 = 10.0;
+
+/// The loudness gets measured over the last 30 seconds (blocks of 400 ms).
+const MAX_BLOCKS: usize = 75;
+
+/// Number of blocks which are needed before the gain gets adjusted for the first time.
+const MIN_BLOCKS: usize = 10;
+
+/// The gain only gets adjusted if it differs by at least this amount of dB, otherwise the
+/// volume would constantly fluctuate.
+const GAIN_THRESHOLD: f64 = 1.0;
+
+/// Measures the loudness of the playback, and determines the gain which is needed to reach the
+/// target loudness. It's a simplified EBU R128 measurement without K-weighting, based on the
+/// RMS values of the `level` element.
+pub struct LoudnessNormalizer {
+    /// Mean square of each block summed up over all channels, and the highest peak in dB
+    blocks: VecDeque<(f64, f64)>,
+    /// Currently applied gain in dB
+    gain: f64,
+    enabled: bool,
+}
+
+impl LoudnessNormalizer {
+    pub fn new() -> Self {
+        Self {
+            blocks: VecDeque::new(),
+            gain: 0.0,
+            enabled: false,
+        }
+    }
+
+    /// Starts a new measurement. The gain which was learned earlier for the same station can
+    /// be passed, so that the volume is already right from the beginning.
+    pub fn reset(&mut self, enabled: bool, gain: Option<f64>) {
+        self.blocks.clear();
+        self.gain = gain.unwrap_or(0.0).clamp(MIN_GAIN, MAX_GAIN);
+        self.enabled = enabled;
+    }
+
+    pub fn gain(&self) -> f64 {
+        self.gain
+    }
+
+    /// Adds a block with the RMS and peak value of each channel in dB. Returns the new gain if it
+    /// has changed.
+    pub fn push(&mut self, rms: &[f64], peak: &[f64]) -> Option<f64> {
+        if !self.enabled {
+            return None;
+        }
+
+        let power: f64 = rms.iter().map(|db| 10f64.powf(db / 10.0)).sum();
+        if !power.is_finite() || Self::loudness(power) < ABSOLUTE_GATE {
+            return None;
+        }
+
+        let peak = peak.iter().copied().filter(|db| !db.is_nan()).fold(f64::NEG_INFINITY, f64::max);
+        self.blocks.push_back((power, peak));
+        if self.blocks.len() > MAX_BLOCKS {
+            self.blocks.pop_front();
+        }
+
+        // The loudest peak must not exceed 0 dBFS after applying the gain
+        let max_peak = self.blocks.iter().map(|(_, peak)| *peak).fold(f64::NEG_INFINITY, f64::max);
+        let limit = (-max_peak).clamp(MIN_GAIN, MAX_GAIN);
+
+        if self.blocks.len() < MIN_BLOCKS {
+            // A learned gain gets lowered right away, instead of clipping until the measurement is ready
+            if self.gain > limit {
+                self.gain = limit;
+                return Some(limit);
+            }
+            return None;
+        }
+
+        let gain = (TARGET_LOUDNESS - self.integrated_loudness()).clamp(MIN_GAIN, limit);
+        if (gain - self.gain).abs() < GAIN_THRESHOLD && self.gain <= limit {
+            return None;
+        }
+
+        self.gain = gain;
+        Some(gain)
+    }
+
+    /// Loudness of all blocks which are passing the relative gate.
+    fn integrated_loudness(&self) -> f64 {
+        let mean = |blocks: &[f64]| blocks.iter().sum::<f64>() / blocks.len() as f64;
+
+        let blocks: Vec<f64> = self.blocks.iter().map(|(power, _)| *power).collect();
+        let threshold = Self::loudness(mean(&blocks)) + RELATIVE_GATE;
+
+        let gated: Vec<f64> = blocks.into_iter().filter(|power| Self::loudness(*power) >= threshold).collect();
+        Self::loudness(mean(&gated))
+    }
+
+    fn loudness(power: f64) -> f64 {
+        -0.691 + 10.0 * power.log10()
+    }
+}
+
+/// Converts a gain in dB into the linear factor which is used by the `volume` element.
+pub fn gain_to_volume(gain: f64) -> f64 {
+    10f64.powf(gain / 20.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Loudness of a stereo block with the same RMS value on both channels.
+    fn stereo_loudness(rms: f64) -> f64 {
+        -0.691 + 10.0 * (2.0 * 10f64.powf(rms / 10.0)).log10()
+    }
+
+    fn assert_approx(value: f64, expected: f64) {
+        assert!((value - expected).abs() < 0.001, "{} != {}", value, expected);
+    }
+
+    /// Adds a block with a crest factor of 10 dB, which is typical for music.
+    fn push(normalizer: &mut LoudnessNormalizer, rms: &[f64]) -> Option<f64> {
+        let peak: Vec<f64> = rms.iter().map(|db| db + 10.0).collect();
+        normalizer.push(rms, &peak)
+    }
+
+    fn enabled_normalizer() -> LoudnessNormalizer {
+        let mut normalizer = LoudnessNormalizer::new();
+        normalizer.reset(true, None);
+        normalizer
+    }
+
+    #[test]
+    fn disabled() {
+        let mut normalizer = LoudnessNormalizer::new();
+        for _ in 0..MAX_BLOCKS {
+            assert_eq!(push(&mut normalizer, &[-30.0, -30.0]), None);
+        }
+        assert_eq!(normalizer.gain(), 0.0);
+    }
+
+    #[test]
+    fn needs_enough_blocks() {
+        let mut normalizer = enabled_normalizer();
+        for _ in 1..MIN_BLOCKS {
+            assert_eq!(push(&mut normalizer, &[-30.0, -30.0]), None);
+        }
+
+        // -30 dBFS RMS on both channels is about -27.7 LUFS
+        let gain = push(&mut normalizer, &[-30.0, -30.0]).unwrap();
+        assert_approx(gain, TARGET_LOUDNESS - stereo_loudness(-30.0));
+        assert_approx(normalizer.gain(), gain);
+    }
+
+    #[test]
+    fn absolute_gate() {
+        let mut normalizer = enabled_normalizer();
+
+        // Silence, the level element reports digital silence as -inf or a very low value
+        for rms in &[[-80.0, -80.0], [f64::NEG_INFINITY, f64::NEG_INFINITY], [f64::NAN, -30.0]] {
+            for _ in 0..MAX_BLOCKS {
+                assert_eq!(push(&mut normalizer, rms), None);
+            }
+        }
+        assert!(normalizer.blocks.is_empty());
+    }
+
+    #[test]
+    fn relative_gate() {
+        let mut normalizer = enabled_normalizer();
+        for _ in 0..20 {
+            push(&mut normalizer, &[-20.0, -20.0]);
+        }
+
+        // Quiet passages are more than 10 LU below the average, so they don't count
+        for _ in 0..40 {
+            assert_eq!(push(&mut normalizer, &[-45.0, -45.0]), None);
+        }
+        assert_eq!(normalizer.blocks.len(), 60);
+        assert_approx(normalizer.integrated_loudness(), stereo_loudness(-20.0));
+
+        // Blocks within the gate are averaged by power, not by dB
+        let mut normalizer = enabled_normalizer();
+        for _ in 0..10 {
+            push(&mut normalizer, &[-20.0, -20.0]);
+            push(&mut normalizer, &[-26.0, -26.0]);
+        }
+        let power = (2.0 * 10f64.powf(-2.0) + 2.0 * 10f64.powf(-2.6)) / 2.0;
+        assert_approx(normalizer.integrated_loudness(), -0.691 + 10.0 * power.log10());
+    }
+
+    #[test]
+    fn gain_limits() {
+        let mut normalizer = enabled_normalizer();
+        for _ in 0..MIN_BLOCKS {
+            push(&mut normalizer, &[-50.0]);
+        }
+        assert_eq!(normalizer.gain(), MAX_GAIN);
+
+        let mut normalizer = enabled_normalizer();
+        for _ in 0..MIN_BLOCKS {
+            push(&mut normalizer, &[0.0, 0.0]);
+        }
+        assert_eq!(normalizer.gain(), MIN_GAIN);
+    }
+
+    #[test]
+    fn peak_limit() {
+        // Quiet audio with high peaks (e.g. classical music), the full gain would cause clipping
+        let mut normalizer = enabled_normalizer();
+        for _ in 1..MIN_BLOCKS {
+            assert_eq!(normalizer.push(&[-30.0, -30.0], &[-3.0, -4.0]), None);
+        }
+        assert_eq!(normalizer.push(&[-30.0, -30.0], &[-3.0, -4.0]), Some(3.0));
+
+        // A learned gain is lowered as soon as a louder peak shows up, even if it's only a bit too high
+        let mut normalizer = enabled_normalizer();
+        normalizer.reset(true, Some(TARGET_LOUDNESS - stereo_loudness(-30.0)));
+        for _ in 1..MIN_BLOCKS {
+            assert_eq!(push(&mut normalizer, &[-30.0, -30.0]), None);
+        }
+        assert_eq!(push(&mut normalizer, &[-30.0, -30.0]), None);
+        assert_eq!(normalizer.push(&[-30.0, -30.0], &[-9.5, -12.0]), Some(9.5));
+        assert_eq!(normalizer.gain(), 9.5);
+
+        // Even before there are enough blocks for measuring the loudness
+        normalizer.reset(true, Some(MAX_GAIN));
+        assert_eq!(normalizer.push(&[-30.0, -30.0], &[-6.0, -6.0]), Some(6.0));
+        assert_eq!(push(&mut normalizer, &[-30.0, -30.0]), None);
+    }
+
+    #[test]
+    fn gain_threshold() {
+        let mut normalizer = enabled_normalizer();
+        for _ in 0..MIN_BLOCKS {
+            push(&mut normalizer, &[-30.0, -30.0]);
+        }
+        let gain = normalizer.gain();
+
+        // Small changes of the loudness are ignored
+        for _ in 0..MAX_BLOCKS {
+            assert_eq!(push(&mut normalizer, &[-30.5, -30.5]), None);
+        }
+        assert_eq!(normalizer.gain(), gain);
+    }
+
+    #[test]
+    fn sliding_window() {
+        let mut normalizer = enabled_normalizer();
+        for _ in 0..MAX_BLOCKS {
+            push(&mut normalizer, &[-20.0, -20.0]);
+        }
+        for _ in 0..MAX_BLOCKS {
+            push(&mut normalizer, &[-30.0, -30.0]);
+        }
+
+        // Only the last 30 seconds are taken into account
+        assert_eq!(normalizer.blocks.len(), MAX_BLOCKS);
+        assert_approx(normalizer.integrated_loudness(), stereo_loudness(-30.0));
+        assert!((normalizer.gain() - (TARGET_LOUDNESS - stereo_loudness(-30.0))).abs() < GAIN_THRESHOLD);
+    }
+
+    #[test]
+    fn reset() {
+        let mut normalizer = enabled_normalizer();
+        for _ in 0..MIN_BLOCKS {
+            push(&mut normalizer, &[-30.0, -30.0]);
+        }
+
+        normalizer.reset(true, Some(4.0));
+        assert!(normalizer.blocks.is_empty());
+        assert_eq!(normalizer.gain(), 4.0);
+
+        normalizer.reset(true, Some(50.0));
+        assert_eq!(normalizer.gain(), MAX_GAIN);
+
+        normalizer.reset(false, None);
+        assert_eq!(normalizer.gain(), 0.0);
+        assert_eq!(push(&mut normalizer, &[-30.0, -30.0]), None);
+    }
+
+    #[test]
+    fn volume() {
+        assert_approx(gain_to_volume(0.0), 1.0);
+        assert_approx(gain_to_volume(20.0), 10.0);
+        assert_approx(gain_to_volume(-20.0 * 2f64.log10()), 0.5);
+    }
+}
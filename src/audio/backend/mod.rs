@@ -15,17 +15,20 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 mod gstreamer_backend;
+mod id3_tag;
+mod loudness_normalizer;
 mod song_backend;
+mod timeshift_buffer;
 
+pub use gstreamer_backend::GstreamerBackend;
 pub use gstreamer_backend::GstreamerMessage;
+pub use song_backend::SongBackend;
 
 use crate::app::Action;
 use crate::settings::{settings_manager, Key};
 
 use glib::{Receiver, Sender};
-use gstreamer_backend::GstreamerBackend;
 use gtk::glib;
-use song_backend::SongBackend;
 use std::convert::TryInto;
 
 pub struct Backend {
@@ -19,6 +19,7 @@ use gtk::glib;
 use indexmap::IndexMap;
 
 use std::fs;
+use std::path::PathBuf;
 
 use crate::api::Error;
 use crate::app::Action;
@@ -49,7 +50,8 @@ impl SongBackend {
         Self { listbox, songs, save_count }
     }
 
-    pub fn add_song(&mut self, song: Song) {
+    /// Adds a recorded song, and saves it directly if `auto_save` is set.
+    pub fn add_song(&mut self, song: Song, auto_save: bool) -> Result<(), Error> {
         // Check if song does not exist yet
         if self.songs.get(&song.title).is_none() {
             // Ensure max length
@@ -59,11 +61,16 @@ impl SongBackend {
                 self.remove_song(song);
             }
 
+            let result = if auto_save { self.save_song(song.clone()) } else { Ok(()) };
+
             // Add song to indexmap & listbox
-            self.listbox.add_song(song.clone());
+            self.listbox.add_song(song.clone(), auto_save && result.is_ok());
             self.songs.insert(song.title.to_string(), song);
+
+            result
         } else {
             warn!("Song \"{}\" is already recorded", song.title);
+            Ok(())
         }
     }
 
@@ -81,19 +88,50 @@ impl SongBackend {
     pub fn save_song(&self, song: Song) -> Result<(), Error> {
         debug!("Save song \"{}\"", &song.title);
 
-        let mut dest_path = glib::user_special_dir(glib::UserDirectory::Music);
-        dest_path.push(song.path.file_name().unwrap());
+        let template = settings_manager::string(Key::RecorderFilenameTemplate);
+        let mut file_path = song.file_path(&template);
+        if file_path.file_name().is_none() {
+            warn!("Invalid filename template \"{}\", using the song title instead", template);
+            file_path = PathBuf::from(song.path.file_name().unwrap());
+        }
 
-        let custom_path = settings_manager::string(Key::RecorderSongSavePath);
-        if !custom_path.is_empty() {
-            dest_path.push(custom_path);
-            dest_path.push(song.path.file_name().unwrap());
+        let dest_path = Self::unique_path(Self::save_dir().join(file_path));
+        if let Some(parent) = dest_path.parent() {
+            fs::create_dir_all(parent)?;
         }
 
+        debug!("Copy song to {:?}", dest_path);
         fs::copy(song.path, dest_path)?;
         Ok(())
     }
 
+    /// Appends a number to the filename if the path already exists, eg. "Title (2).ogg"
+    fn unique_path(path: PathBuf) -> PathBuf {
+        if !path.exists() {
+            return path;
+        }
+
+        let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let extension = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+
+        (2..)
+            .map(|number| path.with_file_name(format!("{} ({}){}", stem, number, extension)))
+            .find(|path| !path.exists())
+            .unwrap()
+    }
+
+    /// Returns the directory in which recordings get saved.
+    pub fn save_dir() -> PathBuf {
+        let mut path = glib::user_special_dir(glib::UserDirectory::Music);
+
+        let custom_path = settings_manager::string(Key::RecorderSongSavePath);
+        if !custom_path.is_empty() {
+            path.push(custom_path);
+        }
+
+        path
+    }
+
     pub fn delete_songs(&self) {
         let mut path = path::CACHE.clone();
         path.push("recording");
@@ -0,0 +1,168 @@
+// Shortwave - timeshift_buffer.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use gstreamer::Buffer;
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// The buffer always keeps a few seconds, even if timeshift is disabled, otherwise the live
+/// playback would run dry. In that case pausing continues with the most recent seconds.
+const MIN_BUFFER_DURATION: Duration = Duration::from_secs(5);
+
+/// Keeps the most recent decoded audio of the stream in RAM, so that the playback can be paused
+/// and rewinded while the stream itself keeps running. Decoded audio takes about 10 to 20 MB per
+/// minute (depending on sample rate and format), that's why timeshift is disabled by default and
+/// the buffer size is limited to 30 minutes by the settings schema.
+///
+/// ```text
+///  buffers:  [ oldest | ... | ... | ... | ... | newest ]
+///                             ^                        ^
+///                          position                   live
+/// ```
+pub struct TimeshiftBuffer {
+    buffers: VecDeque<Buffer>,
+    /// Index of the next buffer which gets played. If it's equal to the number of buffers, the
+    /// playback is live.
+    position: usize,
+    /// Duration of all buffers in nanoseconds
+    duration: u64,
+    /// Duration of the buffers between position and live in nanoseconds
+    delay: u64,
+    max_duration: u64,
+    paused: bool,
+    /// Timestamp of the next buffer which gets played, in pipeline running time
+    pub next_pts: Option<u64>,
+}
+
+impl TimeshiftBuffer {
+    pub fn new(max_duration: Duration) -> Self {
+        let mut timeshift = Self {
+            buffers: VecDeque::new(),
+            position: 0,
+            duration: 0,
+            delay: 0,
+            max_duration: 0,
+            paused: false,
+            next_pts: None,
+        };
+        timeshift.set_max_duration(max_duration);
+        timeshift
+    }
+
+    /// Whether the buffer is large enough for rewinding the playback.
+    pub fn is_available(&self) -> bool {
+        self.max_duration > MIN_BUFFER_DURATION.as_nanos() as u64
+    }
+
+    pub fn set_max_duration(&mut self, max_duration: Duration) {
+        self.max_duration = max_duration.max(MIN_BUFFER_DURATION).as_nanos() as u64;
+        self.shrink();
+    }
+
+    pub fn clear(&mut self) {
+        self.buffers.clear();
+        self.position = 0;
+        self.duration = 0;
+        self.delay = 0;
+        self.paused = false;
+        self.next_pts = None;
+    }
+
+    /// Adds a new buffer of the live stream.
+    pub fn push(&mut self, buffer: Buffer) {
+        let duration = Self::buffer_duration(&buffer);
+        self.buffers.push_back(buffer);
+        self.duration += duration;
+        self.delay += duration;
+        self.shrink();
+    }
+
+    /// Returns the next buffer which should get played.
+    pub fn pop(&mut self) -> Option<Buffer> {
+        if self.paused {
+            return None;
+        }
+
+        let buffer = self.buffers.get(self.position)?.clone();
+        self.position += 1;
+        self.delay -= Self::buffer_duration(&buffer).min(self.delay);
+        Some(buffer)
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// How far the playback is behind the live stream.
+    pub fn delay(&self) -> Duration {
+        Duration::from_nanos(self.delay)
+    }
+
+    /// Moves the playback position, negative offsets are rewinding.
+    pub fn seek(&mut self, offset: i64) {
+        let mut remaining = Duration::from_secs(offset.unsigned_abs()).as_nanos() as u64;
+
+        if offset < 0 {
+            while remaining > 0 && self.position > 0 {
+                self.position -= 1;
+                let duration = Self::buffer_duration(&self.buffers[self.position]);
+                self.delay += duration;
+                remaining = remaining.saturating_sub(duration);
+            }
+        } else {
+            while remaining > 0 && self.position < self.buffers.len() {
+                let duration = Self::buffer_duration(&self.buffers[self.position]);
+                self.position += 1;
+                self.delay -= duration.min(self.delay);
+                remaining = remaining.saturating_sub(duration);
+            }
+        }
+    }
+
+    /// Jumps to the live stream.
+    pub fn go_live(&mut self) {
+        self.position = self.buffers.len();
+        self.delay = 0;
+    }
+
+    /// Removes the oldest buffers until the maximum duration is met.
+    fn shrink(&mut self) {
+        while self.duration > self.max_duration {
+            let buffer = match self.buffers.pop_front() {
+                Some(buffer) => buffer,
+                None => break,
+            };
+            let duration = Self::buffer_duration(&buffer);
+            self.duration -= duration.min(self.duration);
+
+            // The playback position has fallen out of the buffer, so it continues with the oldest buffer
+            if self.position > 0 {
+                self.position -= 1;
+            } else {
+                self.delay -= duration.min(self.delay);
+            }
+        }
+    }
+
+    fn buffer_duration(buffer: &Buffer) -> u64 {
+        buffer.duration().map(|d| d.nseconds()).unwrap_or(0)
+    }
+}
@@ -29,10 +29,11 @@ use std::str::FromStr;
 use std::sync::mpsc::{channel, Receiver, Sender};
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::Duration;
 
-use crate::api::{StationMetadata, SwStation};
+use crate::api::{StationMetadata, StreamVariant, SwStation};
 use crate::app::Action;
-use crate::audio::{Controller, GCastDevice, PlaybackState};
+use crate::audio::{Controller, GCastDevice, PlaybackState, StreamInfo, StreamTitle, TimeshiftState};
 
 enum GCastAction {
     Connect,
@@ -231,7 +232,27 @@ impl Controller for Rc<GCastController> {
         // Ignore
     }
 
-    fn set_song_title(&self, _title: &str) {
+    fn set_song_title(&self, _title: &StreamTitle) {
+        // Ignore
+    }
+
+    fn set_sleep_timer(&self, _remaining: Option<Duration>) {
+        // Ignore
+    }
+
+    fn set_recording(&self, _recording: bool) {
+        // Ignore
+    }
+
+    fn set_timeshift(&self, _timeshift: Option<TimeshiftState>) {
+        // Ignore
+    }
+
+    fn set_stream_variant(&self, _variant: Option<&StreamVariant>) {
+        // Ignore
+    }
+
+    fn set_stream_info(&self, _info: &StreamInfo) {
         // Ignore
     }
 }
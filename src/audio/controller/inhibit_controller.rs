@@ -18,31 +18,52 @@ use gtk::gio;
 use gtk::prelude::*;
 
 use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
 
-use crate::api::SwStation;
+use crate::api::{StreamVariant, SwStation};
 use crate::app::SwApplication;
 use crate::audio::Controller;
-use crate::audio::PlaybackState;
+use crate::audio::{PlaybackState, StreamInfo, StreamTitle, TimeshiftState};
 
 #[derive(Debug, Default)]
 pub struct InhibitController {
     cookie: Cell<u32>,
+    alarm_cookie: Cell<u32>,
 }
 
 impl InhibitController {
-    pub fn new() -> Self {
-        Self::default()
+    pub fn new() -> Rc<Self> {
+        Rc::new(Self::default())
+    }
+
+    /// Keeps the system awake, so that pending alarms are able to fire.
+    pub fn set_alarm_pending(&self, pending: bool) {
+        let app = gio::Application::default().unwrap().downcast_ref::<SwApplication>().unwrap().clone();
+
+        if pending && self.alarm_cookie.get() == 0 {
+            let msg = Some("Alarm pending");
+            let cookie = app.inhibit(app.active_window().as_ref(), gtk::ApplicationInhibitFlags::SUSPEND, msg);
+            self.alarm_cookie.set(cookie);
+
+            debug!("Install alarm inhibitor")
+        } else if !pending && self.alarm_cookie.get() != 0 {
+            app.uninhibit(self.alarm_cookie.get());
+            self.alarm_cookie.set(0);
+
+            debug!("Remove alarm inhibitor");
+        }
     }
 }
 
-impl Controller for InhibitController {
+impl Controller for Rc<InhibitController> {
     fn set_station(&self, _station: SwStation) {}
 
     fn set_playback_state(&self, playback_state: &PlaybackState) {
         let app = gio::Application::default().unwrap().downcast_ref::<SwApplication>().unwrap().clone();
         let window = app.active_window().unwrap();
 
-        if playback_state == &PlaybackState::Playing || playback_state == &PlaybackState::Loading {
+        if matches!(playback_state, PlaybackState::Playing | PlaybackState::Loading | PlaybackState::Reconnecting(_)) {
             if self.cookie.get() == 0 {
                 let msg = Some("Playback active");
                 let cookie = app.inhibit(Some(&window), gtk::ApplicationInhibitFlags::SUSPEND, msg);
@@ -62,5 +83,15 @@ impl Controller for InhibitController {
 
     fn set_volume(&self, _volume: f64) {}
 
-    fn set_song_title(&self, _title: &str) {}
+    fn set_song_title(&self, _title: &StreamTitle) {}
+
+    fn set_sleep_timer(&self, _remaining: Option<Duration>) {}
+
+    fn set_recording(&self, _recording: bool) {}
+
+    fn set_timeshift(&self, _timeshift: Option<TimeshiftState>) {}
+
+    fn set_stream_variant(&self, _variant: Option<&StreamVariant>) {}
+
+    fn set_stream_info(&self, _info: &StreamInfo) {}
 }
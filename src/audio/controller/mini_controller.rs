@@ -21,11 +21,12 @@ use gtk::prelude::*;
 
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::time::Duration;
 
-use crate::api::SwStation;
+use crate::api::{StreamVariant, SwStation};
 use crate::app::Action;
 use crate::audio::Controller;
-use crate::audio::PlaybackState;
+use crate::audio::{PlaybackState, StreamInfo, StreamTitle, TimeshiftState};
 
 pub struct MiniController {
     pub widget: gtk::Box,
@@ -37,7 +38,7 @@ pub struct MiniController {
     subtitle_revealer: gtk::Revealer,
     playback_button_stack: gtk::Stack,
     start_playback_button: gtk::Button,
-    stop_playback_button: gtk::Button,
+    pause_playback_button: gtk::Button,
     loading_button: gtk::Button,
     volume_button: gtk::VolumeButton,
     volume_signal_id: glib::signal::SignalHandlerId,
@@ -52,7 +53,7 @@ impl MiniController {
         get_widget!(builder, gtk::Revealer, subtitle_revealer);
         get_widget!(builder, gtk::Stack, playback_button_stack);
         get_widget!(builder, gtk::Button, start_playback_button);
-        get_widget!(builder, gtk::Button, stop_playback_button);
+        get_widget!(builder, gtk::Button, pause_playback_button);
         get_widget!(builder, gtk::Button, loading_button);
         get_widget!(builder, gtk::VolumeButton, volume_button);
 
@@ -72,7 +73,7 @@ impl MiniController {
             subtitle_revealer,
             playback_button_stack,
             start_playback_button,
-            stop_playback_button,
+            pause_playback_button,
             loading_button,
             volume_button,
             volume_signal_id,
@@ -88,9 +89,9 @@ impl MiniController {
             send!(sender, Action::PlaybackSet(true));
         }));
 
-        // stop_playback_button
-        self.stop_playback_button.connect_clicked(clone!(@strong self.sender as sender => move |_| {
-            send!(sender, Action::PlaybackSet(false));
+        // pause_playback_button
+        self.pause_playback_button.connect_clicked(clone!(@strong self.sender as sender => move |_| {
+            send!(sender, Action::PlaybackPause);
         }));
 
         // loading_button
@@ -110,10 +111,16 @@ impl Controller for MiniController {
     }
 
     fn set_playback_state(&self, playback_state: &PlaybackState) {
+        self.loading_button.set_tooltip_text(None);
+
         match playback_state {
-            PlaybackState::Playing => self.playback_button_stack.set_visible_child_name("stop_playback"),
-            PlaybackState::Stopped => self.playback_button_stack.set_visible_child_name("start_playback"),
+            PlaybackState::Playing => self.playback_button_stack.set_visible_child_name("pause_playback"),
+            PlaybackState::Paused | PlaybackState::Stopped => self.playback_button_stack.set_visible_child_name("start_playback"),
             PlaybackState::Loading => self.playback_button_stack.set_visible_child_name("loading"),
+            PlaybackState::Reconnecting(attempt) => {
+                self.playback_button_stack.set_visible_child_name("loading");
+                self.loading_button.set_tooltip_text(Some(&super::reconnect_text(*attempt)));
+            }
             PlaybackState::Failure(_) => self.playback_button_stack.set_visible_child_name("start_playback"),
         };
     }
@@ -125,10 +132,11 @@ impl Controller for MiniController {
         glib::signal::signal_handler_unblock(&self.volume_button, &self.volume_signal_id);
     }
 
-    fn set_song_title(&self, title: &str) {
+    fn set_song_title(&self, title: &StreamTitle) {
+        let title = title.to_string();
         if !title.is_empty() {
-            self.subtitle_label.set_text(title);
-            self.subtitle_label.set_tooltip_text(Some(title));
+            self.subtitle_label.set_text(&title);
+            self.subtitle_label.set_tooltip_text(Some(&title));
             self.subtitle_revealer.set_reveal_child(true);
         } else {
             self.subtitle_label.set_text("");
@@ -136,4 +144,24 @@ impl Controller for MiniController {
             self.subtitle_revealer.set_reveal_child(false);
         }
     }
+
+    fn set_sleep_timer(&self, _remaining: Option<Duration>) {
+        // We don't have to do anything here.
+    }
+
+    fn set_recording(&self, _recording: bool) {
+        // We don't have to do anything here.
+    }
+
+    fn set_timeshift(&self, _timeshift: Option<TimeshiftState>) {
+        // We don't have to do anything here.
+    }
+
+    fn set_stream_variant(&self, _variant: Option<&StreamVariant>) {
+        // We don't have to do anything here.
+    }
+
+    fn set_stream_info(&self, _info: &StreamInfo) {
+        // We don't have to do anything here.
+    }
 }
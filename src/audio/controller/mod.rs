@@ -31,12 +31,60 @@ pub use mpris_controller::MprisController;
 #[cfg(unix)]
 mod mpris_controller;
 
-use crate::api::SwStation;
-use crate::audio::PlaybackState;
+use std::time::Duration;
+
+use crate::api::{StreamVariant, SwStation};
+use crate::audio::{PlaybackState, StreamInfo, StreamTitle, TimeshiftState};
+use crate::i18n::*;
 
 pub trait Controller {
     fn set_station(&self, station: SwStation);
     fn set_playback_state(&self, playback_state: &PlaybackState);
     fn set_volume(&self, volume: f64);
-    fn set_song_title(&self, title: &str);
+    fn set_song_title(&self, title: &StreamTitle);
+    fn set_sleep_timer(&self, remaining: Option<Duration>);
+    fn set_recording(&self, recording: bool);
+    fn set_timeshift(&self, timeshift: Option<TimeshiftState>);
+    fn set_stream_variant(&self, variant: Option<&StreamVariant>);
+    fn set_stream_info(&self, info: &StreamInfo);
+}
+
+/// Returns the countdown text of the sleep timer, eg. "Stops in 12:34"
+fn sleep_timer_text(remaining: Duration) -> String {
+    let secs = remaining.as_secs();
+    let countdown = if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, (secs % 3600) / 60, secs % 60)
+    } else {
+        format!("{:02}:{:02}", secs / 60, secs % 60)
+    };
+
+    // Sleep timer countdown. "{}" is the remaining time, eg. "12:34"
+    i18n_f("Stops in {}", &[&countdown])
+}
+
+/// Returns how far the playback is behind the live stream, eg. "-01:23"
+fn timeshift_text(delay: Duration) -> String {
+    let secs = delay.as_secs();
+    if secs == 0 {
+        return i18n("Live");
+    }
+
+    if secs >= 3600 {
+        format!("-{}:{:02}:{:02}", secs / 3600, (secs % 3600) / 60, secs % 60)
+    } else {
+        format!("-{:02}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// Returns the status text while the stream is reconnecting, eg. "Reconnecting (attempt 2)"
+fn reconnect_text(attempt: u32) -> String {
+    // Status when the stream got interrupted. "{}" is the number of the attempt, eg. "2"
+    i18n_f("Reconnecting (attempt {})", &[&attempt.to_string()])
+}
+
+/// Returns a short description of the variant of an adaptive stream, eg. "HLS · 128 kbit/s"
+fn stream_variant_text(variant: &StreamVariant) -> String {
+    let bitrate = (variant.bandwidth / 1000).to_string();
+    // Variant of a HLS / DASH stream. The first "{}" is the protocol, eg. "HLS", the second one the bitrate, eg. "128"
+    i18n_f("{} · {} kbit/s", &[variant.protocol.name(), &bitrate])
 }
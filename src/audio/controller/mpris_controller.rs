@@ -23,21 +23,24 @@ use mpris_player::{Metadata, MprisPlayer, OrgMprisMediaPlayer2Player, PlaybackSt
 use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::api::FaviconDownloader;
+use crate::api::StreamVariant;
 use crate::api::SwStation;
 use crate::app::Action;
 use crate::audio::Controller;
-use crate::audio::PlaybackState;
+use crate::audio::{PlaybackState, StreamInfo, StreamTitle, TimeshiftState};
 use crate::config;
 
 pub struct MprisController {
     sender: Sender<Action>,
     mpris: Arc<MprisPlayer>,
 
-    song_title: Cell<Option<String>>,
+    song_title: Cell<Option<StreamTitle>>,
     station: Cell<Option<SwStation>>,
     volume: Rc<RefCell<f64>>,
+    can_seek: Cell<bool>,
 }
 
 impl MprisController {
@@ -47,6 +50,7 @@ impl MprisController {
         mpris.set_can_play(false);
         mpris.set_can_seek(false);
         mpris.set_can_set_fullscreen(false);
+        // Pausing is always possible, the playback keeps a few seconds buffered even without timeshift
         mpris.set_can_pause(true);
 
         let volume = Rc::new(RefCell::new(0.0));
@@ -57,6 +61,7 @@ impl MprisController {
             song_title: Cell::new(None),
             station: Cell::new(None),
             volume,
+            can_seek: Cell::new(false),
         };
 
         controller.setup_signals();
@@ -76,10 +81,14 @@ impl MprisController {
                     metadata.art_url = Some(path);
                 })
             });
-            metadata.artist = Some(vec![station.metadata().name]);
+            metadata.artist = Some(vec![station.metadata().name.clone()]);
+            metadata.album = Some(station.metadata().name);
         }
         if let Some(song_title) = song_title.clone() {
-            metadata.title = Some(song_title);
+            if let Some(artist) = song_title.artist {
+                metadata.artist = Some(vec![artist]);
+            }
+            metadata.title = Some(song_title.title);
         }
 
         self.station.set(station);
@@ -99,7 +108,7 @@ impl MprisController {
             match mpris.get_playback_status().unwrap().as_ref() {
                 "Paused" => send!(sender, Action::PlaybackSet(true)),
                 "Stopped" => send!(sender, Action::PlaybackSet(true)),
-                _ => send!(sender, Action::PlaybackSet(false)),
+                _ => send!(sender, Action::PlaybackPause),
             };
         }));
 
@@ -115,7 +124,12 @@ impl MprisController {
 
         // mpris pause
         self.mpris.connect_pause(clone!(@strong self.sender as sender => move || {
-            send!(sender, Action::PlaybackSet(false));
+            send!(sender, Action::PlaybackPause);
+        }));
+
+        // mpris seek (offset in microseconds)
+        self.mpris.connect_seek(clone!(@strong self.sender as sender => move |offset| {
+            send!(sender, Action::PlaybackSeek(offset / 1_000_000));
         }));
 
         // mpris volume
@@ -140,6 +154,9 @@ impl Controller for MprisController {
 
         match playback_state {
             PlaybackState::Playing => self.mpris.set_playback_status(PlaybackStatus::Playing),
+            PlaybackState::Paused => self.mpris.set_playback_status(PlaybackStatus::Paused),
+            // The playback continues as soon as the stream is available again
+            PlaybackState::Reconnecting(_) => self.mpris.set_playback_status(PlaybackStatus::Playing),
             _ => self.mpris.set_playback_status(PlaybackStatus::Stopped),
         };
     }
@@ -149,8 +166,32 @@ impl Controller for MprisController {
         self.mpris.set_volume(volume).unwrap();
     }
 
-    fn set_song_title(&self, title: &str) {
-        self.song_title.set(Some(title.to_string()));
+    fn set_song_title(&self, title: &StreamTitle) {
+        self.song_title.set(Some(title.clone()));
         self.update_metadata();
     }
+
+    fn set_sleep_timer(&self, _remaining: Option<Duration>) {
+        // Not supported by MPRIS
+    }
+
+    fn set_recording(&self, _recording: bool) {
+        // Not supported by MPRIS
+    }
+
+    fn set_timeshift(&self, timeshift: Option<TimeshiftState>) {
+        // Pausing is always possible, but rewinding needs the timeshift buffer
+        let can_seek = timeshift.is_some();
+        if self.can_seek.replace(can_seek) != can_seek {
+            self.mpris.set_can_seek(can_seek);
+        }
+    }
+
+    fn set_stream_variant(&self, _variant: Option<&StreamVariant>) {
+        // Not supported by MPRIS
+    }
+
+    fn set_stream_info(&self, _info: &StreamInfo) {
+        // Not supported by MPRIS
+    }
 }
@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use chrono::{Local, NaiveTime, TimeZone, Timelike};
 use futures_util::future::FutureExt;
 use glib::clone;
 use glib::Sender;
@@ -22,11 +23,13 @@ use gtk::{gio, glib};
 
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::time::Duration;
 
-use crate::api::{FaviconDownloader, SwStation};
-use crate::app::Action;
+use crate::api::{FaviconDownloader, StreamVariant, SwStation};
+use crate::app::{Action, SwApplication};
 use crate::audio::Controller;
-use crate::audio::PlaybackState;
+use crate::audio::{PlaybackState, StreamInfo, StreamTitle, TimeshiftState};
+use crate::i18n::*;
 use crate::ui::{FaviconSize, StationFavicon, StreamingDialog, SwStationDialog};
 
 pub struct SidebarController {
@@ -41,11 +44,26 @@ pub struct SidebarController {
     action_revealer: gtk::Revealer,
     playback_button_stack: gtk::Stack,
     start_playback_button: gtk::Button,
+    pause_playback_button: gtk::Button,
     stop_playback_button: gtk::Button,
     loading_button: gtk::Button,
     error_label: gtk::Label,
     volume_button: gtk::VolumeButton,
     volume_signal_id: glib::signal::SignalHandlerId,
+    record_button: gtk::ToggleButton,
+    record_signal_id: glib::signal::SignalHandlerId,
+    stream_info_button: gtk::MenuButton,
+    stream_info_grid: gtk::Grid,
+    sleep_timer_revealer: gtk::Revealer,
+    sleep_timer_label: gtk::Label,
+    stream_variant_label: gtk::Label,
+    reconnect_revealer: gtk::Revealer,
+    reconnect_label: gtk::Label,
+    timeshift_revealer: gtk::Revealer,
+    timeshift_label: gtk::Label,
+    rewind_button: gtk::Button,
+    forward_button: gtk::Button,
+    live_button: gtk::Button,
 
     action_group: gio::SimpleActionGroup,
     streaming_dialog: Rc<StreamingDialog>,
@@ -61,10 +79,24 @@ impl SidebarController {
         get_widget!(builder, gtk::Revealer, action_revealer);
         get_widget!(builder, gtk::Stack, playback_button_stack);
         get_widget!(builder, gtk::Button, start_playback_button);
+        get_widget!(builder, gtk::Button, pause_playback_button);
         get_widget!(builder, gtk::Button, stop_playback_button);
         get_widget!(builder, gtk::Button, loading_button);
         get_widget!(builder, gtk::Label, error_label);
         get_widget!(builder, gtk::VolumeButton, volume_button);
+        get_widget!(builder, gtk::ToggleButton, record_button);
+        get_widget!(builder, gtk::MenuButton, stream_info_button);
+        get_widget!(builder, gtk::Grid, stream_info_grid);
+        get_widget!(builder, gtk::Revealer, sleep_timer_revealer);
+        get_widget!(builder, gtk::Label, sleep_timer_label);
+        get_widget!(builder, gtk::Label, stream_variant_label);
+        get_widget!(builder, gtk::Revealer, reconnect_revealer);
+        get_widget!(builder, gtk::Label, reconnect_label);
+        get_widget!(builder, gtk::Revealer, timeshift_revealer);
+        get_widget!(builder, gtk::Label, timeshift_label);
+        get_widget!(builder, gtk::Button, rewind_button);
+        get_widget!(builder, gtk::Button, forward_button);
+        get_widget!(builder, gtk::Button, live_button);
 
         let station = Rc::new(RefCell::new(None));
 
@@ -77,6 +109,11 @@ impl SidebarController {
             send!(sender, Action::PlaybackSetVolume(value));
         }));
 
+        // record_button | We need the record_signal_id later to block the signal
+        let record_signal_id = record_button.connect_toggled(clone!(@strong sender => move |button| {
+            send!(sender, Action::PlaybackSetRecording(button.is_active()));
+        }));
+
         // action group
         let action_group = gio::SimpleActionGroup::new();
         sidebar_controller.insert_action_group("player", Some(&action_group));
@@ -95,11 +132,26 @@ impl SidebarController {
             subtitle_revealer,
             playback_button_stack,
             start_playback_button,
+            pause_playback_button,
             stop_playback_button,
             loading_button,
             error_label,
             volume_button,
             volume_signal_id,
+            record_button,
+            record_signal_id,
+            stream_info_button,
+            stream_info_grid,
+            sleep_timer_revealer,
+            sleep_timer_label,
+            stream_variant_label,
+            reconnect_revealer,
+            reconnect_label,
+            timeshift_revealer,
+            timeshift_label,
+            rewind_button,
+            forward_button,
+            live_button,
             action_group,
             streaming_dialog,
         };
@@ -114,16 +166,32 @@ impl SidebarController {
             send!(sender, Action::PlaybackSet(true));
         }));
 
+        // pause_playback_button
+        self.pause_playback_button.connect_clicked(clone!(@strong self.sender as sender => move |_| {
+            send!(sender, Action::PlaybackPause);
+        }));
+
         // stop_playback_button
         self.stop_playback_button.connect_clicked(clone!(@strong self.sender as sender => move |_| {
             send!(sender, Action::PlaybackSet(false));
         }));
 
-        // stop_playback_button
+        // loading_button
         self.loading_button.connect_clicked(clone!(@strong self.sender as sender => move |_| {
             send!(sender, Action::PlaybackSet(false));
         }));
 
+        // timeshift buttons
+        self.rewind_button.connect_clicked(clone!(@strong self.sender as sender => move |_| {
+            send!(sender, Action::PlaybackSeek(-30));
+        }));
+        self.forward_button.connect_clicked(clone!(@strong self.sender as sender => move |_| {
+            send!(sender, Action::PlaybackSeek(30));
+        }));
+        self.live_button.connect_clicked(clone!(@strong self.sender as sender => move |_| {
+            send!(sender, Action::PlaybackGoLive);
+        }));
+
         // details button
         action!(
             self.action_group,
@@ -143,6 +211,142 @@ impl SidebarController {
                 streaming_dialog.show();
             })
         );
+
+        // sleep timer (duration in minutes)
+        let sleep_timer_action = gio::SimpleAction::new("sleep-timer", Some(glib::VariantTy::new("u").unwrap()));
+        sleep_timer_action.connect_activate(clone!(@strong self.sender as sender => move |_, minutes| {
+            let minutes = minutes.and_then(|m| m.get::<u32>()).unwrap();
+            send!(sender, Action::PlaybackSetSleepTimer(Some(Duration::from_secs(u64::from(minutes) * 60))));
+        }));
+        self.action_group.add_action(&sleep_timer_action);
+
+        action!(
+            self.action_group,
+            "sleep-timer-until",
+            clone!(@strong self.sender as sender => move |_, _| {
+                Self::show_sleep_timer_dialog(sender.clone());
+            })
+        );
+
+        action!(
+            self.action_group,
+            "cancel-sleep-timer",
+            clone!(@strong self.sender as sender => move |_, _| {
+                send!(sender, Action::PlaybackSetSleepTimer(None));
+            })
+        );
+        self.action_group
+            .lookup_action("cancel-sleep-timer")
+            .unwrap()
+            .downcast::<gio::SimpleAction>()
+            .unwrap()
+            .set_enabled(false);
+    }
+
+    /// Asks for a clock time at which the playback should get stopped.
+    fn show_sleep_timer_dialog(sender: Sender<Action>) {
+        let window = gio::Application::default().unwrap().downcast_ref::<SwApplication>().unwrap().active_window().unwrap();
+        let dialog = gtk::MessageDialog::new(
+            Some(&window),
+            gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+            gtk::MessageType::Question,
+            gtk::ButtonsType::None,
+            &i18n("Stop playback at"),
+        );
+
+        let cancel_label = i18n("_Cancel");
+        let start_label = i18n("_Start Timer");
+        dialog.add_button(&cancel_label, gtk::ResponseType::Cancel);
+        dialog.add_button(&start_label, gtk::ResponseType::Accept);
+        dialog.set_default_response(gtk::ResponseType::Accept);
+
+        // Default to one hour from now
+        let default = Local::now() + chrono::Duration::hours(1);
+        let hour_spinbutton = gtk::SpinButton::with_range(0.0, 23.0, 1.0);
+        hour_spinbutton.set_value(default.hour().into());
+        hour_spinbutton.set_orientation(gtk::Orientation::Vertical);
+        let minute_spinbutton = gtk::SpinButton::with_range(0.0, 59.0, 1.0);
+        minute_spinbutton.set_value(default.minute().into());
+        minute_spinbutton.set_orientation(gtk::Orientation::Vertical);
+
+        let time_box = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+        time_box.set_halign(gtk::Align::Center);
+        time_box.append(&hour_spinbutton);
+        time_box.append(&gtk::Label::new(Some(":")));
+        time_box.append(&minute_spinbutton);
+        dialog.message_area().downcast::<gtk::Box>().unwrap().append(&time_box);
+
+        dialog.connect_response(move |dialog, response| {
+            if response == gtk::ResponseType::Accept {
+                let now = Local::now();
+                let time = NaiveTime::from_hms_opt(hour_spinbutton.value_as_int() as u32, minute_spinbutton.value_as_int() as u32, 0);
+
+                // The time has already passed today -> use tomorrow
+                let target = time.and_then(|time| {
+                    (0..2)
+                        .filter_map(|days| {
+                            let target = (now.date().naive_local() + chrono::Duration::days(days)).and_time(time);
+
+                            // Times within a daylight saving time gap don't exist, they are moved behind the gap
+                            Local
+                                .from_local_datetime(&target)
+                                .earliest()
+                                .or_else(|| Local.from_local_datetime(&(target + chrono::Duration::hours(1))).earliest())
+                        })
+                        .find(|target| *target > now)
+                });
+
+                if let Some(target) = target {
+                    let duration = (target - now).to_std().unwrap_or_default();
+                    send!(sender, Action::PlaybackSetSleepTimer(Some(duration)));
+                }
+            }
+            dialog.close();
+        });
+
+        dialog.show();
+    }
+
+    /// Returns the rows of the stream info popover, only for values which are known.
+    fn stream_info_rows(info: &StreamInfo) -> Vec<(String, String)> {
+        let kbits = |bits: u32| i18n_f("{} kbit/s", &[&(bits / 1000).to_string()]);
+        let mut rows = Vec::new();
+
+        if let Some(codec) = &info.codec {
+            rows.push((i18n("Codec"), codec.clone()));
+        }
+        if let Some(bitrate) = info.nominal_bitrate {
+            rows.push((i18n("Nominal Bitrate"), kbits(bitrate)));
+        }
+        if let Some(bitrate) = info.bitrate {
+            rows.push((i18n("Bitrate"), kbits(bitrate)));
+        }
+        if let Some(channels) = info.channels {
+            let channels = match channels {
+                1 => i18n("Mono"),
+                2 => i18n("Stereo"),
+                _ => channels.to_string(),
+            };
+            rows.push((i18n("Channels"), channels));
+        }
+        if let Some(sample_rate) = info.sample_rate {
+            let khz = format!("{:.1}", f64::from(sample_rate) / 1000.0);
+            rows.push((i18n("Sample Rate"), i18n_f("{} kHz", &[&khz])));
+        }
+        if let Some(organization) = &info.organization {
+            rows.push((i18n("Organization"), organization.clone()));
+        }
+        if let Some(genre) = &info.genre {
+            rows.push((i18n("Genre"), genre.clone()));
+        }
+        if let Some(buffer_fill) = info.buffer_fill {
+            rows.push((i18n("Buffer"), format!("{} %", buffer_fill)));
+        }
+        if let Some(input_rate) = info.input_rate {
+            rows.push((i18n("Download Rate"), kbits(input_rate as u32 * 8)));
+        }
+
+        rows
     }
 }
 
@@ -171,10 +375,19 @@ impl Controller for SidebarController {
     }
 
     fn set_playback_state(&self, playback_state: &PlaybackState) {
+        let is_playing = matches!(playback_state, PlaybackState::Playing | PlaybackState::Paused);
+        self.record_button.set_sensitive(is_playing);
+        self.stop_playback_button.set_sensitive(is_playing);
+        self.reconnect_revealer.set_reveal_child(matches!(playback_state, PlaybackState::Reconnecting(_)));
+
         match playback_state {
-            PlaybackState::Playing => self.playback_button_stack.set_visible_child_name("stop_playback"),
-            PlaybackState::Stopped => self.playback_button_stack.set_visible_child_name("start_playback"),
+            PlaybackState::Playing => self.playback_button_stack.set_visible_child_name("pause_playback"),
+            PlaybackState::Paused | PlaybackState::Stopped => self.playback_button_stack.set_visible_child_name("start_playback"),
             PlaybackState::Loading => self.playback_button_stack.set_visible_child_name("loading"),
+            PlaybackState::Reconnecting(attempt) => {
+                self.playback_button_stack.set_visible_child_name("loading");
+                self.reconnect_label.set_text(&super::reconnect_text(*attempt));
+            }
             PlaybackState::Failure(msg) => {
                 self.playback_button_stack.set_visible_child_name("error");
                 let mut text = self.error_label.text().to_string();
@@ -191,10 +404,11 @@ impl Controller for SidebarController {
         glib::signal::signal_handler_unblock(&self.volume_button, &self.volume_signal_id);
     }
 
-    fn set_song_title(&self, title: &str) {
+    fn set_song_title(&self, title: &StreamTitle) {
+        let title = title.to_string();
         if !title.is_empty() {
-            self.subtitle_label.set_text(title);
-            self.subtitle_label.set_tooltip_text(Some(title));
+            self.subtitle_label.set_text(&title);
+            self.subtitle_label.set_tooltip_text(Some(&title));
             self.subtitle_revealer.set_reveal_child(true);
         } else {
             self.subtitle_label.set_text("");
@@ -202,4 +416,63 @@ impl Controller for SidebarController {
             self.subtitle_revealer.set_reveal_child(false);
         }
     }
+
+    fn set_sleep_timer(&self, remaining: Option<Duration>) {
+        let cancel_action = self.action_group.lookup_action("cancel-sleep-timer").unwrap().downcast::<gio::SimpleAction>().unwrap();
+        cancel_action.set_enabled(remaining.is_some());
+
+        if let Some(remaining) = remaining {
+            self.sleep_timer_label.set_text(&super::sleep_timer_text(remaining));
+            self.sleep_timer_revealer.set_reveal_child(true);
+        } else {
+            self.sleep_timer_revealer.set_reveal_child(false);
+        }
+    }
+
+    fn set_recording(&self, recording: bool) {
+        // We need to block the signal, otherwise we risk creating a endless loop
+        glib::signal::signal_handler_block(&self.record_button, &self.record_signal_id);
+        self.record_button.set_active(recording);
+        glib::signal::signal_handler_unblock(&self.record_button, &self.record_signal_id);
+    }
+
+    fn set_timeshift(&self, timeshift: Option<TimeshiftState>) {
+        self.timeshift_revealer.set_reveal_child(timeshift.is_some());
+
+        if let Some(timeshift) = timeshift {
+            let is_live = timeshift.delay.as_secs() == 0;
+            self.forward_button.set_sensitive(!is_live);
+            self.live_button.set_sensitive(!is_live);
+            self.timeshift_label.set_text(&super::timeshift_text(timeshift.delay));
+        }
+    }
+
+    fn set_stream_variant(&self, variant: Option<&StreamVariant>) {
+        self.stream_variant_label.set_visible(variant.is_some());
+
+        if let Some(variant) = variant {
+            self.stream_variant_label.set_text(&super::stream_variant_text(variant));
+            self.stream_variant_label.set_tooltip_text(variant.codecs.as_deref());
+        }
+    }
+
+    fn set_stream_info(&self, info: &StreamInfo) {
+        self.stream_info_button.set_sensitive(!info.is_empty());
+
+        while let Some(child) = self.stream_info_grid.first_child() {
+            self.stream_info_grid.remove(&child);
+        }
+
+        for (row, (title, value)) in Self::stream_info_rows(info).into_iter().enumerate() {
+            let title_label = gtk::Label::new(Some(&title));
+            title_label.set_xalign(1.0);
+            title_label.add_css_class("dim-label");
+            self.stream_info_grid.attach(&title_label, 0, row as i32, 1, 1);
+
+            let value_label = gtk::Label::new(Some(&value));
+            value_label.set_xalign(0.0);
+            value_label.set_selectable(true);
+            self.stream_info_grid.attach(&value_label, 1, row as i32, 1, 1);
+        }
+    }
 }
@@ -22,11 +22,12 @@ use gtk::prelude::*;
 
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::time::Duration;
 
-use crate::api::{FaviconDownloader, SwStation};
+use crate::api::{FaviconDownloader, StreamVariant, SwStation};
 use crate::app::Action;
 use crate::audio::Controller;
-use crate::audio::PlaybackState;
+use crate::audio::{PlaybackState, StreamInfo, StreamTitle, TimeshiftState};
 use crate::ui::{FaviconSize, StationFavicon, SwView};
 
 pub struct ToolbarController {
@@ -41,8 +42,12 @@ pub struct ToolbarController {
     action_revealer: gtk::Revealer,
     playback_button_stack: gtk::Stack,
     start_playback_button: gtk::Button,
-    stop_playback_button: gtk::Button,
+    pause_playback_button: gtk::Button,
     loading_button: gtk::Button,
+    record_button: gtk::ToggleButton,
+    record_signal_id: glib::signal::SignalHandlerId,
+    sleep_timer_revealer: gtk::Revealer,
+    sleep_timer_label: gtk::Label,
     toolbox_gesture: gtk::GestureClick,
 }
 
@@ -56,8 +61,11 @@ impl ToolbarController {
         get_widget!(builder, gtk::Revealer, action_revealer);
         get_widget!(builder, gtk::Stack, playback_button_stack);
         get_widget!(builder, gtk::Button, start_playback_button);
-        get_widget!(builder, gtk::Button, stop_playback_button);
+        get_widget!(builder, gtk::Button, pause_playback_button);
         get_widget!(builder, gtk::Button, loading_button);
+        get_widget!(builder, gtk::ToggleButton, record_button);
+        get_widget!(builder, gtk::Revealer, sleep_timer_revealer);
+        get_widget!(builder, gtk::Label, sleep_timer_label);
         get_widget!(builder, gtk::GestureClick, toolbox_gesture);
 
         let station = Rc::new(RefCell::new(None));
@@ -66,6 +74,11 @@ impl ToolbarController {
         let station_favicon = Rc::new(StationFavicon::new(FaviconSize::Mini));
         favicon_box.append(&station_favicon.widget);
 
+        // record_button | We need the record_signal_id later to block the signal
+        let record_signal_id = record_button.connect_toggled(clone!(@strong sender => move |button| {
+            send!(sender, Action::PlaybackSetRecording(button.is_active()));
+        }));
+
         let controller = Self {
             widget: toolbar_controller,
             sender,
@@ -77,8 +90,12 @@ impl ToolbarController {
             subtitle_revealer,
             playback_button_stack,
             start_playback_button,
-            stop_playback_button,
+            pause_playback_button,
             loading_button,
+            record_button,
+            record_signal_id,
+            sleep_timer_revealer,
+            sleep_timer_label,
             toolbox_gesture,
         };
 
@@ -92,9 +109,9 @@ impl ToolbarController {
             send!(sender, Action::PlaybackSet(true));
         }));
 
-        // stop_playback_button
-        self.stop_playback_button.connect_clicked(clone!(@strong self.sender as sender => move |_| {
-            send!(sender, Action::PlaybackSet(false));
+        // pause_playback_button
+        self.pause_playback_button.connect_clicked(clone!(@strong self.sender as sender => move |_| {
+            send!(sender, Action::PlaybackPause);
         }));
 
         // loading_button
@@ -133,10 +150,17 @@ impl Controller for ToolbarController {
     }
 
     fn set_playback_state(&self, playback_state: &PlaybackState) {
+        self.record_button.set_sensitive(matches!(playback_state, PlaybackState::Playing | PlaybackState::Paused));
+        self.loading_button.set_tooltip_text(None);
+
         match playback_state {
-            PlaybackState::Playing => self.playback_button_stack.set_visible_child_name("stop_playback"),
-            PlaybackState::Stopped => self.playback_button_stack.set_visible_child_name("start_playback"),
+            PlaybackState::Playing => self.playback_button_stack.set_visible_child_name("pause_playback"),
+            PlaybackState::Paused | PlaybackState::Stopped => self.playback_button_stack.set_visible_child_name("start_playback"),
             PlaybackState::Loading => self.playback_button_stack.set_visible_child_name("loading"),
+            PlaybackState::Reconnecting(attempt) => {
+                self.playback_button_stack.set_visible_child_name("loading");
+                self.loading_button.set_tooltip_text(Some(&super::reconnect_text(*attempt)));
+            }
             PlaybackState::Failure(_) => self.playback_button_stack.set_visible_child_name("start_playback"),
         };
     }
@@ -145,10 +169,11 @@ impl Controller for ToolbarController {
         // We don't have to do anything here.
     }
 
-    fn set_song_title(&self, title: &str) {
+    fn set_song_title(&self, title: &StreamTitle) {
+        let title = title.to_string();
         if !title.is_empty() {
-            self.subtitle_label.set_text(title);
-            self.subtitle_label.set_tooltip_text(Some(title));
+            self.subtitle_label.set_text(&title);
+            self.subtitle_label.set_tooltip_text(Some(&title));
             self.subtitle_revealer.set_reveal_child(true);
         } else {
             self.subtitle_label.set_text("");
@@ -156,4 +181,32 @@ impl Controller for ToolbarController {
             self.subtitle_revealer.set_reveal_child(false);
         }
     }
+
+    fn set_sleep_timer(&self, remaining: Option<Duration>) {
+        if let Some(remaining) = remaining {
+            self.sleep_timer_label.set_text(&super::sleep_timer_text(remaining));
+            self.sleep_timer_revealer.set_reveal_child(true);
+        } else {
+            self.sleep_timer_revealer.set_reveal_child(false);
+        }
+    }
+
+    fn set_recording(&self, recording: bool) {
+        // We need to block the signal, otherwise we risk creating a endless loop
+        glib::signal::signal_handler_block(&self.record_button, &self.record_signal_id);
+        self.record_button.set_active(recording);
+        glib::signal::signal_handler_unblock(&self.record_button, &self.record_signal_id);
+    }
+
+    fn set_timeshift(&self, _timeshift: Option<TimeshiftState>) {
+        // We don't have to do anything here.
+    }
+
+    fn set_stream_variant(&self, _variant: Option<&StreamVariant>) {
+        // We don't have to do anything here.
+    }
+
+    fn set_stream_info(&self, _info: &StreamInfo) {
+        // We don't have to do anything here.
+    }
 }
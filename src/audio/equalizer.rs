@@ -0,0 +1,138 @@
+// Shortwave - equalizer.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use gio::prelude::*;
+use gtk::gio;
+
+use crate::i18n::*;
+use crate::settings::{settings_manager, Key};
+
+/// Number of bands of the `equalizer-10bands` element.
+pub const EQUALIZER_BANDS: usize = 10;
+
+/// Center frequencies of the bands in Hz.
+pub const EQUALIZER_FREQUENCIES: [u32; EQUALIZER_BANDS] = [29, 59, 119, 237, 474, 947, 1889, 3770, 7523, 15011];
+
+/// The element allows cutting down to -24 dB, but a symmetric range is easier to handle for users.
+pub const EQUALIZER_MAX_GAIN: f64 = 12.0;
+
+/// Gain of each band in dB.
+pub type EqualizerGains = [f64; EQUALIZER_BANDS];
+
+/// Custom presets are identified by their name, the prefix keeps them apart from the built-in ones.
+const CUSTOM_PRESET_PREFIX: &str = "custom:";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EqualizerPreset {
+    /// Stable identifier, which gets stored in the settings / database.
+    pub id: String,
+    pub name: String,
+    pub gains: EqualizerGains,
+}
+
+impl EqualizerPreset {
+    fn new(id: &str, name: String, gains: EqualizerGains) -> Self {
+        Self { id: id.to_string(), name, gains }
+    }
+
+    /// The built-in presets, followed by the custom ones.
+    pub fn all() -> Vec<Self> {
+        let mut presets = Self::builtin();
+        presets.append(&mut Self::custom());
+        presets
+    }
+
+    pub fn builtin() -> Vec<Self> {
+        vec![
+            Self::new("flat", i18n("Flat"), [0.0; EQUALIZER_BANDS]),
+            // Removes rumble and lifts the frequencies which are important for intelligibility
+            Self::new("speech", i18n("Speech"), [-6.0, -5.0, -3.0, 0.0, 2.0, 4.0, 5.0, 4.0, 2.0, 0.0]),
+            Self::new("bass-boost", i18n("Bass Boost"), [6.0, 5.0, 4.0, 2.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+            Self::new("treble-boost", i18n("Treble Boost"), [0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 2.0, 4.0, 5.0, 6.0]),
+            Self::new("rock", i18n("Rock"), [5.0, 4.0, 2.0, -1.0, -2.0, -1.0, 2.0, 4.0, 5.0, 5.0]),
+            Self::new("pop", i18n("Pop"), [-1.0, 1.0, 3.0, 4.0, 3.0, 0.0, -1.0, -1.0, 1.0, 2.0]),
+            Self::new("jazz", i18n("Jazz"), [3.0, 2.0, 1.0, 2.0, -1.0, -1.0, 0.0, 1.0, 2.0, 3.0]),
+            Self::new("classical", i18n("Classical"), [4.0, 3.0, 2.0, 1.0, 0.0, 0.0, 0.0, 1.0, 2.0, 3.0]),
+        ]
+    }
+
+    pub fn custom() -> Vec<Self> {
+        custom_presets()
+            .into_iter()
+            .map(|(name, gains)| Self::new(&format!("{}{}", CUSTOM_PRESET_PREFIX, name), name, gains_from_vec(&gains)))
+            .collect()
+    }
+
+    pub fn by_id(id: &str) -> Option<Self> {
+        Self::all().into_iter().find(|preset| preset.id == id)
+    }
+
+    pub fn is_custom(&self) -> bool {
+        self.id.starts_with(CUSTOM_PRESET_PREFIX)
+    }
+
+    /// Stores the gains as custom preset. An existing custom preset with the same name gets replaced.
+    pub fn save_custom(name: &str, gains: &EqualizerGains) -> Self {
+        let mut presets = custom_presets();
+        presets.retain(|(n, _)| n != name);
+        presets.push((name.to_string(), gains.to_vec()));
+        set_custom_presets(&presets);
+
+        Self::new(&format!("{}{}", CUSTOM_PRESET_PREFIX, name), name.to_string(), *gains)
+    }
+
+    pub fn remove_custom(&self) {
+        if !self.is_custom() {
+            return;
+        }
+
+        let mut presets = custom_presets();
+        presets.retain(|(name, _)| *name != self.name);
+        set_custom_presets(&presets);
+    }
+}
+
+/// Returns the gains which are currently configured in the settings. They don't necessarily
+/// belong to a preset, since every band can be adjusted manually.
+pub fn equalizer_gains() -> EqualizerGains {
+    let settings = settings_manager::settings();
+    let gains: Vec<f64> = settings.get(&Key::EqualizerGains.to_string());
+    gains_from_vec(&gains)
+}
+
+pub fn set_equalizer_gains(gains: &EqualizerGains) {
+    let settings = settings_manager::settings();
+    settings.set(&Key::EqualizerGains.to_string(), &gains.to_vec()).unwrap();
+}
+
+fn custom_presets() -> Vec<(String, Vec<f64>)> {
+    let settings = settings_manager::settings();
+    settings.get(&Key::EqualizerCustomPresets.to_string())
+}
+
+fn set_custom_presets(presets: &[(String, Vec<f64>)]) {
+    let settings = settings_manager::settings();
+    settings.set(&Key::EqualizerCustomPresets.to_string(), &presets.to_vec()).unwrap();
+}
+
+/// Missing bands are treated as flat, values out of range get clamped.
+fn gains_from_vec(values: &[f64]) -> EqualizerGains {
+    let mut gains = [0.0; EQUALIZER_BANDS];
+    for (gain, value) in gains.iter_mut().zip(values) {
+        *gain = value.clamp(-EQUALIZER_MAX_GAIN, EQUALIZER_MAX_GAIN);
+    }
+    gains
+}
@@ -14,11 +14,27 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+mod alarm_scheduler;
 mod backend;
 mod controller;
+mod equalizer;
+mod recording_format;
+mod recording_scheduler;
+mod recording_tags;
+mod stream_info;
+mod stream_quality;
+mod title_parser;
 
+pub use alarm_scheduler::AlarmScheduler;
 pub use controller::Controller;
 pub use controller::GCastController;
+pub use equalizer::{equalizer_gains, set_equalizer_gains, EqualizerGains, EqualizerPreset, EQUALIZER_FREQUENCIES, EQUALIZER_MAX_GAIN};
+pub use recording_format::RecordingFormat;
+pub use recording_scheduler::RecordingScheduler;
+pub use recording_tags::recording_tags;
+pub use stream_info::StreamInfo;
+pub use stream_quality::StreamQuality;
+pub use title_parser::{StreamTitle, TitleParser};
 
 mod gcast_discoverer;
 mod player;
@@ -29,4 +45,5 @@ pub use gcast_discoverer::GCastDiscoverer;
 pub use gcast_discoverer::GCastDiscovererMessage;
 pub use player::PlaybackState;
 pub use player::Player;
+pub use player::TimeshiftState;
 pub use song::Song;
@@ -14,27 +14,32 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use chrono::Local;
 use glib::clone;
 use glib::Sender;
 use gtk::prelude::*;
 use gtk::{gio, glib};
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::convert::TryInto;
 use std::fs;
+use std::io;
 use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use crate::api::SwStation;
+use url::Url;
+
+use crate::api::{AdaptiveStream, PlaylistFormat, PlaylistResolver, StationMetadata, StreamProtocol, SwStation};
 use crate::app::Action;
 use crate::audio::backend::*;
 #[cfg(unix)]
 use crate::audio::controller::MprisController;
 use crate::audio::controller::{Controller, GCastController, InhibitController, MiniController, SidebarController, ToolbarController};
-use crate::audio::{GCastDevice, Song};
+use crate::audio::{equalizer_gains, recording_tags, EqualizerPreset, GCastDevice, Song, StreamQuality, StreamTitle, TitleParser};
 use crate::config;
+use crate::database::{AutoSaveRules, History, SongLog, StationSettings};
 use crate::i18n::*;
 use crate::path;
 use crate::settings::{settings_manager, Key};
@@ -66,24 +71,75 @@ use crate::ui::Notification;
 //                                                                                                             //
 /////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 
+/// Duration of the volume fade out before the sleep timer stops the playback.
+const SLEEP_TIMER_FADE_DURATION: Duration = Duration::from_secs(30);
+
+/// Duration of the volume ramp when an alarm starts the playback.
+const VOLUME_RAMP_DURATION: Duration = Duration::from_secs(60);
+
+/// Number of reconnection attempts before the playback fails.
+const RECONNECT_MAX_ATTEMPTS: u32 = 8;
+
+/// Longest delay between two reconnection attempts.
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// Number of reconnection attempts before the next stream URL of the station gets tried.
+const RECONNECT_ATTEMPTS_PER_URL: u32 = 2;
+
+/// The connection counts as stable again after playing for this duration.
+const RECONNECT_STABLE_DURATION: Duration = Duration::from_secs(30);
+
+/// The last played station, which gets persisted in the settings.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct LastStation {
+    uuid: String,
+    is_local: bool,
+    metadata: StationMetadata,
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub enum PlaybackState {
     Playing,
     Stopped,
+    Paused,
     Loading,
+    /// The stream got interrupted, contains the number of the reconnection attempt
+    Reconnecting(u32),
     Failure(String),
 }
 
+/// Position of the playback within the timeshift buffer.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TimeshiftState {
+    /// How far the playback is behind the live stream
+    pub delay: Duration,
+}
+
 pub struct Player {
     pub widget: gtk::Box,
     pub toolbar_controller_widget: gtk::Box,
     pub mini_controller_widget: gtk::Box,
     controller: Vec<Box<dyn Controller>>,
     gcast_controller: Rc<GCastController>,
+    inhibit_controller: Rc<InhibitController>,
 
     backend: Arc<Mutex<Backend>>,
     current_station: RefCell<Option<SwStation>>,
     song_title: RefCell<SongTitle>,
+    history: History,
+    sleep_timer: RefCell<Option<SleepTimer>>,
+    stream_recording: RefCell<Option<StreamRecording>>,
+    volume_ramp: RefCell<Option<glib::SourceId>>,
+    timeshift: RefCell<Option<TimeshiftState>>,
+    reconnect: RefCell<Option<Reconnect>>,
+    /// Recorded songs whose file isn't finalized yet, and whether they get saved automatically
+    finalizing_songs: RefCell<Vec<(Song, bool)>>,
+    /// Whether the playback should be running, reconnecting is only done in that case
+    playback_requested: Cell<bool>,
+    /// All stream URLs of the current station, in the order in which they get tried
+    stream_urls: RefCell<Vec<Url>>,
+    /// URL of the HLS / DASH stream whose variants got fetched
+    adaptive_stream_url: RefCell<Option<Url>>,
 
     builder: gtk::Builder,
     sender: Sender<Action>,
@@ -123,7 +179,7 @@ impl Player {
 
         // Inhibit Controller
         let inhibit_controller = InhibitController::new();
-        controller.push(Box::new(inhibit_controller));
+        controller.push(Box::new(inhibit_controller.clone()));
 
         let controller: Vec<Box<dyn Controller>> = controller;
 
@@ -138,15 +194,29 @@ impl Player {
         // Song title -> [Current Song] - [Previous Song]
         let song_title = RefCell::new(SongTitle::new());
 
+        // Listening history
+        let history = History::new();
+
         let player = Rc::new(Self {
             widget: player,
             toolbar_controller_widget,
             mini_controller_widget,
             controller,
             gcast_controller,
+            inhibit_controller,
             backend,
             current_station,
             song_title,
+            history,
+            sleep_timer: RefCell::default(),
+            stream_recording: RefCell::default(),
+            volume_ramp: RefCell::default(),
+            timeshift: RefCell::default(),
+            reconnect: RefCell::default(),
+            finalizing_songs: RefCell::default(),
+            playback_requested: Cell::new(false),
+            stream_urls: RefCell::default(),
+            adaptive_stream_url: RefCell::default(),
             builder,
             sender,
         });
@@ -159,15 +229,46 @@ impl Player {
         player
     }
 
-    pub fn set_station(&self, station: SwStation) {
+    pub fn set_station(self: Rc<Self>, station: SwStation) {
+        self.load_station(station, true);
+    }
+
+    /// Restores the last played station, and starts the playback if autoplay is enabled.
+    /// Returns `true` if a station got restored.
+    pub fn restore_last_station(self: Rc<Self>) -> bool {
+        let data = settings_manager::string(Key::PlaybackLastStation);
+        if data.is_empty() {
+            return false;
+        }
+
+        match serde_json::from_str::<LastStation>(&data) {
+            Ok(last) => {
+                debug!("Restore last station: {}", last.metadata.name);
+                let station = SwStation::new(last.uuid, last.is_local, last.metadata);
+                self.load_station(station, settings_manager::boolean(Key::PlaybackAutoplay));
+                true
+            }
+            Err(err) => {
+                warn!("Unable to restore last station: {}", err);
+                false
+            }
+        }
+    }
+
+    fn load_station(self: Rc<Self>, station: SwStation, autoplay: bool) {
+        // Close the history session of the previous station
+        self.history.stop_session();
+        self.cancel_reconnect();
+
         *self.current_station.borrow_mut() = Some(station.clone());
         self.set_playback(PlaybackState::Stopped);
+        self.update_equalizer();
+        self.update_loudness_normalization();
 
-        // SwStation is broken, we refuse to play it
+        // The station failed the last check of radio-browser.info, but the stream could be back again
         if station.metadata().lastcheckok != 1 {
-            let notification = Notification::new_info(&i18n("This station cannot be played because the stream is offline."));
+            let notification = Notification::new_info(&i18n("This station might be offline, the playback could fail."));
             send!(self.sender, Action::ViewShowNotification(notification));
-            return;
         }
 
         for con in &*self.controller {
@@ -177,11 +278,66 @@ impl Player {
         // Reset song title
         self.song_title.borrow_mut().reset();
 
-        match station.metadata().url_resolved {
-            Some(url) => {
+        // Remember station, so that it can be restored on the next start
+        let last_station = LastStation {
+            uuid: station.uuid(),
+            is_local: station.is_local(),
+            metadata: station.metadata(),
+        };
+        settings_manager::set_string(Key::PlaybackLastStation, serde_json::to_string(&last_station).unwrap());
+
+        let metadata = station.metadata();
+        *self.stream_urls.borrow_mut() = metadata.stream_urls();
+
+        // The station URL can be a playlist, which contains further stream URLs (e.g. mirrors).
+        // GStreamer can't play the playlist itself, so the playback only starts after resolving it.
+        if let Some(url) = metadata.url.clone().filter(|url| PlaylistFormat::for_url(url).is_some() && self.stream_urls.borrow().contains(url)) {
+            self.playback_requested.set(autoplay);
+
+            let uuid = station.uuid();
+            let future = clone!(@weak self as this => async move {
+                let result = PlaylistResolver::resolve(&url).await;
+
+                // The station got changed in the meantime
+                if this.current_station.borrow().as_ref().map(|station| station.uuid()) != Some(uuid) {
+                    return;
+                }
+
+                match result {
+                    Ok(urls) => this.add_stream_urls(&url, urls),
+                    Err(err) => warn!("Unable to resolve playlist {}: {}", url.to_string(), err),
+                }
+
+                // The playback could have been started or stopped while resolving
+                let autoplay = this.playback_requested.get();
+                this.start_stream(&metadata, autoplay);
+            });
+            spawn!(future);
+        } else {
+            self.start_stream(&metadata, autoplay);
+        }
+    }
+
+    fn start_stream(self: Rc<Self>, metadata: &StationMetadata, autoplay: bool) {
+        let url = self.stream_urls.borrow().first().cloned();
+        self.reset_stream_variant();
+
+        // The variants of adaptive streams are getting fetched early, if we know them in advance.
+        // Otherwise they are getting detected by the content type, as soon as the playback starts.
+        if let Some(url) = url.clone().filter(|url| autoplay && (metadata.hls == 1 || StreamProtocol::for_url(url).is_some())) {
+            self.clone().probe_adaptive_stream(url);
+        }
+
+        match url {
+            Some(url) if autoplay => {
                 debug!("Start playing new URI: {}", url.to_string());
+                self.playback_requested.set(true);
                 self.backend.lock().unwrap().gstreamer.new_source_uri(&url.to_string());
             }
+            Some(url) => {
+                debug!("Prepare new URI: {}", url.to_string());
+                self.backend.lock().unwrap().gstreamer.prepare_source_uri(&url.to_string());
+            }
             None => {
                 let notification = Notification::new_error(&i18n("Station cannot be streamed."), &i18n("URL is not valid."));
                 send!(self.sender, Action::ViewShowNotification(notification));
@@ -189,13 +345,105 @@ impl Player {
         }
     }
 
+    /// Replaces a playlist URL of the current station with the stream URLs it contains.
+    /// The playlist URL is kept if it doesn't contain any stream URLs.
+    fn add_stream_urls(&self, playlist_url: &Url, urls: Vec<Url>) {
+        if urls.is_empty() {
+            warn!("Playlist {} doesn't contain any stream URLs", playlist_url.to_string());
+            return;
+        }
+
+        let mut stream_urls = self.stream_urls.borrow_mut();
+        let position = stream_urls.iter().position(|url| url == playlist_url).unwrap_or(stream_urls.len());
+        stream_urls.retain(|url| url != playlist_url);
+
+        let urls: Vec<Url> = urls.into_iter().filter(|url| !stream_urls.contains(url)).collect();
+        debug!("Found {} additional stream URLs in playlist {}", urls.len(), playlist_url.to_string());
+
+        let position = position.min(stream_urls.len());
+        stream_urls.splice(position..position, urls);
+    }
+
+    /// Returns the stream URL which should be used for the given reconnection attempt.
+    /// Each URL gets tried a few times, before the next one gets used.
+    fn stream_url(&self, attempt: u32) -> Option<Url> {
+        let stream_urls = self.stream_urls.borrow();
+        if stream_urls.is_empty() {
+            return None;
+        }
+
+        let index = ((attempt.saturating_sub(1) / RECONNECT_ATTEMPTS_PER_URL) as usize) % stream_urls.len();
+        stream_urls.get(index).cloned()
+    }
+
+    /// Fetches the variants of a HLS / DASH stream, so that the chosen variant can be shown.
+    fn probe_adaptive_stream(self: Rc<Self>, url: Url) {
+        if self.adaptive_stream_url.borrow().as_ref() == Some(&url) {
+            return;
+        }
+        *self.adaptive_stream_url.borrow_mut() = Some(url.clone());
+
+        let future = clone!(@weak self as this => async move {
+            match AdaptiveStream::fetch(&url, true).await {
+                Ok(Some(stream)) => this.set_adaptive_stream(stream),
+                Ok(None) => debug!("{} is not an adaptive stream", url.to_string()),
+                Err(err) => warn!("Unable to fetch adaptive stream {}: {}", url.to_string(), err),
+            }
+        });
+        spawn!(future);
+    }
+
+    fn set_adaptive_stream(&self, stream: AdaptiveStream) {
+        // Another stream got started in the meantime
+        if self.adaptive_stream_url.borrow().as_ref() != Some(&stream.url) {
+            return;
+        }
+
+        // The demuxer chooses the same variant, based on the connection speed of the quality
+        let variant = StreamQuality::from_settings().select_variant(&stream.variants);
+        if let Some(variant) = variant {
+            debug!("Using {} variant with {} bit/s", stream.protocol.name(), variant.bandwidth);
+        }
+
+        for con in &*self.controller {
+            con.set_stream_variant(variant);
+        }
+    }
+
+    fn reset_stream_variant(&self) {
+        *self.adaptive_stream_url.borrow_mut() = None;
+
+        for con in &*self.controller {
+            con.set_stream_variant(None);
+        }
+    }
+
     pub fn set_playback(&self, playback: PlaybackState) {
         debug!("Set playback: {:?}", playback);
         match playback {
             PlaybackState::Playing => {
-                self.backend.lock().unwrap().gstreamer.set_state(gstreamer::State::Playing);
+                self.playback_requested.set(true);
+
+                let mut backend = self.backend.lock().unwrap();
+                if backend.gstreamer.state() == PlaybackState::Paused {
+                    backend.gstreamer.set_paused(false);
+                } else {
+                    backend.gstreamer.set_state(gstreamer::State::Playing);
+                }
+            }
+            PlaybackState::Paused => {
+                let backend = self.backend.lock().unwrap();
+                if backend.gstreamer.state() == PlaybackState::Playing {
+                    backend.gstreamer.set_paused(true);
+                }
             }
             PlaybackState::Stopped => {
+                self.playback_requested.set(false);
+                self.cancel_reconnect();
+
+                // Keep the data of a manual recording
+                self.stop_stream_recording();
+
                 let mut backend = self.backend.lock().unwrap();
 
                 // Discard recorded data when the stream stops
@@ -207,21 +455,187 @@ impl Player {
                 self.song_title.borrow_mut().reset();
 
                 backend.gstreamer.set_state(gstreamer::State::Null);
+                drop(backend);
+
+                // The sleep timer has stopped the playback, so we can reset it now
+                if self.sleep_timer.borrow().as_ref().map_or(false, |timer| timer.source_id.is_none()) {
+                    self.cancel_sleep_timer();
+                }
             }
             _ => (),
         }
     }
 
     pub fn toggle_playback(&self) {
-        if self.backend.lock().unwrap().gstreamer.state() == PlaybackState::Playing {
-            self.set_playback(PlaybackState::Stopped);
-        } else if self.backend.lock().unwrap().gstreamer.state() == PlaybackState::Stopped {
+        let state = self.backend.lock().unwrap().gstreamer.state();
+        match state {
+            PlaybackState::Playing => self.set_playback(PlaybackState::Paused),
+            PlaybackState::Paused | PlaybackState::Stopped => self.set_playback(PlaybackState::Playing),
+            _ => (),
+        }
+    }
+
+    /// Restarts the playback after the stream got interrupted, with an exponentially growing delay.
+    fn reconnect(self: Rc<Self>, msg: String) {
+        // The playback got stopped in the meantime. The pipeline state can't be used for this,
+        // since it's also not playing while a reconnection attempt fails.
+        if !self.playback_requested.get() {
+            return;
+        }
+
+        let mut reconnect = self.reconnect.borrow_mut();
+
+        // There can be multiple errors for the same interruption
+        if reconnect.as_ref().map_or(false, |r| r.source_id.is_some()) {
+            return;
+        }
+
+        let attempt = match reconnect.as_ref() {
+            Some(r) if r.last_attempt.elapsed() < RECONNECT_STABLE_DURATION => r.attempt + 1,
+            _ => 1,
+        };
+
+        // Discard recorded data, since the song has not been recorded completely.
+        // A manual recording gets finalized instead, since it's still useful.
+        if self.stream_recording.borrow().is_some() {
+            self.stop_stream_recording();
+        } else if self.backend.lock().unwrap().gstreamer.is_recording() {
+            self.backend.lock().unwrap().gstreamer.stop_recording(true);
+        }
+
+        // Make sure that every stream URL gets tried at least once
+        let max_attempts = RECONNECT_MAX_ATTEMPTS.max(self.stream_urls.borrow().len() as u32 * RECONNECT_ATTEMPTS_PER_URL);
+        if attempt > max_attempts {
+            warn!("Unable to reconnect: {}", msg);
+            *reconnect = None;
+            drop(reconnect);
+
+            self.process_gst_message(GstreamerMessage::PlaybackStateChanged(PlaybackState::Failure(msg)));
+            return;
+        }
+
+        let delay = Duration::from_secs(1 << (attempt - 1).min(6)).min(RECONNECT_MAX_DELAY);
+        warn!("Stream got interrupted: {} (Reconnecting in {:?}, attempt {})", msg, delay, attempt);
+
+        let source_id = glib::timeout_add_local(
+            delay,
+            clone!(@weak self as this => @default-return glib::Continue(false), move || {
+                if let Some(reconnect) = this.reconnect.borrow_mut().as_mut() {
+                    // The source gets removed by returning false
+                    reconnect.source_id = None;
+                    reconnect.last_attempt = Instant::now();
+                }
+
+                if let Some(url) = this.stream_url(attempt) {
+                    // The new URL doesn't have to be the same adaptive stream
+                    if this.adaptive_stream_url.borrow().as_ref() != Some(&url) {
+                        this.reset_stream_variant();
+                    }
+
+                    debug!("Reconnect to URI: {}", url.to_string());
+                    this.backend.lock().unwrap().gstreamer.new_source_uri(&url.to_string());
+                }
+                glib::Continue(false)
+            }),
+        );
+
+        *reconnect = Some(Reconnect {
+            attempt,
+            last_attempt: Instant::now(),
+            source_id: Some(source_id),
+        });
+        drop(reconnect);
+
+        for con in &*self.controller {
+            con.set_playback_state(&PlaybackState::Reconnecting(attempt));
+        }
+    }
+
+    fn cancel_reconnect(&self) {
+        if let Some(reconnect) = self.reconnect.borrow_mut().take() {
+            debug!("Cancel reconnecting");
+            if let Some(source_id) = reconnect.source_id {
+                glib::source::source_remove(source_id);
+            }
+        }
+    }
+
+    /// Moves the playback position within the timeshift buffer, negative values are rewinding.
+    pub fn seek(&self, offset: i64) {
+        self.backend.lock().unwrap().gstreamer.timeshift_seek(offset);
+        self.update_timeshift();
+    }
+
+    /// Jumps back to the live stream, and resumes the playback if it's paused.
+    pub fn go_live(&self) {
+        let state = {
+            let backend = self.backend.lock().unwrap();
+            backend.gstreamer.timeshift_go_live();
+            backend.gstreamer.state()
+        };
+
+        if state == PlaybackState::Paused {
             self.set_playback(PlaybackState::Playing);
         }
+        self.update_timeshift();
+    }
+
+    fn update_timeshift(&self) {
+        let backend = self.backend.lock().unwrap();
+        let is_playing = matches!(backend.gstreamer.state(), PlaybackState::Playing | PlaybackState::Paused);
+        let timeshift = if backend.gstreamer.timeshift_available() && is_playing {
+            Some(TimeshiftState {
+                // Only full seconds are getting displayed
+                delay: Duration::from_secs(backend.gstreamer.timeshift_delay().as_secs()),
+            })
+        } else {
+            None
+        };
+        drop(backend);
+
+        if *self.timeshift.borrow() != timeshift {
+            *self.timeshift.borrow_mut() = timeshift;
+            for con in &*self.controller {
+                con.set_timeshift(timeshift);
+            }
+        }
+    }
+
+    /// Applies the equalizer preset of the current station, or the global equalizer settings if
+    /// the station doesn't have its own preset.
+    pub fn update_equalizer(&self) {
+        let gains = if settings_manager::boolean(Key::EqualizerEnabled) {
+            let station_preset = self
+                .current_station
+                .borrow()
+                .as_ref()
+                .and_then(|station| StationSettings::get(&station.uuid()).equalizer_preset)
+                .and_then(|id| EqualizerPreset::by_id(&id));
+
+            Some(station_preset.map_or_else(equalizer_gains, |preset| preset.gains))
+        } else {
+            None
+        };
+
+        self.backend.lock().unwrap().gstreamer.set_equalizer(gains.as_ref());
+    }
+
+    /// The normalization gain is applied in addition to the playback volume.
+    pub fn update_loudness_normalization(&self) {
+        let enabled = settings_manager::boolean(Key::LoudnessNormalization);
+        let learned_gain = self.current_station.borrow().as_ref().and_then(|station| StationSettings::get(&station.uuid()).loudness_gain);
+
+        self.backend.lock().unwrap().gstreamer.set_loudness_normalization(enabled, learned_gain);
     }
 
     pub fn set_volume(&self, volume: f64) {
         debug!("Set volume: {}", &volume);
+
+        // A manual volume change stops a running volume ramp
+        if let Some(source_id) = self.volume_ramp.borrow_mut().take() {
+            glib::source::source_remove(source_id);
+        }
+
         self.backend.lock().unwrap().gstreamer.set_volume(volume);
 
         for con in &*self.controller {
@@ -229,6 +643,210 @@ impl Player {
         }
 
         settings_manager::set_double(Key::PlaybackVolume, volume);
+
+        // Make sure that the sleep timer restores the new volume
+        if let Some(timer) = self.sleep_timer.borrow_mut().as_mut() {
+            timer.volume = volume;
+        }
+    }
+
+    /// Slowly raises the volume from zero to the passed volume.
+    pub fn ramp_volume(self: Rc<Self>, volume: f64) {
+        debug!("Ramp volume up to {}", volume);
+        if let Some(source_id) = self.volume_ramp.borrow_mut().take() {
+            glib::source::source_remove(source_id);
+        }
+
+        self.backend.lock().unwrap().gstreamer.set_volume(0.0);
+
+        let start = Instant::now();
+        let source_id = glib::timeout_add_local(
+            Duration::from_millis(250),
+            clone!(@weak self as this => @default-return glib::Continue(false), move || {
+                let factor = start.elapsed().as_secs_f64() / VOLUME_RAMP_DURATION.as_secs_f64();
+                if factor < 1.0 {
+                    this.backend.lock().unwrap().gstreamer.set_volume(volume * factor);
+                    glib::Continue(true)
+                } else {
+                    // The source gets removed by returning false
+                    this.volume_ramp.borrow_mut().take();
+
+                    // The alarm volume only applies to the current playback, the volume which is
+                    // stored in the settings stays untouched
+                    this.backend.lock().unwrap().gstreamer.set_volume(volume);
+                    for con in &*this.controller {
+                        con.set_volume(volume);
+                    }
+                    glib::Continue(false)
+                }
+            }),
+        );
+        *self.volume_ramp.borrow_mut() = Some(source_id);
+    }
+
+    /// Starts or stops recording the whole stream, independent of the song titles.
+    pub fn set_recording(&self, recording: bool) {
+        if recording {
+            self.start_stream_recording();
+        } else {
+            self.stop_stream_recording();
+        }
+    }
+
+    fn start_stream_recording(&self) {
+        if self.stream_recording.borrow().is_some() {
+            return;
+        }
+
+        let station = match self.current_station.borrow().clone() {
+            Some(station) if matches!(self.backend.lock().unwrap().gstreamer.state(), PlaybackState::Playing | PlaybackState::Paused) => station,
+            _ => {
+                let notification = Notification::new_info(&i18n("Recording is only possible during playback."));
+                send!(self.sender, Action::ViewShowNotification(notification));
+
+                for con in &*self.controller {
+                    con.set_recording(false);
+                }
+                return;
+            }
+        };
+
+        let mut backend = self.backend.lock().unwrap();
+
+        // There can only be one recording at the same time,
+        // so the current song gets discarded
+        if backend.gstreamer.is_recording() {
+            backend.gstreamer.stop_recording(true);
+        }
+
+        let recording = StreamRecording {
+            station_name: station.metadata().name,
+            split_on_title: settings_manager::boolean(Key::RecorderSplitOnTitle),
+        };
+        let title = self.song_title.borrow().current_title();
+        let name = title.as_ref().map(|t| t.to_string());
+        let path = match recording.next_path(name.as_deref(), backend.gstreamer.recording_extension()) {
+            Ok(path) => path,
+            Err(err) => {
+                self.stream_recording_failed(err);
+                return;
+            }
+        };
+        backend.gstreamer.start_recording(path, recording_tags(&station, title.as_ref()));
+        *self.stream_recording.borrow_mut() = Some(recording);
+
+        for con in &*self.controller {
+            con.set_recording(true);
+        }
+    }
+
+    /// Ends the manual recording, if the recording file can't be created (e.g. the save path isn't writable).
+    fn stream_recording_failed(&self, err: io::Error) {
+        warn!("Unable to create recording file: {}", err);
+        *self.stream_recording.borrow_mut() = None;
+
+        let notification = Notification::new_error(&i18n("Unable to start recording."), &err.to_string());
+        send!(self.sender, Action::ViewShowNotification(notification));
+
+        for con in &*self.controller {
+            con.set_recording(false);
+        }
+    }
+
+    fn stop_stream_recording(&self) {
+        if self.stream_recording.borrow_mut().take().is_some() {
+            let mut backend = self.backend.lock().unwrap();
+            if backend.gstreamer.is_recording() {
+                backend.gstreamer.stop_recording(false);
+            }
+            drop(backend);
+
+            let notification = Notification::new_info(&i18n("Recording saved."));
+            send!(self.sender, Action::ViewShowNotification(notification));
+        }
+
+        for con in &*self.controller {
+            con.set_recording(false);
+        }
+    }
+
+    /// Returns the tags for a new recording of the current station.
+    fn recording_tags(&self, title: Option<&StreamTitle>) -> gstreamer::TagList {
+        match &*self.current_station.borrow() {
+            Some(station) => recording_tags(station, title),
+            None => gstreamer::TagList::new(),
+        }
+    }
+
+    /// Keeps the system awake while an alarm is pending.
+    pub fn set_alarm_pending(&self, pending: bool) {
+        self.inhibit_controller.set_alarm_pending(pending);
+    }
+
+    /// Stops the playback after the passed duration. `None` cancels the sleep timer.
+    pub fn set_sleep_timer(self: Rc<Self>, duration: Option<Duration>) {
+        self.cancel_sleep_timer();
+
+        if let Some(duration) = duration {
+            debug!("Set sleep timer: {:?}", duration);
+            let source_id = glib::timeout_add_local(
+                Duration::from_millis(250),
+                clone!(@weak self as this => @default-return glib::Continue(false), move || this.update_sleep_timer()),
+            );
+
+            *self.sleep_timer.borrow_mut() = Some(SleepTimer {
+                deadline: Instant::now() + duration,
+                volume: settings_manager::double(Key::PlaybackVolume),
+                source_id: Some(source_id),
+            });
+            self.update_sleep_timer();
+        }
+    }
+
+    fn cancel_sleep_timer(&self) {
+        if let Some(timer) = self.sleep_timer.borrow_mut().take() {
+            debug!("Reset sleep timer");
+            if let Some(source_id) = timer.source_id {
+                glib::source::source_remove(source_id);
+            }
+
+            // Restore the volume, which might got changed by fading out
+            self.backend.lock().unwrap().gstreamer.set_volume(timer.volume);
+
+            for con in &*self.controller {
+                con.set_sleep_timer(None);
+            }
+        }
+    }
+
+    fn update_sleep_timer(&self) -> glib::Continue {
+        let mut sleep_timer = self.sleep_timer.borrow_mut();
+        let timer = match sleep_timer.as_mut() {
+            Some(timer) => timer,
+            None => return glib::Continue(false),
+        };
+
+        let remaining = timer.deadline.saturating_duration_since(Instant::now());
+        for con in &*self.controller {
+            con.set_sleep_timer(Some(remaining));
+        }
+
+        if settings_manager::boolean(Key::SleepTimerFadeOut) && remaining < SLEEP_TIMER_FADE_DURATION {
+            let factor = remaining.as_secs_f64() / SLEEP_TIMER_FADE_DURATION.as_secs_f64();
+            self.backend.lock().unwrap().gstreamer.set_volume(timer.volume * factor);
+        }
+
+        if remaining == Duration::from_secs(0) {
+            debug!("Sleep timer expired, stop playback.");
+
+            // The timer gets reset as soon as the playback is stopped
+            timer.source_id = None;
+            send!(self.sender, Action::PlaybackSet(false));
+
+            return glib::Continue(false);
+        }
+
+        glib::Continue(true)
     }
 
     pub fn save_song(&self, song: Song) {
@@ -262,6 +880,15 @@ impl Player {
         let receiver = self.backend.clone().lock().unwrap().gstreamer_receiver.take().unwrap();
         receiver.attach(None, clone!(@strong self as this => move |message| this.clone().process_gst_message(message)));
 
+        // Keep the controllers updated about the position within the timeshift buffer
+        glib::timeout_add_local(
+            Duration::from_millis(500),
+            clone!(@weak self as this => @default-return glib::Continue(false), move || {
+                this.update_timeshift();
+                glib::Continue(true)
+            }),
+        );
+
         // Disconnect from gcast device
         get_widget!(self.builder, gtk::Button, disconnect_button);
         disconnect_button.connect_clicked(clone!(@strong self.sender as sender => move |_| {
@@ -269,39 +896,76 @@ impl Player {
         }));
     }
 
-    fn process_gst_message(&self, message: GstreamerMessage) -> glib::Continue {
+    fn process_gst_message(self: Rc<Self>, message: GstreamerMessage) -> glib::Continue {
         match message {
-            GstreamerMessage::SongTitleChanged(title) => {
+            GstreamerMessage::SongTitleChanged(raw_title) => {
                 let backend = &mut self.backend.lock().unwrap();
-                debug!("Song title has changed to: \"{}\"", title);
+                debug!("Song title has changed to: \"{}\"", raw_title);
 
-                // If we're already recording something, we need to stop it first.
-                if backend.gstreamer.is_recording() {
+                // Ads and jingles end the current song, but they don't get handled as a song themselves
+                let title = TitleParser::new(self.current_station.borrow().as_ref()).parse(&raw_title);
+
+                let mut recording_error = None;
+                let stream_recording = self.stream_recording.borrow();
+                if let Some(recording) = &*stream_recording {
+                    // A manual recording only gets interrupted if it should be split into multiple files
+                    if let (true, Some(title)) = (recording.split_on_title, &title) {
+                        backend.gstreamer.stop_recording(false);
+                        match recording.next_path(Some(&title.to_string()), backend.gstreamer.recording_extension()) {
+                            Ok(path) => backend.gstreamer.start_recording(path, self.recording_tags(Some(title))),
+                            Err(err) => recording_error = Some(err),
+                        }
+                    }
+                } else if backend.gstreamer.is_recording() {
+                    // If we're already recording something, we need to stop it first.
                     let threshold: i64 = settings_manager::integer(Key::RecorderSongDurationThreshold).try_into().unwrap();
                     let duration: i64 = backend.gstreamer.current_recording_duration();
                     if duration > threshold {
+                        let path = backend.gstreamer.recording_path().expect("Unable to get song path");
                         backend.gstreamer.stop_recording(false);
 
                         let duration = Duration::from_secs(duration.try_into().unwrap());
-                        let song = self.song_title.borrow().create_song(duration).expect("Unable to create new song");
+                        let station = self.current_station.borrow().clone();
+                        let station_name = station.as_ref().map(|s| s.metadata().name).unwrap_or_default();
+                        let song = self.song_title.borrow().create_song(&station_name, path, duration).expect("Unable to create new song");
 
-                        backend.song.add_song(song);
+                        // The song gets added as soon as the file is finalized, see `RecordingStopped`
+                        let auto_save = station.map_or(false, |s| StationSettings::get(&s.uuid()).auto_save || AutoSaveRules::matches(&song, &s.uuid()));
+                        self.finalizing_songs.borrow_mut().push((song, auto_save));
                     } else {
                         debug!("Discard recorded data, song duration ({} sec) is below threshold ({} sec).", duration, threshold);
                         backend.gstreamer.stop_recording(true);
                     }
                 }
 
+                drop(stream_recording);
+                if let Some(err) = recording_error {
+                    self.stream_recording_failed(err);
+                }
+                let is_stream_recording = self.stream_recording.borrow().is_some();
+
+                let title = match title {
+                    Some(title) => title,
+                    None => return glib::Continue(true),
+                };
+
                 // Set new song title
                 self.song_title.borrow_mut().set_current_title(title.clone());
+                self.history.add_song_title(&title.to_string());
+                if let Some(station) = &*self.current_station.borrow() {
+                    SongLog::add(station, &title.to_string());
+                }
                 for con in &*self.controller {
                     con.set_song_title(&title);
                 }
 
                 // Start recording new song
                 // We don't start recording the "first" detected song, since it is going to be incomplete
-                if !self.song_title.borrow().is_first_song() {
-                    backend.gstreamer.start_recording(self.song_title.borrow().current_path().expect("Unable to get song path"));
+                if is_stream_recording {
+                    debug!("Song will not be recorded separately, because the whole stream is being recorded.")
+                } else if !self.song_title.borrow().is_first_song() {
+                    let path = self.song_title.borrow().current_path(backend.gstreamer.recording_extension()).expect("Unable to get song path");
+                    backend.gstreamer.start_recording(path, self.recording_tags(Some(&title)));
                 } else {
                     debug!("Song will not be recorded because it may be incomplete (first song for this station).")
                 }
@@ -312,14 +976,70 @@ impl Player {
                 }
             }
             GstreamerMessage::PlaybackStateChanged(state) => {
+                // The pipeline keeps running while the playback is paused
+                let state = if state == PlaybackState::Playing && self.backend.lock().unwrap().gstreamer.is_paused() {
+                    PlaybackState::Paused
+                } else {
+                    state
+                };
+
                 for con in &*self.controller {
                     con.set_playback_state(&state);
                 }
 
                 // Discard recorded data when a failure occurs,
                 // since the song has not been recorded completely.
-                if self.backend.lock().unwrap().gstreamer.is_recording() && matches!(state, PlaybackState::Failure(_)) {
-                    self.backend.lock().unwrap().gstreamer.stop_recording(true);
+                // A manual recording gets finalized instead, since it's still useful.
+                if matches!(state, PlaybackState::Failure(_)) {
+                    self.playback_requested.set(false);
+
+                    if self.stream_recording.borrow().is_some() {
+                        self.stop_stream_recording();
+                    } else if self.backend.lock().unwrap().gstreamer.is_recording() {
+                        self.backend.lock().unwrap().gstreamer.stop_recording(true);
+                    }
+                }
+
+                // Update listening history
+                match state {
+                    PlaybackState::Playing => {
+                        if let Some(station) = &*self.current_station.borrow() {
+                            self.history.start_session(station);
+                        }
+                    }
+                    PlaybackState::Stopped | PlaybackState::Failure(_) => self.history.stop_session(),
+                    _ => (),
+                }
+            }
+            GstreamerMessage::StreamInterrupted(msg) => self.reconnect(msg),
+            GstreamerMessage::AdaptiveStreamDetected => {
+                let uri = self.backend.lock().unwrap().gstreamer.source_uri();
+                if let Some(url) = uri.and_then(|uri| Url::parse(&uri).ok()) {
+                    self.probe_adaptive_stream(url);
+                }
+            }
+            GstreamerMessage::StreamInfoChanged(info) => {
+                for con in &*self.controller {
+                    con.set_stream_info(&info);
+                }
+            }
+            GstreamerMessage::LoudnessGainChanged(gain) => {
+                // Remember the gain, so that the station has the right volume from the start next time
+                if let Some(station) = self.current_station.borrow().as_ref() {
+                    let mut settings = StationSettings::get(&station.uuid());
+                    settings.loudness_gain = Some(gain);
+                    StationSettings::set(settings);
+                }
+            }
+            GstreamerMessage::RecordingStopped(path) => {
+                // Songs can only be copied after the file has been finalized, otherwise it's incomplete
+                let position = self.finalizing_songs.borrow().iter().position(|(song, _)| song.path == path);
+                if let Some(position) = position {
+                    let (song, auto_save) = self.finalizing_songs.borrow_mut().remove(position);
+                    if let Err(err) = self.backend.lock().unwrap().song.add_song(song, auto_save) {
+                        let notification = Notification::new_error(&i18n("Cannot save song"), &err.to_string());
+                        send!(self.sender, Action::ViewShowNotification(notification))
+                    }
                 }
             }
         }
@@ -328,7 +1048,7 @@ impl Player {
 
     fn show_song_notification(&self) {
         let current_station = self.current_station.borrow().clone().unwrap();
-        let notification = gio::Notification::new(&self.song_title.borrow().current_title().unwrap());
+        let notification = gio::Notification::new(&self.song_title.borrow().current_title().unwrap().to_string());
         notification.set_body(Some(&current_station.metadata().name));
         //notification.add_button("Record and save this song", "app.record-and-save-song");
 
@@ -350,9 +1070,43 @@ impl Player {
     }
 }
 
+/// A manual recording of the whole stream, which runs until it gets stopped.
+struct StreamRecording {
+    station_name: String,
+    split_on_title: bool,
+}
+
+impl StreamRecording {
+    /// Returns the path of the next recording file, and creates the directory if needed.
+    fn next_path(&self, title: Option<&str>, extension: &str) -> io::Result<PathBuf> {
+        let mut name = format!("{} {}", self.station_name, Local::now().format("%Y-%m-%d %H-%M-%S"));
+        if let Some(title) = title.filter(|t| self.split_on_title && !t.is_empty()) {
+            name = format!("{} - {}", name, title);
+        }
+
+        let mut path = SongBackend::save_dir();
+        fs::create_dir_all(&path)?;
+        path.push(sanitize_filename::sanitize(format!("{}.{}", name, extension)));
+        Ok(path)
+    }
+}
+
+struct Reconnect {
+    attempt: u32,
+    last_attempt: Instant,
+    source_id: Option<glib::SourceId>,
+}
+
+struct SleepTimer {
+    deadline: Instant,
+    /// Volume before the fade out started
+    volume: f64,
+    source_id: Option<glib::SourceId>,
+}
+
 pub struct SongTitle {
-    current_title: Option<String>,
-    previous_title: Option<String>,
+    current_title: Option<StreamTitle>,
+    previous_title: Option<StreamTitle>,
 }
 
 impl SongTitle {
@@ -363,7 +1117,7 @@ impl SongTitle {
         }
     }
 
-    pub fn set_current_title(&mut self, title: String) {
+    pub fn set_current_title(&mut self, title: StreamTitle) {
         if self.current_title.is_none() {
             self.current_title = Some(title);
         } else {
@@ -372,24 +1126,23 @@ impl SongTitle {
         }
     }
 
-    pub fn current_title(&self) -> Option<String> {
+    pub fn current_title(&self) -> Option<StreamTitle> {
         self.current_title.clone()
     }
 
-    /// Returns song for current title
-    pub fn create_song(&self, duration: Duration) -> Option<Song> {
+    /// Returns song for current title, which got recorded to the passed path
+    pub fn create_song(&self, station_name: &str, path: PathBuf, duration: Duration) -> Option<Song> {
         if let Some(title) = &self.current_title {
-            let path = self.current_path().expect("Unable to get path for current song");
-            return Some(Song::new(&title, path, duration));
+            return Some(Song::new(title, station_name, path, duration));
         }
         None
     }
 
     /// Returns path for current title
-    fn current_path(&self) -> Option<PathBuf> {
+    fn current_path(&self, extension: &str) -> Option<PathBuf> {
         if let Some(title) = &self.current_title {
             let title = title.to_string();
-            let filename = sanitize_filename::sanitize(title.clone() + ".ogg");
+            let filename = sanitize_filename::sanitize(format!("{}.{}", title, extension));
 
             let mut path = path::CACHE.clone();
             path.push("recording");
@@ -0,0 +1,75 @@
+// Shortwave - recording_format.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::str::FromStr;
+
+use crate::settings::{settings_manager, Key};
+
+#[derive(Display, Copy, Debug, Clone, EnumString, PartialEq)]
+pub enum RecordingFormat {
+    Vorbis,
+    Opus,
+    Flac,
+    Mp3,
+    Aac,
+    /// Writes the original compressed stream, without re-encoding it.
+    Passthrough,
+}
+
+impl RecordingFormat {
+    /// All formats, in the same order as they are listed in the settings.
+    pub const ALL: [Self; 6] = [Self::Vorbis, Self::Opus, Self::Flac, Self::Mp3, Self::Aac, Self::Passthrough];
+
+    pub fn from_settings() -> Self {
+        let value = settings_manager::string(Key::RecorderFormat);
+        Self::from_str(&value).unwrap_or_else(|_| {
+            warn!("Unknown recording format \"{}\", using Vorbis instead", value);
+            Self::Vorbis
+        })
+    }
+
+    /// Returns the file extension. `None` for passthrough, since it depends on the stream.
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            Self::Vorbis => Some("ogg"),
+            Self::Opus => Some("opus"),
+            Self::Flac => Some("flac"),
+            Self::Mp3 => Some("mp3"),
+            Self::Aac => Some("m4a"),
+            Self::Passthrough => None,
+        }
+    }
+
+    /// Whether the bitrate setting has an effect on this format.
+    pub fn has_bitrate(&self) -> bool {
+        !matches!(self, Self::Flac | Self::Passthrough)
+    }
+
+    /// Returns the Gstreamer description of the encoder (and muxer) elements.
+    pub fn encoder_description(&self) -> Option<String> {
+        // kbit/s
+        let bitrate = settings_manager::integer(Key::RecorderBitrate);
+
+        match self {
+            Self::Vorbis => Some(format!("vorbisenc bitrate={} ! oggmux", bitrate * 1000)),
+            Self::Opus => Some(format!("opusenc bitrate={} ! oggmux", bitrate * 1000)),
+            Self::Flac => Some(String::from("flacenc")),
+            Self::Mp3 => Some(format!("lamemp3enc target=bitrate cbr=true bitrate={} ! id3v2mux", bitrate)),
+            Self::Aac => Some(format!("avenc_aac bitrate={} ! mp4mux", bitrate * 1000)),
+            Self::Passthrough => None,
+        }
+    }
+}
@@ -0,0 +1,300 @@
+// Shortwave - recording_scheduler.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use chrono::Local;
+use glib::clone;
+use glib::Sender;
+use gtk::glib;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::app::Action;
+use crate::audio::backend::{GstreamerBackend, GstreamerMessage};
+use crate::audio::{recording_tags, PlaybackState};
+use crate::database::{ScheduledRecordingEntry, ScheduledRecordings};
+use crate::i18n::*;
+use crate::ui::Notification;
+
+/// A scheduled recording which is currently running.
+struct Recorder {
+    entry: ScheduledRecordingEntry,
+    gstreamer: GstreamerBackend,
+    stop_source_id: Option<glib::SourceId>,
+    reconnect_source_id: Option<glib::SourceId>,
+    reconnect_attempt: u32,
+    /// Number of the file which gets recorded, a new file is started after reconnecting
+    part: u32,
+    stopping: bool,
+}
+
+/// Starts and stops scheduled recordings. Every recording uses its own pipeline,
+/// so it doesn't matter which station (if any) is currently being played.
+pub struct RecordingScheduler {
+    recordings: RefCell<Vec<ScheduledRecordingEntry>>,
+    recorders: RefCell<HashMap<i32, Recorder>>,
+    sender: Sender<Action>,
+}
+
+impl RecordingScheduler {
+    pub fn new(sender: Sender<Action>) -> Rc<Self> {
+        let scheduler = Rc::new(Self {
+            recordings: RefCell::new(ScheduledRecordings::entries()),
+            recorders: RefCell::default(),
+            sender,
+        });
+
+        glib::timeout_add_seconds_local(
+            10,
+            clone!(@weak scheduler => @default-return glib::Continue(false), move || {
+                scheduler.clone().check_recordings();
+                glib::Continue(true)
+            }),
+        );
+
+        scheduler
+    }
+
+    /// Reloads the scheduled recordings from the database. Running recordings
+    /// which got removed in the meantime are getting stopped.
+    pub fn reload(self: Rc<Self>) {
+        *self.recordings.borrow_mut() = ScheduledRecordings::entries();
+
+        let removed: Vec<i32> = self.recorders.borrow().keys().filter(|id| !self.recordings.borrow().iter().any(|e| e.id == **id)).cloned().collect();
+        for id in removed {
+            self.stop_recording(id);
+        }
+
+        self.check_recordings();
+    }
+
+    pub fn is_running(&self, id: i32) -> bool {
+        self.recorders.borrow().contains_key(&id)
+    }
+
+    fn check_recordings(self: Rc<Self>) {
+        let now = Local::now();
+
+        let recordings = self.recordings.borrow().clone();
+        for entry in recordings {
+            if self.is_running(entry.id) {
+                continue;
+            }
+
+            if entry.end_time() <= now {
+                warn!("Scheduled recording {} got missed (scheduled for {})", entry.id, entry.start_time());
+                ScheduledRecordings::remove(entry.id);
+                send!(self.sender, Action::ScheduledRecordingsChanged);
+            } else if entry.start_time() <= now {
+                self.clone().start_recording(entry);
+            }
+        }
+    }
+
+    fn start_recording(self: Rc<Self>, entry: ScheduledRecordingEntry) {
+        let id = entry.id;
+        let station = match entry.station() {
+            Some(station) => station,
+            None => {
+                warn!("Unable to start scheduled recording {}: Invalid station data", id);
+                return;
+            }
+        };
+
+        let url = match station.metadata().url_resolved {
+            Some(url) => url,
+            None => {
+                let notification = Notification::new_error(&i18n("Unable to start scheduled recording."), &i18n("URL is not valid."));
+                send!(self.sender, Action::ViewShowNotification(notification));
+                return;
+            }
+        };
+
+        info!("Start scheduled recording {} of \"{}\"", id, station.metadata().name);
+
+        let (gstreamer_sender, gstreamer_receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+        let mut gstreamer = GstreamerBackend::new_recorder(gstreamer_sender, self.sender.clone());
+        gstreamer_receiver.attach(
+            None,
+            clone!(@weak self as this => @default-return glib::Continue(false), move |message| {
+                this.process_gst_message(id, message);
+                glib::Continue(true)
+            }),
+        );
+        gstreamer.new_source_uri(&url.to_string());
+
+        // Stop the recording when the time window is over
+        let remaining = (entry.end_time() - Local::now()).num_seconds().max(1);
+        let stop_source_id = glib::timeout_add_seconds_local(
+            remaining.try_into().unwrap(),
+            clone!(@weak self as this => @default-return glib::Continue(false), move || {
+                if let Some(recorder) = this.recorders.borrow_mut().get_mut(&id) {
+                    // The source gets removed by returning false
+                    recorder.stop_source_id = None;
+                }
+                this.clone().stop_recording(id);
+                glib::Continue(false)
+            }),
+        );
+
+        let recorder = Recorder {
+            entry,
+            gstreamer,
+            stop_source_id: Some(stop_source_id),
+            reconnect_source_id: None,
+            reconnect_attempt: 0,
+            part: 0,
+            stopping: false,
+        };
+        self.recorders.borrow_mut().insert(id, recorder);
+    }
+
+    /// Finalizes the recording file. The recorder gets removed as soon as the file is written.
+    fn stop_recording(self: Rc<Self>, id: i32) {
+        let mut recorders = self.recorders.borrow_mut();
+        let recorder = match recorders.get_mut(&id) {
+            Some(recorder) => recorder,
+            None => return,
+        };
+
+        if let Some(source_id) = recorder.stop_source_id.take() {
+            glib::source::source_remove(source_id);
+        }
+        if let Some(source_id) = recorder.reconnect_source_id.take() {
+            glib::source::source_remove(source_id);
+        }
+
+        if recorder.gstreamer.is_recording() {
+            debug!("Stop scheduled recording {}", id);
+            recorder.stopping = true;
+            recorder.gstreamer.stop_recording(false);
+        } else {
+            drop(recorders);
+            self.finish_recording(id);
+        }
+    }
+
+    /// Finalizes the file which has been recorded so far, and connects to the station again.
+    /// The recording continues in a new file.
+    fn reconnect(self: Rc<Self>, id: i32, msg: String) {
+        let mut recorders = self.recorders.borrow_mut();
+        let recorder = match recorders.get_mut(&id) {
+            Some(recorder) if !recorder.stopping => recorder,
+            _ => return,
+        };
+
+        // There can be multiple errors for the same interruption
+        if recorder.reconnect_source_id.is_some() {
+            return;
+        }
+
+        if recorder.gstreamer.is_recording() {
+            recorder.gstreamer.stop_recording(false);
+        }
+
+        // There's no limit for the number of attempts, the recording gets stopped at the end time anyway
+        recorder.reconnect_attempt += 1;
+        let delay = Duration::from_secs(1 << (recorder.reconnect_attempt - 1).min(5));
+        warn!("Scheduled recording {} got interrupted: {} (Reconnecting in {:?})", id, msg, delay);
+
+        let source_id = glib::timeout_add_local(
+            delay,
+            clone!(@weak self as this => @default-return glib::Continue(false), move || {
+                if let Some(recorder) = this.recorders.borrow_mut().get_mut(&id) {
+                    // The source gets removed by returning false
+                    recorder.reconnect_source_id = None;
+
+                    if let Some(url) = recorder.entry.station().and_then(|station| station.metadata().url_resolved) {
+                        recorder.gstreamer.new_source_uri(&url.to_string());
+                    }
+                }
+                glib::Continue(false)
+            }),
+        );
+        recorder.reconnect_source_id = Some(source_id);
+    }
+
+    fn finish_recording(&self, id: i32) {
+        if let Some(recorder) = self.recorders.borrow_mut().remove(&id) {
+            info!("Finished scheduled recording {} ({:?})", id, recorder.entry.path());
+        }
+
+        ScheduledRecordings::remove(id);
+        send!(self.sender, Action::ScheduledRecordingsChanged);
+    }
+
+    fn process_gst_message(self: Rc<Self>, id: i32, message: GstreamerMessage) {
+        match message {
+            GstreamerMessage::PlaybackStateChanged(PlaybackState::Playing) => {
+                if let Some(recorder) = self.recorders.borrow_mut().get_mut(&id) {
+                    // Buffering can cause multiple state changes, only start recording once
+                    if !recorder.stopping && !recorder.gstreamer.is_recording() {
+                        let mut path = recorder.entry.path();
+                        recorder.reconnect_attempt = 0;
+                        recorder.part += 1;
+
+                        // After reconnecting, the recording continues in a new file
+                        if recorder.part > 1 {
+                            let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+                            let mut name = format!("{} ({})", stem, recorder.part);
+                            if let Some(extension) = path.extension() {
+                                name.push_str(&format!(".{}", extension.to_string_lossy()));
+                            }
+                            path.set_file_name(name);
+                        }
+
+                        // Make sure that the file extension matches the recording format
+                        let extension = recorder.gstreamer.recording_extension();
+                        if path.extension().map_or(true, |e| e != extension) {
+                            let mut name = path.into_os_string();
+                            name.push(format!(".{}", extension));
+                            path = PathBuf::from(name);
+                        }
+
+                        let tags = match recorder.entry.station() {
+                            Some(station) => recording_tags(&station, None),
+                            None => gstreamer::TagList::new(),
+                        };
+                        recorder.gstreamer.start_recording(path, tags);
+                    }
+                }
+            }
+            GstreamerMessage::StreamInterrupted(msg) => self.reconnect(id, msg),
+            GstreamerMessage::PlaybackStateChanged(PlaybackState::Failure(msg)) => {
+                warn!("Scheduled recording {} failed: {}", id, msg);
+                let notification = Notification::new_error(&i18n("Scheduled recording failed."), &msg);
+                send!(self.sender, Action::ViewShowNotification(notification));
+
+                self.finish_recording(id);
+            }
+            // A file which got finalized because of an interruption
+            GstreamerMessage::RecordingStopped(_) if !self.recorders.borrow().get(&id).map_or(false, |recorder| recorder.stopping) => {
+                debug!("Finalized interrupted part of scheduled recording {}", id);
+            }
+            GstreamerMessage::RecordingStopped(_) => {
+                let notification = Notification::new_info(&i18n("Scheduled recording finished."));
+                send!(self.sender, Action::ViewShowNotification(notification));
+
+                self.finish_recording(id);
+            }
+            _ => (),
+        }
+    }
+}
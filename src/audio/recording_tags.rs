@@ -0,0 +1,77 @@
+// Shortwave - recording_tags.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use gstreamer::tags::{Album, Artist, Comment, DateTime, Image, Title};
+use gstreamer::{TagList, TagMergeMode};
+use gtk::gdk_pixbuf::Pixbuf;
+
+use crate::api::{FaviconDownloader, SwStation};
+use crate::audio::StreamTitle;
+
+/// Creates the tags which get written into a recorded file.
+///
+/// The station name is used as album.
+pub fn recording_tags(station: &SwStation, title: Option<&StreamTitle>) -> TagList {
+    let metadata = station.metadata();
+    let mut tags = TagList::new();
+
+    {
+        let tags = tags.get_mut().unwrap();
+        let mode = TagMergeMode::Replace;
+
+        if let Some(title) = title {
+            if let Some(artist) = &title.artist {
+                tags.add::<Artist>(&artist.as_str(), mode);
+            }
+            tags.add::<Title>(&title.title.as_str(), mode);
+        }
+
+        tags.add::<Album>(&metadata.name.as_str(), mode);
+        tags.add::<DateTime>(&gstreamer::DateTime::new_now_local_time(), mode);
+
+        if let Some(url) = metadata.url_resolved.as_ref().or_else(|| metadata.url.as_ref()) {
+            tags.add::<Comment>(&url.as_str(), mode);
+        }
+
+        if let Some(cover) = cover_sample(station) {
+            tags.add::<Image>(&cover, mode);
+        }
+    }
+
+    tags
+}
+
+/// Converts the cached station favicon into a PNG image sample.
+fn cover_sample(station: &SwStation) -> Option<gstreamer::Sample> {
+    let url = station.metadata().favicon?;
+    let path = FaviconDownloader::file(&url).ok()?.path()?;
+    if !path.exists() {
+        return None;
+    }
+
+    let pixbuf = match Pixbuf::from_file(&path) {
+        Ok(pixbuf) => pixbuf,
+        Err(err) => {
+            warn!("Unable to load station favicon as cover: {}", err);
+            return None;
+        }
+    };
+    let data = pixbuf.save_to_bufferv("png", &[]).ok()?;
+
+    let buffer = gstreamer::Buffer::from_mut_slice(data);
+    let caps = gstreamer::Caps::builder("image/png").build();
+    Some(gstreamer::Sample::builder().buffer(&buffer).caps(&caps).build())
+}
@@ -14,25 +14,73 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use chrono::{DateTime, Local};
+
 use std::path::PathBuf;
 use std::time::Duration;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::audio::StreamTitle;
+use crate::i18n::*;
+
+#[derive(Debug, Clone)]
 pub struct Song {
     pub title: String,
+    pub stream_title: StreamTitle,
+    pub station_name: String,
     pub path: PathBuf,
     pub duration: Duration,
+    /// Time when the recording of this song started
+    pub recorded: DateTime<Local>,
 }
 
 impl Song {
-    pub fn new(title: &str, path: PathBuf, duration: Duration) -> Self {
-        debug!("Created new song: \"{}\", {:?}", title, path);
+    pub fn new(stream_title: &StreamTitle, station_name: &str, path: PathBuf, duration: Duration) -> Self {
+        debug!("Created new song: \"{}\", {:?}", stream_title, path);
+
+        let recorded = Local::now() - chrono::Duration::from_std(duration).unwrap_or_else(|_| chrono::Duration::zero());
 
         Self {
-            title: title.to_string(),
+            title: stream_title.to_string(),
+            stream_title: stream_title.clone(),
+            station_name: station_name.to_string(),
             path,
             duration,
+            recorded,
+        }
+    }
+
+    /// Returns the relative path of the saved song, based on a template like
+    /// `{station}/{artist} - {title} ({date}).{ext}`.
+    ///
+    /// Every placeholder value gets sanitized, so it can't introduce additional directories.
+    pub fn file_path(&self, template: &str) -> PathBuf {
+        let artist = self.stream_title.artist.clone().unwrap_or_else(|| i18n("Unknown Artist"));
+        let extension = self.path.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+
+        let values = [
+            ("{station}", self.station_name.clone()),
+            ("{artist}", artist),
+            ("{title}", self.stream_title.title.clone()),
+            ("{song}", self.title.clone()),
+            ("{date}", self.recorded.format("%Y-%m-%d").to_string()),
+            ("{time}", self.recorded.format("%H-%M").to_string()),
+            ("{ext}", extension),
+        ];
+
+        let mut path = PathBuf::new();
+        for component in template.split('/') {
+            let mut component = component.to_string();
+            for (placeholder, value) in &values {
+                component = component.replace(placeholder, &sanitize_filename::sanitize(value));
+            }
+
+            let component = sanitize_filename::sanitize(component.trim());
+            if !component.is_empty() && component != "." && component != ".." {
+                path.push(component);
+            }
         }
+
+        path
     }
 }
 
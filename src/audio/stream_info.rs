@@ -0,0 +1,82 @@
+// Shortwave - stream_info.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use gstreamer::{tags, TagList};
+
+/// Technical details about the stream which is currently being played. Unlike the values of
+/// radio-browser.info, they are determined by Gstreamer, so they are describing the actual stream.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct StreamInfo {
+    pub codec: Option<String>,
+    /// Bitrate in bit/s, as announced by the stream
+    pub nominal_bitrate: Option<u32>,
+    /// Bitrate in bit/s, as measured by the decoder
+    pub bitrate: Option<u32>,
+    pub channels: Option<i32>,
+    /// Sample rate in Hz
+    pub sample_rate: Option<i32>,
+    pub organization: Option<String>,
+    pub genre: Option<String>,
+    /// Fill level of the network buffer in percent
+    pub buffer_fill: Option<i32>,
+    /// Average rate of the incoming data in bytes/s
+    pub input_rate: Option<i32>,
+}
+
+impl StreamInfo {
+    /// Takes over the values of the tags, returns `true` if anything has changed.
+    pub fn update_tags(&mut self, tags: &TagList) -> bool {
+        let previous = self.clone();
+
+        if let Some(codec) = tags.get::<tags::AudioCodec>() {
+            self.codec = Some(codec.get().to_string());
+        }
+        if let Some(bitrate) = tags.get::<tags::NominalBitrate>() {
+            self.nominal_bitrate = Some(bitrate.get()).filter(|b| *b > 0);
+        }
+        if let Some(bitrate) = tags.get::<tags::Bitrate>() {
+            self.bitrate = Some(bitrate.get()).filter(|b| *b > 0);
+        }
+        if let Some(organization) = tags.get::<tags::Organization>() {
+            self.organization = Some(organization.get().to_string()).filter(|o| !o.is_empty());
+        }
+        if let Some(genre) = tags.get::<tags::Genre>() {
+            self.genre = Some(genre.get().to_string()).filter(|g| !g.is_empty());
+        }
+
+        *self != previous
+    }
+
+    /// Takes over the format of the decoded audio, returns `true` if anything has changed.
+    pub fn update_format(&mut self, channels: Option<i32>, sample_rate: Option<i32>) -> bool {
+        let previous = self.clone();
+        self.channels = channels;
+        self.sample_rate = sample_rate;
+        *self != previous
+    }
+
+    /// Takes over the buffering statistics, returns `true` if anything has changed.
+    pub fn update_buffering(&mut self, percent: i32, input_rate: i32) -> bool {
+        let previous = self.clone();
+        self.buffer_fill = Some(percent);
+        self.input_rate = Some(input_rate).filter(|r| *r > 0);
+        *self != previous
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
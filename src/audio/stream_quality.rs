@@ -0,0 +1,63 @@
+// Shortwave - stream_quality.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::str::FromStr;
+
+use crate::api::StreamVariant;
+use crate::settings::{settings_manager, Key};
+
+/// Preference for choosing a variant of adaptive (HLS / DASH) streams.
+#[derive(Display, Copy, Debug, Clone, EnumString, PartialEq)]
+pub enum StreamQuality {
+    /// Always uses the variant with the highest bandwidth.
+    High,
+    /// Uses the best variant up to 128 kbit/s.
+    Normal,
+    /// Always uses the variant with the lowest bandwidth, to save data.
+    Low,
+}
+
+impl StreamQuality {
+    /// All qualities, in the same order as they are listed in the settings.
+    pub const ALL: [Self; 3] = [Self::High, Self::Normal, Self::Low];
+
+    pub fn from_settings() -> Self {
+        let value = settings_manager::string(Key::StreamQuality);
+        Self::from_str(&value).unwrap_or_else(|_| {
+            warn!("Unknown stream quality \"{}\", using High instead", value);
+            Self::High
+        })
+    }
+
+    /// Returns the connection speed in kbit/s, which gets passed to the adaptive demuxers.
+    /// They are choosing the best variant which fits into this bandwidth, or the lowest one if
+    /// none fits.
+    pub fn connection_speed(&self) -> u64 {
+        match self {
+            Self::High => 100_000,
+            Self::Normal => 128,
+            Self::Low => 1,
+        }
+    }
+
+    /// Returns the variant which gets chosen by the demuxer, the variants have to be sorted by
+    /// bandwidth in ascending order. This only matches if the `bitrate-limit` of the demuxer
+    /// is set to 1.0, so that the whole connection speed gets used.
+    pub fn select_variant<'a>(&self, variants: &'a [StreamVariant]) -> Option<&'a StreamVariant> {
+        let max_bandwidth = self.connection_speed() * 1000;
+        variants.iter().rev().find(|v| u64::from(v.bandwidth) <= max_bandwidth).or_else(|| variants.first())
+    }
+}
@@ -0,0 +1,273 @@
+// Shortwave - title_parser.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use gio::prelude::*;
+use gtk::gio;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use std::fmt;
+
+use crate::api::SwStation;
+use crate::settings::{settings_manager, Key};
+
+/// Separators which are commonly used between artist and title.
+const SEPARATORS: [&str; 4] = [" - ", " – ", " — ", " ~ "];
+
+/// Metadata format used by some station networks, for example:
+/// `Artist - text="Title" song_spot="M" MediaBaseId="123"`
+static EXTENDED_METADATA: Lazy<Regex> = Lazy::new(|| Regex::new(r#"^(?P<artist>.*?)\s*-?\s*text="(?P<title>[^"]*)"(?P<extra>.*)$"#).unwrap());
+static SONG_SPOT: Lazy<Regex> = Lazy::new(|| Regex::new(r#"song_spot="(?P<spot>[^"]*)""#).unwrap());
+
+/// Titles which are sent during ads, jingles or station announcements.
+static ADVERT: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)\b(adverts?|advertisements?|commercials?|werbung|jingles?|station id|ad ?break|sponsored)\b").unwrap());
+
+/// A song title, split into artist and title if possible.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamTitle {
+    /// The title like it got sent by the station
+    pub raw: String,
+    pub artist: Option<String>,
+    pub title: String,
+}
+
+impl fmt::Display for StreamTitle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.artist {
+            Some(artist) => write!(f, "{} - {}", artist, self.title),
+            None => write!(f, "{}", self.title),
+        }
+    }
+}
+
+impl StreamTitle {
+    fn new(raw: &str, artist: Option<&str>, title: &str) -> Self {
+        let artist = artist.map(|a| a.trim().to_string()).filter(|a| !a.is_empty());
+
+        Self {
+            raw: raw.to_string(),
+            artist,
+            title: title.trim().to_string(),
+        }
+    }
+}
+
+/// Parses the raw ICY titles of stations.
+///
+/// Custom patterns can be configured with the `song-title-patterns` settings key. Each entry
+/// consists of a station name (empty for all stations) and a regex with the named groups
+/// `artist` and `title`. Custom patterns are preferred over the built-in ones.
+pub struct TitleParser {
+    station_name: Option<String>,
+    patterns: Vec<Regex>,
+}
+
+impl TitleParser {
+    pub fn new(station: Option<&SwStation>) -> Self {
+        let station_name = station.map(|s| s.metadata().name.trim().to_string());
+
+        let settings = settings_manager::settings();
+        let entries: Vec<(String, String)> = settings.get(&Key::SongTitlePatterns.to_string());
+
+        let patterns = entries
+            .into_iter()
+            .filter(|(station, _)| station.is_empty() || station_name.as_ref().map_or(false, |n| n.eq_ignore_ascii_case(station.trim())))
+            .filter_map(|(_, pattern)| match Regex::new(&pattern) {
+                Ok(regex) => Some(regex),
+                Err(err) => {
+                    warn!("Invalid song title pattern \"{}\": {}", pattern, err);
+                    None
+                }
+            })
+            .collect();
+
+        Self { station_name, patterns }
+    }
+
+    /// Returns `None` if the title is empty, or if it belongs to an ad / jingle.
+    pub fn parse(&self, raw: &str) -> Option<StreamTitle> {
+        // Some stations are wrapping the whole title in quotes. Single quotes at only one end are
+        // part of the title (e.g. "'Til Tuesday"), and so are the quotes of extended metadata.
+        let mut text = raw.trim();
+        for quote in &['\'', '"'] {
+            if text.len() >= 2 && text.starts_with(*quote) && text.ends_with(*quote) {
+                text = text[1..text.len() - 1].trim();
+            }
+        }
+
+        if text.is_empty() {
+            return None;
+        }
+
+        // Stations which only send their own name in between songs
+        if let Some(station_name) = &self.station_name {
+            if text.eq_ignore_ascii_case(station_name) {
+                debug!("Ignore song title \"{}\", it only contains the station name", text);
+                return None;
+            }
+        }
+
+        for regex in &self.patterns {
+            if let Some(captures) = regex.captures(text) {
+                if let Some(title) = captures.name("title").filter(|t| !t.as_str().trim().is_empty()) {
+                    return Some(StreamTitle::new(raw, captures.name("artist").map(|a| a.as_str()), title.as_str()));
+                }
+            }
+        }
+
+        if let Some(captures) = EXTENDED_METADATA.captures(text) {
+            // Everything which isn't marked as music is an ad or an announcement
+            let extra = captures.name("extra").map_or("", |e| e.as_str());
+            if let Some(spot) = SONG_SPOT.captures(extra).and_then(|c| c.name("spot")) {
+                if spot.as_str() != "M" {
+                    debug!("Ignore song title \"{}\", it's not marked as music", text);
+                    return None;
+                }
+            }
+
+            let title = captures.name("title").map_or("", |t| t.as_str());
+            if !title.trim().is_empty() {
+                return Some(StreamTitle::new(raw, captures.name("artist").map(|a| a.as_str()), title));
+            }
+        }
+
+        for separator in &SEPARATORS {
+            let mut parts = text.splitn(2, separator);
+            if let (Some(artist), Some(title)) = (parts.next(), parts.next()) {
+                if !artist.trim().is_empty() && !title.trim().is_empty() {
+                    return Some(StreamTitle::new(raw, Some(artist), title));
+                }
+            }
+        }
+
+        // Without an artist there's a good chance that it isn't a song
+        if ADVERT.is_match(text) {
+            debug!("Ignore song title \"{}\", it looks like an ad or jingle", text);
+            return None;
+        }
+
+        Some(StreamTitle::new(raw, None, text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parser(station_name: Option<&str>, patterns: &[&str]) -> TitleParser {
+        TitleParser {
+            station_name: station_name.map(str::to_string),
+            patterns: patterns.iter().map(|p| Regex::new(p).unwrap()).collect(),
+        }
+    }
+
+    fn parse(raw: &str) -> Option<(Option<String>, String)> {
+        parser(None, &[]).parse(raw).map(|t| (t.artist, t.title))
+    }
+
+    fn song(artist: Option<&str>, title: &str) -> Option<(Option<String>, String)> {
+        Some((artist.map(str::to_string), title.to_string()))
+    }
+
+    #[test]
+    fn separators() {
+        assert_eq!(parse("Daft Punk - One More Time"), song(Some("Daft Punk"), "One More Time"));
+        assert_eq!(parse("Daft Punk – One More Time"), song(Some("Daft Punk"), "One More Time"));
+        assert_eq!(parse("Daft Punk — One More Time"), song(Some("Daft Punk"), "One More Time"));
+        assert_eq!(parse("Daft Punk ~ One More Time"), song(Some("Daft Punk"), "One More Time"));
+
+        // Only the first separator splits, the title can contain further ones
+        assert_eq!(parse("AC/DC - Back In Black - Live"), song(Some("AC/DC"), "Back In Black - Live"));
+
+        // Hyphens without spaces are part of the name
+        assert_eq!(parse("Jay-Z"), song(None, "Jay-Z"));
+        assert_eq!(parse(" - Untitled"), song(None, "- Untitled"));
+    }
+
+    #[test]
+    fn quotes_and_whitespace() {
+        assert_eq!(parse("  'Daft Punk - Aerodynamic'  "), song(Some("Daft Punk"), "Aerodynamic"));
+        assert_eq!(parse("\"Air - La femme d'argent\""), song(Some("Air"), "La femme d'argent"));
+        assert_eq!(parse("   "), None);
+        assert_eq!(parse("''"), None);
+
+        // Quotes which are part of the title
+        assert_eq!(parse("'Til Tuesday - Voices Carry"), song(Some("'Til Tuesday"), "Voices Carry"));
+        assert_eq!(parse("Guns N' Roses - Don't Cry"), song(Some("Guns N' Roses"), "Don't Cry"));
+    }
+
+    #[test]
+    fn raw_title_is_kept() {
+        let title = parser(None, &[]).parse(" Moby - Porcelain ").unwrap();
+        assert_eq!(title.raw, " Moby - Porcelain ");
+        assert_eq!(title.to_string(), "Moby - Porcelain");
+    }
+
+    #[test]
+    fn extended_metadata() {
+        assert_eq!(
+            parse(r#"Dua Lipa - text="Levitating" song_spot="M" MediaBaseId="2215823" itunesTrackId="0" amgTrackId="-1""#),
+            song(Some("Dua Lipa"), "Levitating")
+        );
+        assert_eq!(parse(r#"Dua Lipa text="Levitating" song_spot="M""#), song(Some("Dua Lipa"), "Levitating"));
+
+        // Titles without song_spot are treated as music
+        assert_eq!(parse(r#"text="Levitating""#), song(None, "Levitating"));
+
+        // Everything else are ads, traffic news, station promos, ...
+        assert_eq!(parse(r#" - text="Traffic and weather together" song_spot="T" MediaBaseId="0""#), None);
+        assert_eq!(parse(r#"Z100 - text="Spot Block" song_spot="F""#), None);
+    }
+
+    #[test]
+    fn adverts() {
+        assert_eq!(parse("Advertisement"), None);
+        assert_eq!(parse("Commercial Break"), None);
+        assert_eq!(parse("Werbung"), None);
+        assert_eq!(parse("Station ID"), None);
+        assert_eq!(parse("AD BREAK"), None);
+
+        // Only whole words are matching
+        assert_eq!(parse("Adventures"), song(None, "Adventures"));
+
+        // A song with an artist is never treated as ad
+        assert_eq!(parse("The Jingles - Commercial"), song(Some("The Jingles"), "Commercial"));
+    }
+
+    #[test]
+    fn station_name() {
+        let parser = parser(Some("Radio Paradise"), &[]);
+        assert_eq!(parser.parse("radio paradise"), None);
+        assert_eq!(parser.parse("Radio Paradise - Eclectic Mix").map(|t| t.title), Some("Eclectic Mix".to_string()));
+    }
+
+    #[test]
+    fn custom_patterns() {
+        let parser = parser(None, &[r"^(?P<title>.+?) by (?P<artist>.+)$", r"^Now playing: (?P<title>.+)$"]);
+
+        let title = parser.parse("Teardrop by Massive Attack").unwrap();
+        assert_eq!(title.artist.as_deref(), Some("Massive Attack"));
+        assert_eq!(title.title, "Teardrop");
+
+        let title = parser.parse("Now playing: Angel").unwrap();
+        assert_eq!(title.artist, None);
+        assert_eq!(title.title, "Angel");
+
+        // Falls back to the built-in patterns
+        let title = parser.parse("Massive Attack - Angel").unwrap();
+        assert_eq!(title.artist.as_deref(), Some("Massive Attack"));
+    }
+}
@@ -0,0 +1,54 @@
+// Shortwave - alarms.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::models::{AlarmEntry, NewAlarmEntry};
+use super::queries;
+
+/// Access to the alarms which are stored in the database.
+pub struct Alarms;
+
+impl Alarms {
+    pub fn entries() -> Vec<AlarmEntry> {
+        queries::alarms().unwrap_or_else(|err| {
+            warn!("Unable to load alarms: {}", err);
+            Vec::new()
+        })
+    }
+
+    pub fn add(entry: NewAlarmEntry) {
+        if let Err(err) = queries::insert_alarm(entry) {
+            warn!("Unable to add alarm: {}", err);
+        }
+    }
+
+    pub fn update(id: i32, entry: NewAlarmEntry) {
+        if let Err(err) = queries::update_alarm(id, entry) {
+            warn!("Unable to update alarm {}: {}", id, err);
+        }
+    }
+
+    pub fn set_enabled(id: i32, enabled: bool) {
+        if let Err(err) = queries::set_alarm_enabled(id, enabled) {
+            warn!("Unable to update alarm {}: {}", id, err);
+        }
+    }
+
+    pub fn remove(id: i32) {
+        if let Err(err) = queries::delete_alarm(id) {
+            warn!("Unable to remove alarm {}: {}", id, err);
+        }
+    }
+}
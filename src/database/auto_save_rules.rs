@@ -0,0 +1,86 @@
+// Shortwave - auto_save_rules.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use regex::Regex;
+
+use std::cell::RefCell;
+
+use super::models::{AutoSaveRuleEntry, NewAutoSaveRuleEntry};
+use super::queries;
+use crate::audio::Song;
+
+thread_local! {
+    /// The enabled rules with their compiled regex. They are checked for every recorded song,
+    /// so they only get loaded again after they have been changed.
+    static ENABLED_RULES: RefCell<Option<Vec<(AutoSaveRuleEntry, Option<Regex>)>>> = RefCell::new(None);
+}
+
+/// Access to the auto-save rules which are stored in the database.
+pub struct AutoSaveRules;
+
+impl AutoSaveRules {
+    pub fn entries() -> Vec<AutoSaveRuleEntry> {
+        queries::auto_save_rules().unwrap_or_else(|err| {
+            warn!("Unable to load auto-save rules: {}", err);
+            Vec::new()
+        })
+    }
+
+    pub fn add(entry: NewAutoSaveRuleEntry) {
+        if let Err(err) = queries::insert_auto_save_rule(entry) {
+            warn!("Unable to add auto-save rule: {}", err);
+        }
+        Self::invalidate();
+    }
+
+    pub fn set_enabled(id: i32, enabled: bool) {
+        if let Err(err) = queries::set_auto_save_rule_enabled(id, enabled) {
+            warn!("Unable to update auto-save rule {}: {}", id, err);
+        }
+        Self::invalidate();
+    }
+
+    pub fn remove(id: i32) {
+        if let Err(err) = queries::delete_auto_save_rule(id) {
+            warn!("Unable to remove auto-save rule {}: {}", id, err);
+        }
+        Self::invalidate();
+    }
+
+    /// Whether the song matches at least one of the enabled rules.
+    pub fn matches(song: &Song, station_uuid: &str) -> bool {
+        ENABLED_RULES.with(|rules| {
+            let mut rules = rules.borrow_mut();
+            let rules = rules.get_or_insert_with(|| {
+                Self::entries()
+                    .into_iter()
+                    .filter(|rule| rule.enabled)
+                    .map(|rule| {
+                        let regex = rule.regex();
+                        (rule, regex)
+                    })
+                    .collect()
+            });
+
+            rules.iter().any(|(rule, regex)| rule.matches(song, station_uuid, regex.as_ref()))
+        })
+    }
+
+    /// Has to be called whenever the rules in the database are getting changed.
+    pub fn invalidate() {
+        ENABLED_RULES.with(|rules| *rules.borrow_mut() = None);
+    }
+}
@@ -0,0 +1,234 @@
+// Shortwave - backup.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use gio::prelude::*;
+use glib::translate::*;
+use glib::ToVariant;
+use gtk::{gio, glib};
+use thiserror::Error;
+
+use std::collections::BTreeMap;
+use std::ptr;
+
+use super::models::{NewAlarmEntry, NewAutoSaveRuleEntry, NewScheduledRecordingEntry, StationEntry, StationSettingsEntry};
+use super::queries;
+use crate::api::StationMetadata;
+use crate::settings::{settings_manager, Key};
+
+/// Gets increased whenever the backup format changes in an incompatible way.
+///
+/// Version 2: Settings are stored in the GVariant text format, and the alarms, scheduled
+/// recordings, station settings and auto-save rules are included.
+pub const BACKUP_VERSION: u32 = 2;
+
+#[derive(Error, Debug)]
+pub enum BackupError {
+    #[error("Serde error: {0}")]
+    SerdeError(#[from] serde_json::error::Error),
+
+    #[error("Input/Output error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Database error: {0}")]
+    DieselError(#[from] diesel::result::Error),
+
+    #[error("Unsupported backup version: {0}")]
+    UnsupportedVersion(u32),
+}
+
+/// Settings which are part of a backup. Window geometry and similar
+/// machine specific values are not included on purpose.
+fn backup_keys() -> Vec<Key> {
+    vec![
+        Key::ApiLookupDomain,
+        Key::DarkMode,
+        Key::Notifications,
+        Key::ViewSorting,
+        Key::ViewOrder,
+        Key::RecorderSaveCount,
+        Key::RecorderSongDurationThreshold,
+        Key::RecorderSongSavePath,
+        Key::RecorderFilenameTemplate,
+        Key::RecorderSplitOnTitle,
+        Key::RecorderFormat,
+        Key::RecorderBitrate,
+        Key::SongTitlePatterns,
+        Key::PlaybackVolume,
+        Key::PlaybackAutoplay,
+        Key::SleepTimerFadeOut,
+        Key::TimeshiftBufferSize,
+        Key::StreamQuality,
+        Key::EqualizerEnabled,
+        Key::EqualizerPreset,
+        Key::EqualizerGains,
+        Key::EqualizerCustomPresets,
+        Key::LoudnessNormalization,
+    ]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RestoreMode {
+    /// Add missing stations, keep the existing ones.
+    Merge,
+    /// Remove all existing stations first.
+    Replace,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Backup {
+    pub version: u32,
+    pub created: String,
+    pub stations: Vec<BackupStation>,
+    /// Settings values in the GVariant text format. Version 1 backups are containing plain JSON values.
+    #[serde(default)]
+    pub settings: BTreeMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub station_settings: Vec<StationSettingsEntry>,
+    #[serde(default)]
+    pub alarms: Vec<NewAlarmEntry>,
+    #[serde(default)]
+    pub scheduled_recordings: Vec<NewScheduledRecordingEntry>,
+    #[serde(default)]
+    pub auto_save_rules: Vec<NewAutoSaveRuleEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BackupStation {
+    pub uuid: String,
+    pub is_local: bool,
+    pub data: Option<StationMetadata>,
+}
+
+impl Backup {
+    /// Creates a new backup of the library stations, the database entries which are belonging
+    /// to them, and the current settings.
+    pub fn create() -> Result<Self, BackupError> {
+        let stations = queries::stations()?
+            .into_iter()
+            .map(|entry| BackupStation {
+                data: entry.data.and_then(|data| serde_json::from_str(&data).ok()),
+                uuid: entry.uuid,
+                is_local: entry.is_local,
+            })
+            .collect();
+
+        let gsettings = settings_manager::settings();
+        let mut settings = BTreeMap::new();
+        for key in backup_keys() {
+            let name = key.to_string();
+            let value = gsettings.value(&name).print(false).to_string();
+            settings.insert(name, serde_json::Value::from(value));
+        }
+
+        Ok(Self {
+            version: BACKUP_VERSION,
+            created: chrono::Local::now().to_rfc3339(),
+            stations,
+            settings,
+            station_settings: queries::all_station_settings()?,
+            alarms: queries::alarms()?.into_iter().map(NewAlarmEntry::from).collect(),
+            scheduled_recordings: queries::scheduled_recordings()?.into_iter().map(NewScheduledRecordingEntry::from).collect(),
+            auto_save_rules: queries::auto_save_rules()?.into_iter().map(NewAutoSaveRuleEntry::from).collect(),
+        })
+    }
+
+    pub fn from_json(data: &str) -> Result<Self, BackupError> {
+        let backup: Self = serde_json::from_str(data)?;
+        if backup.version > BACKUP_VERSION {
+            return Err(BackupError::UnsupportedVersion(backup.version));
+        }
+
+        debug!("Loaded backup from {} (version {})", backup.created, backup.version);
+        Ok(backup)
+    }
+
+    pub fn to_json(&self) -> Result<String, BackupError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Returns the stations of this backup as database entries.
+    pub fn entries(&self) -> Vec<StationEntry> {
+        self.stations
+            .iter()
+            .map(|station| StationEntry {
+                uuid: station.uuid.clone(),
+                is_local: station.is_local,
+                data: station.data.as_ref().map(|data| serde_json::to_string(data).unwrap()),
+            })
+            .collect()
+    }
+
+    /// Writes the database entries of the backup, and returns the library stations which got added.
+    pub fn restore_entries(&self, mode: RestoreMode) -> Result<Vec<StationEntry>, BackupError> {
+        Ok(queries::restore_backup(self, mode == RestoreMode::Replace)?)
+    }
+
+    /// Applies the settings values which are stored in the backup.
+    pub fn restore_settings(&self) {
+        let gsettings = settings_manager::settings();
+
+        for key in backup_keys() {
+            let name = key.to_string();
+            let value = match self.settings.get(&name) {
+                Some(value) => value,
+                None => continue,
+            };
+
+            // Use the type of the current value to determine how to parse the new one
+            let current = gsettings.value(&name);
+            let variant = if self.version >= 2 {
+                value.as_str().and_then(|text| parse_variant(current.type_(), text))
+            } else {
+                legacy_variant(&current, value)
+            };
+
+            match variant.map(|variant| gsettings.set_value(&name, &variant)) {
+                Some(Ok(_)) => debug!("Restored settings key \"{}\" -> {}", name, value),
+                _ => warn!("Unable to restore settings key \"{}\" -> {}", name, value),
+            }
+        }
+    }
+}
+
+/// Parses a value in the GVariant text format, glib-rs doesn't provide a binding for this yet.
+fn parse_variant(type_: &glib::VariantTy, text: &str) -> Option<glib::Variant> {
+    unsafe {
+        let mut error = ptr::null_mut();
+        let variant = glib::ffi::g_variant_parse(type_.to_glib_none().0, text.to_glib_none().0, ptr::null(), ptr::null_mut(), &mut error);
+        if error.is_null() {
+            Some(from_glib_full(variant))
+        } else {
+            let error: glib::Error = from_glib_full(error);
+            warn!("Unable to parse settings value \"{}\": {}", text, error);
+            None
+        }
+    }
+}
+
+/// Version 1 backups are only containing scalar values as plain JSON values.
+fn legacy_variant(current: &glib::Variant, value: &serde_json::Value) -> Option<glib::Variant> {
+    if current.get::<bool>().is_some() {
+        value.as_bool().map(|v| v.to_variant())
+    } else if current.get::<i32>().is_some() {
+        value.as_i64().map(|v| (v as i32).to_variant())
+    } else if current.get::<f64>().is_some() {
+        value.as_f64().map(|v| v.to_variant())
+    } else if current.get::<String>().is_some() {
+        value.as_str().map(|v| v.to_variant())
+    } else {
+        None
+    }
+}
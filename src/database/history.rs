@@ -0,0 +1,109 @@
+// Shortwave - history.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cell::{Cell, RefCell};
+
+use super::models::{HistoryEntry, NewHistoryEntry};
+use super::queries;
+use crate::api::SwStation;
+
+/// Maximum number of sessions which get shown in the history.
+const HISTORY_LIMIT: i64 = 250;
+
+/// Keeps track of the current listening session, and writes it to the database.
+#[derive(Debug, Default)]
+pub struct History {
+    session_id: Cell<Option<i32>>,
+    song_titles: RefCell<Vec<String>>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts a new session for the station. Does nothing if a session is already running.
+    pub fn start_session(&self, station: &SwStation) {
+        if self.session_id.get().is_some() {
+            return;
+        }
+
+        match queries::insert_history_entry(NewHistoryEntry::for_station(station)) {
+            Ok(id) => {
+                debug!("Started history session {} for station \"{}\"", id, station.metadata().name);
+                self.session_id.set(Some(id));
+            }
+            Err(err) => warn!("Unable to start history session: {}", err),
+        }
+    }
+
+    pub fn add_song_title(&self, title: &str) {
+        if self.session_id.get().is_none() {
+            return;
+        }
+
+        let mut song_titles = self.song_titles.borrow_mut();
+        if song_titles.last().map(String::as_str) != Some(title) {
+            song_titles.push(title.to_string());
+        }
+        drop(song_titles);
+
+        // Also update the stop time, so that the session has a meaningful
+        // end even if Shortwave doesn't get closed properly.
+        self.update_session();
+    }
+
+    /// Stops the current session, if there's any.
+    pub fn stop_session(&self) {
+        if self.session_id.get().is_none() {
+            return;
+        }
+
+        self.update_session();
+
+        debug!("Stopped history session {}", self.session_id.get().unwrap());
+        self.session_id.set(None);
+        self.song_titles.borrow_mut().clear();
+    }
+
+    /// Returns the most recent sessions, newest first.
+    pub fn entries() -> Vec<HistoryEntry> {
+        queries::history_entries(HISTORY_LIMIT).unwrap_or_else(|err| {
+            warn!("Unable to load history: {}", err);
+            Vec::new()
+        })
+    }
+
+    pub fn clear() {
+        if let Err(err) = queries::delete_history() {
+            warn!("Unable to clear history: {}", err);
+        }
+    }
+
+    fn update_session(&self) {
+        let id = match self.session_id.get() {
+            Some(id) => id,
+            None => return,
+        };
+
+        let stop_time = chrono::Utc::now().timestamp();
+        let song_titles = serde_json::to_string(&*self.song_titles.borrow()).unwrap();
+
+        if let Err(err) = queries::update_history_entry(id, stop_time, &song_titles) {
+            warn!("Unable to update history session {}: {}", id, err);
+        }
+    }
+}
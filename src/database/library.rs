@@ -14,11 +14,13 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use super::backup::{Backup, BackupError, RestoreMode};
 use super::models::StationEntry;
-use crate::api::{Client, Error, StationMetadata, SwStation};
+use crate::api::{Client, Error, Playlist, PlaylistEntry, PlaylistFormat, PlaylistResolver, StationMetadata, SwStation};
 use crate::app::Action;
 use crate::database::connection;
 use crate::database::queries;
+use crate::database::AutoSaveRules;
 use crate::i18n::*;
 use crate::model::SwStationModel;
 use crate::settings::{settings_manager, Key};
@@ -31,6 +33,9 @@ use gtk::subclass::prelude::*;
 use once_cell::sync::Lazy;
 use once_cell::unsync::OnceCell;
 use std::cell::RefCell;
+use std::fs;
+use std::path::PathBuf;
+use url::Url;
 
 #[derive(Display, Copy, Debug, Clone, EnumString, PartialEq, GEnum)]
 #[repr(u32)]
@@ -137,6 +142,8 @@ impl SwLibrary {
 
             let entry = StationEntry::for_station(&station);
             queries::insert_station(entry).unwrap();
+
+            self.resolve_station_url(&station);
         }
 
         self.update_library_status();
@@ -154,6 +161,250 @@ impl SwLibrary {
         self.update_library_status();
     }
 
+    pub fn update_station(&self, station: SwStation) {
+        let imp = imp::SwLibrary::from_instance(self);
+
+        debug!("Update station {:?}", station.metadata().name);
+        imp.model.remove_station(&station);
+        imp.model.add_station(&station);
+
+        let entry = StationEntry::for_station(&station);
+        queries::update_station(entry).unwrap();
+
+        self.update_library_status();
+        self.resolve_station_url(&station);
+    }
+
+    /// Local stations often point to a playlist instead of the actual stream,
+    /// so the resolved URL gets determined the same way as radio-browser.info does it.
+    fn resolve_station_url(&self, station: &SwStation) {
+        let metadata = station.metadata();
+        let url = match metadata.url {
+            // Only stations which haven't been resolved yet
+            Some(url) if station.is_local() && metadata.url_resolved.as_ref() == Some(&url) => url,
+            _ => return,
+        };
+
+        let uuid = station.uuid();
+        let future = clone!(@weak self as this => async move {
+            let resolved = match PlaylistResolver::resolve(&url).await {
+                Ok(urls) => urls.into_iter().next(),
+                Err(err) => {
+                    warn!("Unable to resolve stream URL {}: {}", url.to_string(), err);
+                    return;
+                }
+            };
+
+            // The station could have been edited or removed in the meantime
+            let station = match this.station(&uuid) {
+                Some(station) if station.metadata().url.as_ref() == Some(&url) => station,
+                _ => return,
+            };
+
+            if let Some(resolved) = resolved.filter(|resolved| resolved != &url) {
+                debug!("Resolved stream URL {} to {}", url.to_string(), resolved.to_string());
+                let mut metadata = station.metadata();
+                metadata.url_resolved = Some(resolved);
+                this.update_station(SwStation::new(uuid, true, metadata));
+            }
+        });
+        spawn!(future);
+    }
+
+    /// Imports the stations of a M3U, PLS, XSPF or ASX playlist file into the library.
+    pub fn import_stations(&self, path: PathBuf) {
+        let future = clone!(@strong self as this => async move {
+            let imp = imp::SwLibrary::from_instance(&this);
+            let sender = imp.sender.get().unwrap();
+
+            let data = match fs::read_to_string(&path) {
+                Ok(data) => data,
+                Err(err) => {
+                    let notification = Notification::new_error(&i18n("Could not import stations."), &err.to_string());
+                    send!(sender, Action::ViewShowNotification(notification));
+                    return;
+                }
+            };
+
+            let format = PlaylistFormat::for_path(&path).unwrap_or_else(|| PlaylistFormat::guess(&data));
+            let playlist = Playlist::parse(&data, format, None);
+
+            let mut stations: Vec<SwStation> = Vec::new();
+            for entry in playlist.entries {
+                if this.contains_url(&entry.url) || stations.iter().any(|s| s.metadata().url_resolved.as_ref() == Some(&entry.url)) {
+                    debug!("Skip already existing station {}", entry.url);
+                    continue;
+                }
+
+                let station = this.station_for_playlist_entry(entry).await;
+                if !Self::contains_station(&station) && !stations.iter().any(|s| s.uuid() == station.uuid()) {
+                    stations.push(station);
+                }
+            }
+
+            let count = stations.len();
+            this.add_stations(stations);
+
+            let notification = Notification::new_info(&ni18n_f("Imported {} station", "Imported {} stations", count as u32, &[&count.to_string()]));
+            send!(sender, Action::ViewShowNotification(notification));
+        });
+        spawn!(future);
+    }
+
+    /// Exports the stations as playlist file. The format gets determined by the file extension.
+    pub fn export_stations(&self, stations: Vec<SwStation>, path: PathBuf) {
+        let imp = imp::SwLibrary::from_instance(self);
+
+        let format = PlaylistFormat::for_path(&path).unwrap_or(PlaylistFormat::M3u);
+        // Stations without a stream url are skipped
+        let entries: Vec<_> = stations.iter().filter_map(PlaylistEntry::for_station).collect();
+        let count = entries.len() as u32;
+        let data = Playlist { entries }.serialize(format);
+
+        let notification = match fs::write(&path, data) {
+            Ok(_) => Notification::new_info(&ni18n_f("Exported {} station", "Exported {} stations", count, &[&count.to_string()])),
+            Err(err) => Notification::new_error(&i18n("Could not export stations."), &err.to_string()),
+        };
+        send!(imp.sender.get().unwrap(), Action::ViewShowNotification(notification));
+    }
+
+    /// Writes all library stations and the relevant settings into a JSON backup file.
+    pub fn backup(&self, path: PathBuf) {
+        let imp = imp::SwLibrary::from_instance(self);
+
+        let result = Backup::create().and_then(|backup| backup.to_json()).and_then(|data| fs::write(&path, data).map_err(BackupError::from));
+
+        let notification = match result {
+            Ok(_) => Notification::new_info(&i18n("Library backup created.")),
+            Err(err) => Notification::new_error(&i18n("Could not create library backup."), &err.to_string()),
+        };
+        send!(imp.sender.get().unwrap(), Action::ViewShowNotification(notification));
+    }
+
+    /// Restores the library stations, the database entries which are belonging to them and the settings
+    /// from a JSON backup file.
+    pub fn restore(&self, path: PathBuf, mode: RestoreMode) {
+        let imp = imp::SwLibrary::from_instance(self);
+        let sender = imp.sender.get().unwrap().clone();
+
+        debug!("Restore library backup ({:?})", mode);
+        let result = fs::read_to_string(&path)
+            .map_err(BackupError::from)
+            .and_then(|data| Backup::from_json(&data))
+            .and_then(|backup| backup.restore_entries(mode).map(|entries| (backup, entries)));
+
+        let (backup, entries) = match result {
+            Ok(result) => result,
+            Err(err) => {
+                let notification = Notification::new_error(&i18n("Could not restore library backup."), &err.to_string());
+                send!(sender, Action::ViewShowNotification(notification));
+                return;
+            }
+        };
+
+        if mode == RestoreMode::Replace {
+            imp.model.clear();
+        }
+
+        backup.restore_settings();
+        AutoSaveRules::invalidate();
+        send!(sender, Action::AlarmsChanged);
+        send!(sender, Action::ScheduledRecordingsChanged);
+
+        let future = clone!(@strong self as this => async move {
+            let imp = imp::SwLibrary::from_instance(&this);
+            *imp.status.borrow_mut() = SwLibraryStatus::Loading;
+            this.notify("status");
+
+            let count = entries.len() as u32;
+            let futures = entries.into_iter().map(|entry| this.load_station(entry));
+            join_all(futures).await;
+
+            this.update_library_status();
+
+            let notification = Notification::new_info(&ni18n_f("Restored {} station", "Restored {} stations", count, &[&count.to_string()]));
+            send!(sender, Action::ViewShowNotification(notification));
+        });
+        spawn!(future);
+    }
+
+    /// Returns a radio-browser.info station which matches the entry URL,
+    /// or creates a new local station if there's no such station.
+    async fn station_for_playlist_entry(&self, entry: PlaylistEntry) -> SwStation {
+        let imp = imp::SwLibrary::from_instance(self);
+
+        match imp.client.clone().station_metadata_by_url(&entry.url).await {
+            Ok(Some(metadata)) => return SwStation::new(metadata.stationuuid.clone(), false, metadata),
+            Ok(None) => debug!("No radio-browser.info station found for {}", entry.url),
+            Err(err) => warn!("Unable to lookup station for {}: {}", entry.url, err),
+        }
+
+        let name = entry.title.unwrap_or_else(|| entry.url.host_str().unwrap_or_else(|| entry.url.as_str()).to_string());
+        let mut metadata = StationMetadata::new_local(&name, entry.url);
+        metadata.favicon = entry.image;
+
+        SwStation::new(metadata.stationuuid.clone(), true, metadata)
+    }
+
+    fn contains_url(&self, url: &Url) -> bool {
+        self.station_by_url(url).is_some()
+    }
+
+    /// Returns the library station with the passed UUID.
+    pub fn station(&self, uuid: &str) -> Option<SwStation> {
+        let imp = imp::SwLibrary::from_instance(self);
+
+        (0..imp.model.n_items())
+            .map(|pos| imp.model.item(pos).unwrap().downcast::<SwStation>().unwrap())
+            .find(|station| station.uuid() == uuid)
+    }
+
+    /// Like `station()`, but it also works while the library is still loading, by using the
+    /// data which is cached in the database. Returns `None` if there's no cached data.
+    pub fn station_from_database(&self, uuid: &str) -> Option<SwStation> {
+        if let Some(station) = self.station(uuid) {
+            return Some(station);
+        }
+
+        let entry = match queries::station(uuid) {
+            Ok(entry) => entry?,
+            Err(err) => {
+                warn!("Unable to load station {} from database: {}", uuid, err);
+                return None;
+            }
+        };
+
+        let metadata = self.load_station_metadata(&entry.uuid, entry.data.as_ref()?).ok()?;
+        Some(SwStation::new(entry.uuid, entry.is_local, metadata))
+    }
+
+    /// Returns the library station which streams from the passed URL.
+    pub fn station_by_url(&self, url: &Url) -> Option<SwStation> {
+        let imp = imp::SwLibrary::from_instance(self);
+
+        (0..imp.model.n_items()).map(|pos| imp.model.item(pos).unwrap().downcast::<SwStation>().unwrap()).find(|station| {
+            let metadata = station.metadata();
+            metadata.url_resolved.as_ref() == Some(url) || metadata.url.as_ref() == Some(url)
+        })
+    }
+
+    /// Returns UUID and name of all stations which are stored in the database,
+    /// without fetching any data from radio-browser.info.
+    pub fn stored_stations() -> Vec<(String, String)> {
+        queries::stations()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|entry| {
+                let name = entry
+                    .data
+                    .and_then(|data| serde_json::from_str::<StationMetadata>(&data).ok())
+                    .map(|metadata| metadata.name)
+                    .unwrap_or_default();
+                (entry.uuid, name)
+            })
+            .collect()
+    }
+
     pub fn contains_station(station: &SwStation) -> bool {
         queries::contains_station(&station.uuid()).unwrap()
     }
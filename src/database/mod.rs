@@ -14,10 +14,27 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+mod alarms;
+mod auto_save_rules;
+mod backup;
 mod connection;
+mod history;
 mod library;
 mod models;
 mod queries;
+mod scheduled_recordings;
 mod schema;
+mod song_log;
+mod station_settings;
 
+pub use alarms::Alarms;
+pub use auto_save_rules::AutoSaveRules;
+pub use backup::RestoreMode;
+pub use history::History;
 pub use library::{SwLibrary, SwLibraryStatus};
+pub use models::{
+    AlarmEntry, AutoSaveRuleEntry, AutoSaveRuleKind, HistoryEntry, NewAlarmEntry, NewAutoSaveRuleEntry, NewScheduledRecordingEntry, ScheduledRecordingEntry, SongLogEntry, StationSettingsEntry,
+};
+pub use scheduled_recordings::ScheduledRecordings;
+pub use song_log::SongLog;
+pub use station_settings::StationSettings;
@@ -14,8 +14,15 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use chrono::{DateTime, Datelike, Duration, Local, TimeZone, Weekday};
+use regex::{Regex, RegexBuilder};
+
 use super::schema::*;
 use crate::api::SwStation;
+use crate::audio::Song;
+
+use std::path::PathBuf;
+use std::str::FromStr;
 
 /// Representation of a station within the database.
 #[derive(Queryable, Insertable, Debug, Clone)]
@@ -44,3 +51,363 @@ impl StationEntry {
         }
     }
 }
+
+/// A listening session of a station within the database.
+#[derive(Queryable, Debug, Clone)]
+pub struct HistoryEntry {
+    pub id: i32,
+
+    /// UUID of the played station.
+    pub station_uuid: String,
+
+    /// Whether the played station is local.
+    pub is_local: bool,
+
+    /// Serialized station metadata, so that the station can be played again
+    /// even if it's not part of the library.
+    pub station_data: Option<String>,
+
+    /// Unix timestamp when the playback started.
+    pub start_time: i64,
+
+    /// Unix timestamp when the playback stopped.
+    pub stop_time: Option<i64>,
+
+    /// JSON array of all song titles which were seen during this session.
+    pub song_titles: String,
+}
+
+impl HistoryEntry {
+    /// Returns the station which got played during this session.
+    pub fn station(&self) -> Option<SwStation> {
+        let data = self.station_data.as_ref()?;
+        let metadata = serde_json::from_str(data).ok()?;
+        Some(SwStation::new(self.station_uuid.clone(), self.is_local, metadata))
+    }
+
+    pub fn song_titles(&self) -> Vec<String> {
+        serde_json::from_str(&self.song_titles).unwrap_or_default()
+    }
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "history"]
+pub struct NewHistoryEntry {
+    pub station_uuid: String,
+    pub is_local: bool,
+    pub station_data: Option<String>,
+    pub start_time: i64,
+    pub song_titles: String,
+}
+
+impl NewHistoryEntry {
+    /// Create a new history entry for the station, starting now.
+    pub fn for_station(station: &SwStation) -> Self {
+        let metadata = station.metadata();
+
+        Self {
+            station_uuid: station.uuid(),
+            is_local: station.is_local(),
+            station_data: Some(serde_json::to_string(&metadata).unwrap()),
+            start_time: chrono::Utc::now().timestamp(),
+            song_titles: String::from("[]"),
+        }
+    }
+}
+
+/// A song title which was seen while listening to a station.
+#[derive(Queryable, Debug, Clone)]
+pub struct SongLogEntry {
+    pub id: i32,
+    pub station_uuid: String,
+    pub station_name: String,
+    pub title: String,
+
+    /// Unix timestamp when the song title was seen.
+    pub timestamp: i64,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "song_log"]
+pub struct NewSongLogEntry {
+    pub station_uuid: String,
+    pub station_name: String,
+    pub title: String,
+    pub timestamp: i64,
+}
+
+impl NewSongLogEntry {
+    pub fn new(station: &SwStation, title: &str) -> Self {
+        Self {
+            station_uuid: station.uuid(),
+            station_name: station.metadata().name,
+            title: title.to_string(),
+            timestamp: chrono::Utc::now().timestamp(),
+        }
+    }
+}
+
+/// A scheduled alarm which starts the playback of a library station.
+#[derive(Queryable, Debug, Clone, PartialEq)]
+pub struct AlarmEntry {
+    pub id: i32,
+    pub enabled: bool,
+    pub hour: i32,
+    pub minute: i32,
+
+    /// Bitmask of the weekdays on which the alarm repeats, starting with
+    /// Monday as the lowest bit. Zero means that the alarm only fires once.
+    pub weekdays: i32,
+
+    pub station_uuid: String,
+    pub volume: f64,
+
+    /// Whether the system should be kept awake while the alarm is pending.
+    pub inhibit: bool,
+}
+
+impl AlarmEntry {
+    pub fn is_repeating(&self) -> bool {
+        self.weekdays != 0
+    }
+
+    pub fn repeats_on(&self, weekday: Weekday) -> bool {
+        self.weekdays & (1 << weekday.num_days_from_monday()) != 0
+    }
+
+    /// Returns the next time at which the alarm fires, after the passed time.
+    pub fn next_occurrence(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        (0..=7)
+            .filter_map(|offset| {
+                let date = after.date() + Duration::days(offset);
+                date.and_hms_opt(self.hour as u32, self.minute as u32, 0)
+            })
+            .find(|time| *time > after && (!self.is_repeating() || self.repeats_on(time.weekday())))
+    }
+}
+
+#[derive(Insertable, AsChangeset, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[table_name = "alarms"]
+pub struct NewAlarmEntry {
+    pub enabled: bool,
+    pub hour: i32,
+    pub minute: i32,
+    pub weekdays: i32,
+    pub station_uuid: String,
+    pub volume: f64,
+    pub inhibit: bool,
+}
+
+impl From<AlarmEntry> for NewAlarmEntry {
+    fn from(entry: AlarmEntry) -> Self {
+        Self {
+            enabled: entry.enabled,
+            hour: entry.hour,
+            minute: entry.minute,
+            weekdays: entry.weekdays,
+            station_uuid: entry.station_uuid,
+            volume: entry.volume,
+            inhibit: entry.inhibit,
+        }
+    }
+}
+
+/// A recording of a station for a fixed time window.
+#[derive(Queryable, Debug, Clone, PartialEq)]
+pub struct ScheduledRecordingEntry {
+    pub id: i32,
+    pub station_uuid: String,
+    pub is_local: bool,
+
+    /// Serialized station metadata, so that the recording also works
+    /// if the station got removed from the library in the meantime.
+    pub station_data: String,
+
+    /// Unix timestamp when the recording starts.
+    pub start_time: i64,
+
+    /// Duration of the recording in seconds.
+    pub duration: i32,
+
+    pub path: String,
+}
+
+impl ScheduledRecordingEntry {
+    pub fn station(&self) -> Option<SwStation> {
+        let metadata = serde_json::from_str(&self.station_data).ok()?;
+        Some(SwStation::new(self.station_uuid.clone(), self.is_local, metadata))
+    }
+
+    pub fn start_time(&self) -> DateTime<Local> {
+        Local.timestamp(self.start_time, 0)
+    }
+
+    pub fn end_time(&self) -> DateTime<Local> {
+        self.start_time() + Duration::seconds(self.duration.into())
+    }
+
+    pub fn path(&self) -> PathBuf {
+        PathBuf::from(&self.path)
+    }
+}
+
+#[derive(Insertable, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[table_name = "scheduled_recordings"]
+pub struct NewScheduledRecordingEntry {
+    pub station_uuid: String,
+    pub is_local: bool,
+    pub station_data: String,
+    pub start_time: i64,
+    pub duration: i32,
+    pub path: String,
+}
+
+impl NewScheduledRecordingEntry {
+    pub fn new(station: &SwStation, start_time: DateTime<Local>, duration: i32, path: PathBuf) -> Self {
+        Self {
+            station_uuid: station.uuid(),
+            is_local: station.is_local(),
+            station_data: serde_json::to_string(&station.metadata()).unwrap(),
+            start_time: start_time.timestamp(),
+            duration,
+            path: path.to_string_lossy().to_string(),
+        }
+    }
+}
+
+impl From<ScheduledRecordingEntry> for NewScheduledRecordingEntry {
+    fn from(entry: ScheduledRecordingEntry) -> Self {
+        Self {
+            station_uuid: entry.station_uuid,
+            is_local: entry.is_local,
+            station_data: entry.station_data,
+            start_time: entry.start_time,
+            duration: entry.duration,
+            path: entry.path,
+        }
+    }
+}
+
+/// Per-station settings, stations without an entry are using the default values.
+#[derive(Queryable, Insertable, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[table_name = "station_settings"]
+pub struct StationSettingsEntry {
+    pub station_uuid: String,
+    /// Whether recorded songs of this station get saved automatically.
+    pub auto_save: bool,
+    /// Id of the equalizer preset which gets used for this station instead of the global one.
+    pub equalizer_preset: Option<String>,
+    /// Gain in dB which was learned by the loudness normalization.
+    pub loudness_gain: Option<f64>,
+}
+
+impl StationSettingsEntry {
+    pub fn new(station_uuid: &str) -> Self {
+        Self {
+            station_uuid: station_uuid.to_string(),
+            auto_save: false,
+            equalizer_preset: None,
+            loudness_gain: None,
+        }
+    }
+}
+
+/// The criterion of an auto-save rule.
+#[derive(Display, Copy, Debug, Clone, EnumString, PartialEq)]
+pub enum AutoSaveRuleKind {
+    /// Every song, only limited by the minimum duration.
+    AnySong,
+    /// Songs of the station with the uuid in `value`.
+    Station,
+    /// Songs whose title matches the regex in `value`.
+    Title,
+    /// Songs of one of the comma separated artists in `value`.
+    Artist,
+}
+
+impl AutoSaveRuleKind {
+    pub const ALL: [Self; 4] = [Self::AnySong, Self::Station, Self::Title, Self::Artist];
+}
+
+/// A rule which decides whether a recorded song gets saved automatically.
+#[derive(Queryable, Debug, Clone, PartialEq)]
+pub struct AutoSaveRuleEntry {
+    pub id: i32,
+    pub enabled: bool,
+    pub kind: String,
+    pub value: String,
+
+    /// Minimum song duration in seconds, zero means that there's no limit.
+    pub min_duration: i32,
+}
+
+impl AutoSaveRuleEntry {
+    pub fn kind(&self) -> Option<AutoSaveRuleKind> {
+        AutoSaveRuleKind::from_str(&self.kind).ok()
+    }
+
+    /// Compiles the regular expression of title rules.
+    pub fn regex(&self) -> Option<Regex> {
+        if self.kind() != Some(AutoSaveRuleKind::Title) {
+            return None;
+        }
+
+        match RegexBuilder::new(&self.value).case_insensitive(true).build() {
+            Ok(regex) => Some(regex),
+            Err(err) => {
+                warn!("Invalid auto-save rule regex \"{}\": {}", self.value, err);
+                None
+            }
+        }
+    }
+
+    /// The regex has to be compiled with `regex()` in advance, so that it's not getting compiled
+    /// again for every song.
+    pub fn matches(&self, song: &Song, station_uuid: &str, regex: Option<&Regex>) -> bool {
+        if !self.enabled || song.duration.as_secs() < self.min_duration.max(0) as u64 {
+            return false;
+        }
+
+        match self.kind() {
+            Some(AutoSaveRuleKind::AnySong) => true,
+            Some(AutoSaveRuleKind::Station) => self.value == station_uuid,
+            Some(AutoSaveRuleKind::Title) => regex.map_or(false, |regex| regex.is_match(&song.title)),
+            Some(AutoSaveRuleKind::Artist) => match &song.stream_title.artist {
+                Some(artist) => self.value.split(',').map(str::trim).any(|a| !a.is_empty() && a.to_lowercase() == artist.to_lowercase()),
+                None => false,
+            },
+            None => false,
+        }
+    }
+}
+
+#[derive(Insertable, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[table_name = "auto_save_rules"]
+pub struct NewAutoSaveRuleEntry {
+    pub enabled: bool,
+    pub kind: String,
+    pub value: String,
+    pub min_duration: i32,
+}
+
+impl NewAutoSaveRuleEntry {
+    pub fn new(kind: AutoSaveRuleKind, value: &str, min_duration: i32) -> Self {
+        Self {
+            enabled: true,
+            kind: kind.to_string(),
+            value: value.to_string(),
+            min_duration,
+        }
+    }
+}
+
+impl From<AutoSaveRuleEntry> for NewAutoSaveRuleEntry {
+    fn from(entry: AutoSaveRuleEntry) -> Self {
+        Self {
+            enabled: entry.enabled,
+            kind: entry.kind,
+            value: entry.value,
+            min_duration: entry.min_duration,
+        }
+    }
+}
@@ -14,8 +14,12 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use super::models::StationEntry;
-use super::schema::library;
+use super::backup::Backup;
+use super::models::{
+    AlarmEntry, AutoSaveRuleEntry, HistoryEntry, NewAlarmEntry, NewAutoSaveRuleEntry, NewHistoryEntry, NewScheduledRecordingEntry, NewSongLogEntry, ScheduledRecordingEntry, SongLogEntry,
+    StationEntry, StationSettingsEntry,
+};
+use super::schema::{alarms, auto_save_rules, history, library, scheduled_recordings, song_log, station_settings};
 use crate::database;
 use crate::diesel::prelude::*;
 
@@ -31,6 +35,12 @@ pub fn stations() -> Result<Vec<StationEntry>, diesel::result::Error> {
     Ok(entries)
 }
 
+pub fn station(uuid: &str) -> Result<Option<StationEntry>, diesel::result::Error> {
+    let con = connect_db!();
+    let entry = library::table.filter(library::uuid.eq(uuid)).first::<StationEntry>(&con).optional()?;
+    Ok(entry)
+}
+
 pub fn contains_station(uuid: &str) -> Result<bool, diesel::result::Error> {
     let con = connect_db!();
     let entries = library::table.filter(library::uuid.eq(uuid)).load::<StationEntry>(&con)?;
@@ -54,3 +64,211 @@ pub fn delete_station(uuid: &str) -> Result<(), diesel::result::Error> {
     diesel::delete(library::table.filter(library::uuid.eq(uuid))).execute(&*con)?;
     Ok(())
 }
+
+pub fn history_entries(limit: i64) -> Result<Vec<HistoryEntry>, diesel::result::Error> {
+    let con = connect_db!();
+    let entries = history::table.order(history::start_time.desc()).limit(limit).load::<HistoryEntry>(&con)?;
+    Ok(entries)
+}
+
+/// Inserts a new history entry, and returns its id.
+pub fn insert_history_entry(entry: NewHistoryEntry) -> Result<i32, diesel::result::Error> {
+    let con = connect_db!();
+    diesel::insert_into(history::table).values(entry).execute(&*con)?;
+    let id = history::table.select(history::id).order(history::id.desc()).first::<i32>(&con)?;
+    Ok(id)
+}
+
+pub fn update_history_entry(id: i32, stop_time: i64, song_titles: &str) -> Result<(), diesel::result::Error> {
+    let con = connect_db!();
+    diesel::update(history::table.filter(history::id.eq(id)))
+        .set((history::stop_time.eq(stop_time), history::song_titles.eq(song_titles)))
+        .execute(&*con)?;
+    Ok(())
+}
+
+pub fn delete_history() -> Result<(), diesel::result::Error> {
+    let con = connect_db!();
+    diesel::delete(history::table).execute(&*con)?;
+    Ok(())
+}
+
+/// Returns the most recent song log entries, optionally filtered by song title or station name.
+pub fn song_log_entries(filter: Option<&str>, limit: Option<i64>) -> Result<Vec<SongLogEntry>, diesel::result::Error> {
+    let con = connect_db!();
+    let mut query = song_log::table.order(song_log::timestamp.desc()).into_boxed();
+
+    if let Some(limit) = limit {
+        query = query.limit(limit);
+    }
+
+    if let Some(filter) = filter {
+        let pattern = format!("%{}%", escape_like_pattern(filter));
+        query = query.filter(song_log::title.like(pattern.clone()).escape('\\').or(song_log::station_name.like(pattern).escape('\\')));
+    }
+
+    let entries = query.load::<SongLogEntry>(&con)?;
+    Ok(entries)
+}
+
+/// Escapes the wildcards of a `LIKE` pattern, so that the text gets matched literally.
+fn escape_like_pattern(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+pub fn insert_song_log_entry(entry: NewSongLogEntry) -> Result<(), diesel::result::Error> {
+    let con = connect_db!();
+    diesel::insert_into(song_log::table).values(entry).execute(&*con)?;
+    Ok(())
+}
+
+pub fn delete_song_log() -> Result<(), diesel::result::Error> {
+    let con = connect_db!();
+    diesel::delete(song_log::table).execute(&*con)?;
+    Ok(())
+}
+
+pub fn alarms() -> Result<Vec<AlarmEntry>, diesel::result::Error> {
+    let con = connect_db!();
+    let entries = alarms::table.order((alarms::hour.asc(), alarms::minute.asc())).load::<AlarmEntry>(&con)?;
+    Ok(entries)
+}
+
+pub fn insert_alarm(entry: NewAlarmEntry) -> Result<(), diesel::result::Error> {
+    let con = connect_db!();
+    diesel::insert_into(alarms::table).values(entry).execute(&*con)?;
+    Ok(())
+}
+
+pub fn update_alarm(id: i32, entry: NewAlarmEntry) -> Result<(), diesel::result::Error> {
+    let con = connect_db!();
+    diesel::update(alarms::table.filter(alarms::id.eq(id))).set(&entry).execute(&*con)?;
+    Ok(())
+}
+
+pub fn set_alarm_enabled(id: i32, enabled: bool) -> Result<(), diesel::result::Error> {
+    let con = connect_db!();
+    diesel::update(alarms::table.filter(alarms::id.eq(id))).set(alarms::enabled.eq(enabled)).execute(&*con)?;
+    Ok(())
+}
+
+pub fn delete_alarm(id: i32) -> Result<(), diesel::result::Error> {
+    let con = connect_db!();
+    diesel::delete(alarms::table.filter(alarms::id.eq(id))).execute(&*con)?;
+    Ok(())
+}
+
+pub fn scheduled_recordings() -> Result<Vec<ScheduledRecordingEntry>, diesel::result::Error> {
+    let con = connect_db!();
+    let entries = scheduled_recordings::table.order(scheduled_recordings::start_time.asc()).load::<ScheduledRecordingEntry>(&con)?;
+    Ok(entries)
+}
+
+pub fn insert_scheduled_recording(entry: NewScheduledRecordingEntry) -> Result<(), diesel::result::Error> {
+    let con = connect_db!();
+    diesel::insert_into(scheduled_recordings::table).values(entry).execute(&*con)?;
+    Ok(())
+}
+
+pub fn delete_scheduled_recording(id: i32) -> Result<(), diesel::result::Error> {
+    let con = connect_db!();
+    diesel::delete(scheduled_recordings::table.filter(scheduled_recordings::id.eq(id))).execute(&*con)?;
+    Ok(())
+}
+
+pub fn station_settings(station_uuid: &str) -> Result<Option<StationSettingsEntry>, diesel::result::Error> {
+    let con = connect_db!();
+    let entry = station_settings::table
+        .filter(station_settings::station_uuid.eq(station_uuid))
+        .first::<StationSettingsEntry>(&con)
+        .optional()?;
+    Ok(entry)
+}
+
+pub fn all_station_settings() -> Result<Vec<StationSettingsEntry>, diesel::result::Error> {
+    let con = connect_db!();
+    let entries = station_settings::table.load::<StationSettingsEntry>(&con)?;
+    Ok(entries)
+}
+
+pub fn update_station_settings(entry: StationSettingsEntry) -> Result<(), diesel::result::Error> {
+    let con = connect_db!();
+    diesel::replace_into(station_settings::table).values(entry).execute(&*con)?;
+    Ok(())
+}
+
+pub fn auto_save_rules() -> Result<Vec<AutoSaveRuleEntry>, diesel::result::Error> {
+    let con = connect_db!();
+    let entries = auto_save_rules::table.order(auto_save_rules::id.asc()).load::<AutoSaveRuleEntry>(&con)?;
+    Ok(entries)
+}
+
+pub fn insert_auto_save_rule(entry: NewAutoSaveRuleEntry) -> Result<(), diesel::result::Error> {
+    let con = connect_db!();
+    diesel::insert_into(auto_save_rules::table).values(entry).execute(&*con)?;
+    Ok(())
+}
+
+pub fn set_auto_save_rule_enabled(id: i32, enabled: bool) -> Result<(), diesel::result::Error> {
+    let con = connect_db!();
+    diesel::update(auto_save_rules::table.filter(auto_save_rules::id.eq(id)))
+        .set(auto_save_rules::enabled.eq(enabled))
+        .execute(&*con)?;
+    Ok(())
+}
+
+pub fn delete_auto_save_rule(id: i32) -> Result<(), diesel::result::Error> {
+    let con = connect_db!();
+    diesel::delete(auto_save_rules::table.filter(auto_save_rules::id.eq(id))).execute(&*con)?;
+    Ok(())
+}
+
+/// Writes the entries of the backup into the database within a single transaction, so that a failed
+/// restore doesn't leave a half restored database behind. With `replace` the existing entries are
+/// getting removed first, otherwise only missing entries are getting added.
+/// Returns the library stations which got added.
+pub fn restore_backup(backup: &Backup, replace: bool) -> Result<Vec<StationEntry>, diesel::result::Error> {
+    let con = connect_db!();
+    con.transaction(|| {
+        if replace {
+            diesel::delete(library::table).execute(&*con)?;
+            diesel::delete(station_settings::table).execute(&*con)?;
+            diesel::delete(alarms::table).execute(&*con)?;
+            diesel::delete(scheduled_recordings::table).execute(&*con)?;
+            diesel::delete(auto_save_rules::table).execute(&*con)?;
+        }
+
+        let existing: Vec<String> = library::table.select(library::uuid).load(&con)?;
+        let stations: Vec<StationEntry> = backup.entries().into_iter().filter(|entry| !existing.contains(&entry.uuid)).collect();
+        diesel::insert_into(library::table).values(&stations).execute(&*con)?;
+
+        // Settings of stations which already have some are kept
+        diesel::insert_or_ignore_into(station_settings::table).values(&backup.station_settings).execute(&*con)?;
+
+        let existing: Vec<NewAlarmEntry> = alarms::table.load::<AlarmEntry>(&con)?.into_iter().map(NewAlarmEntry::from).collect();
+        for entry in backup.alarms.iter().filter(|entry| !existing.contains(entry)) {
+            diesel::insert_into(alarms::table).values(entry).execute(&*con)?;
+        }
+
+        let existing: Vec<NewScheduledRecordingEntry> = scheduled_recordings::table
+            .load::<ScheduledRecordingEntry>(&con)?
+            .into_iter()
+            .map(NewScheduledRecordingEntry::from)
+            .collect();
+        for entry in backup.scheduled_recordings.iter().filter(|entry| !existing.contains(entry)) {
+            diesel::insert_into(scheduled_recordings::table).values(entry).execute(&*con)?;
+        }
+
+        let existing: Vec<NewAutoSaveRuleEntry> = auto_save_rules::table
+            .order(auto_save_rules::id.asc())
+            .load::<AutoSaveRuleEntry>(&con)?
+            .into_iter()
+            .map(NewAutoSaveRuleEntry::from)
+            .collect();
+        for entry in backup.auto_save_rules.iter().filter(|entry| !existing.contains(entry)) {
+            diesel::insert_into(auto_save_rules::table).values(entry).execute(&*con)?;
+        }
+
+        Ok(stations)
+    })
+}
@@ -0,0 +1,42 @@
+// Shortwave - scheduled_recordings.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::models::{NewScheduledRecordingEntry, ScheduledRecordingEntry};
+use super::queries;
+
+/// Access to the scheduled recordings which are stored in the database.
+pub struct ScheduledRecordings;
+
+impl ScheduledRecordings {
+    pub fn entries() -> Vec<ScheduledRecordingEntry> {
+        queries::scheduled_recordings().unwrap_or_else(|err| {
+            warn!("Unable to load scheduled recordings: {}", err);
+            Vec::new()
+        })
+    }
+
+    pub fn add(entry: NewScheduledRecordingEntry) {
+        if let Err(err) = queries::insert_scheduled_recording(entry) {
+            warn!("Unable to add scheduled recording: {}", err);
+        }
+    }
+
+    pub fn remove(id: i32) {
+        if let Err(err) = queries::delete_scheduled_recording(id) {
+            warn!("Unable to remove scheduled recording {}: {}", id, err);
+        }
+    }
+}
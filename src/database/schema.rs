@@ -14,6 +14,41 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+table! {
+    alarms (id) {
+        id -> Integer,
+        enabled -> Bool,
+        hour -> Integer,
+        minute -> Integer,
+        weekdays -> Integer,
+        station_uuid -> Text,
+        volume -> Double,
+        inhibit -> Bool,
+    }
+}
+
+table! {
+    auto_save_rules (id) {
+        id -> Integer,
+        enabled -> Bool,
+        kind -> Text,
+        value -> Text,
+        min_duration -> Integer,
+    }
+}
+
+table! {
+    history (id) {
+        id -> Integer,
+        station_uuid -> Text,
+        is_local -> Bool,
+        station_data -> Nullable<Text>,
+        start_time -> BigInt,
+        stop_time -> Nullable<BigInt>,
+        song_titles -> Text,
+    }
+}
+
 table! {
     library (uuid) {
         uuid -> Text,
@@ -22,4 +57,35 @@ table! {
     }
 }
 
-allow_tables_to_appear_in_same_query!(library,);
+table! {
+    scheduled_recordings (id) {
+        id -> Integer,
+        station_uuid -> Text,
+        is_local -> Bool,
+        station_data -> Text,
+        start_time -> BigInt,
+        duration -> Integer,
+        path -> Text,
+    }
+}
+
+table! {
+    song_log (id) {
+        id -> Integer,
+        station_uuid -> Text,
+        station_name -> Text,
+        title -> Text,
+        timestamp -> BigInt,
+    }
+}
+
+table! {
+    station_settings (station_uuid) {
+        station_uuid -> Text,
+        auto_save -> Bool,
+        equalizer_preset -> Nullable<Text>,
+        loudness_gain -> Nullable<Double>,
+    }
+}
+
+allow_tables_to_appear_in_same_query!(alarms, auto_save_rules, history, library, scheduled_recordings, song_log, station_settings,);
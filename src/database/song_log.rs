@@ -0,0 +1,78 @@
+// Shortwave - song_log.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use chrono::{Local, TimeZone};
+
+use std::fs;
+use std::path::Path;
+
+use super::models::{NewSongLogEntry, SongLogEntry};
+use super::queries;
+use crate::api::{Error, SwStation};
+
+/// Maximum number of entries which get returned by a song log query.
+const SONG_LOG_LIMIT: i64 = 1000;
+
+/// Persistent log of all song titles, regardless of whether they got recorded.
+pub struct SongLog;
+
+impl SongLog {
+    pub fn add(station: &SwStation, title: &str) {
+        if let Err(err) = queries::insert_song_log_entry(NewSongLogEntry::new(station, title)) {
+            warn!("Unable to add song title to song log: {}", err);
+        }
+    }
+
+    /// Returns the most recent entries, newest first. The filter matches
+    /// against the song title and the station name.
+    pub fn entries(filter: Option<&str>) -> Vec<SongLogEntry> {
+        let filter = filter.map(str::trim).filter(|f| !f.is_empty());
+        queries::song_log_entries(filter, Some(SONG_LOG_LIMIT)).unwrap_or_else(|err| {
+            warn!("Unable to load song log: {}", err);
+            Vec::new()
+        })
+    }
+
+    /// Exports all entries which are matching the filter, without the limit of `entries()`.
+    pub fn export_csv(filter: Option<&str>, path: &Path) -> Result<(), Error> {
+        let filter = filter.map(str::trim).filter(|f| !f.is_empty());
+        let entries = queries::song_log_entries(filter, None)?;
+
+        let mut data = String::from("time,station,title\n");
+        for entry in &entries {
+            let time = Local.timestamp(entry.timestamp, 0).to_rfc3339();
+            data += &format!("{},{},{}\n", csv_escape(&time), csv_escape(&entry.station_name), csv_escape(&entry.title));
+        }
+
+        fs::write(path, data)?;
+        debug!("Exported {} song log entries to {:?}", entries.len(), path);
+        Ok(())
+    }
+
+    pub fn clear() {
+        if let Err(err) = queries::delete_song_log() {
+            warn!("Unable to clear song log: {}", err);
+        }
+    }
+}
+
+fn csv_escape(value: &str) -> String {
+    if value.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,40 @@
+// Shortwave - station_settings.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::models::StationSettingsEntry;
+use super::queries;
+
+/// Access to the per-station settings which are stored in the database.
+pub struct StationSettings;
+
+impl StationSettings {
+    pub fn get(station_uuid: &str) -> StationSettingsEntry {
+        match queries::station_settings(station_uuid) {
+            Ok(entry) => entry.unwrap_or_else(|| StationSettingsEntry::new(station_uuid)),
+            Err(err) => {
+                warn!("Unable to load settings of station {}: {}", station_uuid, err);
+                StationSettingsEntry::new(station_uuid)
+            }
+        }
+    }
+
+    pub fn set(entry: StationSettingsEntry) {
+        let uuid = entry.station_uuid.clone();
+        if let Err(err) = queries::update_station_settings(entry) {
+            warn!("Unable to update settings of station {}: {}", uuid, err);
+        }
+    }
+}
@@ -32,5 +32,20 @@ pub enum Key {
     RecorderSaveCount,
     RecorderSongDurationThreshold,
     RecorderSongSavePath,
+    RecorderFilenameTemplate,
+    RecorderSplitOnTitle,
+    RecorderFormat,
+    RecorderBitrate,
+    SongTitlePatterns,
     PlaybackVolume,
+    PlaybackAutoplay,
+    PlaybackLastStation,
+    SleepTimerFadeOut,
+    TimeshiftBufferSize,
+    StreamQuality,
+    EqualizerEnabled,
+    EqualizerPreset,
+    EqualizerGains,
+    EqualizerCustomPresets,
+    LoudnessNormalization,
 }
@@ -14,10 +14,19 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use adw::prelude::*;
 use adw::PreferencesWindow;
-use gtk::prelude::*;
+use glib::clone;
+use gtk::glib;
 
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use crate::audio::{equalizer_gains, set_equalizer_gains, EqualizerGains, EqualizerPreset, RecordingFormat, StreamQuality, EQUALIZER_FREQUENCIES, EQUALIZER_MAX_GAIN};
+use crate::database::{AutoSaveRuleEntry, AutoSaveRules};
+use crate::i18n::*;
 use crate::settings::{settings_manager, Key};
+use crate::ui::{auto_save_rule_title, SwAutoSaveRuleDialog};
 
 pub struct SettingsWindow {
     pub widget: PreferencesWindow,
@@ -36,6 +45,7 @@ impl SettingsWindow {
 
         window.setup_widgets();
         window.setup_signals();
+        window.setup_equalizer();
         window
     }
 
@@ -46,7 +56,10 @@ impl SettingsWindow {
     fn setup_widgets(&self) {
         let manager = adw::StyleManager::default().unwrap();
         get_widget!(self.builder, gtk::Widget, appearance_group);
-        appearance_group.set_visible(!manager.system_supports_color_schemes())
+        appearance_group.set_visible(!manager.system_supports_color_schemes());
+
+        get_widget!(self.builder, gtk::ListBox, auto_save_rules_listbox);
+        Self::refresh_auto_save_rules(&auto_save_rules_listbox);
     }
 
     fn setup_signals(&self) {
@@ -55,5 +68,302 @@ impl SettingsWindow {
 
         get_widget!(self.builder, gtk::Switch, show_notifications_button);
         settings_manager::bind_property(Key::Notifications, &show_notifications_button, "active");
+
+        get_widget!(self.builder, gtk::Switch, autoplay_button);
+        settings_manager::bind_property(Key::PlaybackAutoplay, &autoplay_button, "active");
+
+        get_widget!(self.builder, gtk::Switch, sleep_timer_fade_out_button);
+        settings_manager::bind_property(Key::SleepTimerFadeOut, &sleep_timer_fade_out_button, "active");
+
+        get_widget!(self.builder, gtk::Switch, loudness_normalization_button);
+        settings_manager::bind_property(Key::LoudnessNormalization, &loudness_normalization_button, "active");
+
+        get_widget!(self.builder, gtk::SpinButton, timeshift_spinbutton);
+        settings_manager::bind_property(Key::TimeshiftBufferSize, &timeshift_spinbutton, "value");
+
+        get_widget!(self.builder, adw::ComboRow, stream_quality_row);
+        let quality = StreamQuality::from_settings();
+        let position = StreamQuality::ALL.iter().position(|q| *q == quality).unwrap_or(0);
+        stream_quality_row.set_selected(position as u32);
+
+        stream_quality_row.connect_selected_notify(move |row| {
+            if let Some(quality) = StreamQuality::ALL.get(row.selected() as usize) {
+                settings_manager::set_string(Key::StreamQuality, quality.to_string());
+            }
+        });
+
+        get_widget!(self.builder, gtk::Switch, split_on_title_button);
+        settings_manager::bind_property(Key::RecorderSplitOnTitle, &split_on_title_button, "active");
+
+        get_widget!(self.builder, gtk::Entry, filename_template_entry);
+        settings_manager::bind_property(Key::RecorderFilenameTemplate, &filename_template_entry, "text");
+
+        get_widget!(self.builder, gtk::SpinButton, bitrate_spinbutton);
+        settings_manager::bind_property(Key::RecorderBitrate, &bitrate_spinbutton, "value");
+
+        get_widget!(self.builder, adw::ComboRow, recording_format_row);
+        get_widget!(self.builder, adw::ActionRow, bitrate_row);
+        let format = RecordingFormat::from_settings();
+        let position = RecordingFormat::ALL.iter().position(|f| *f == format).unwrap_or(0);
+        recording_format_row.set_selected(position as u32);
+        bitrate_row.set_sensitive(format.has_bitrate());
+
+        recording_format_row.connect_selected_notify(move |row| {
+            if let Some(format) = RecordingFormat::ALL.get(row.selected() as usize) {
+                settings_manager::set_string(Key::RecorderFormat, format.to_string());
+                bitrate_row.set_sensitive(format.has_bitrate());
+            }
+        });
+
+        get_widget!(self.builder, gtk::ListBox, auto_save_rules_listbox);
+        get_widget!(self.builder, gtk::Button, add_auto_save_rule_button);
+        add_auto_save_rule_button.connect_clicked(clone!(@weak self.widget as window, @weak auto_save_rules_listbox as listbox => move |_| {
+            let dialog = SwAutoSaveRuleDialog::new(window.upcast_ref());
+            dialog.connect_close_request(clone!(@weak listbox => @default-return glib::signal::Inhibit(false), move |_| {
+                Self::refresh_auto_save_rules(&listbox);
+                glib::signal::Inhibit(false)
+            }));
+            dialog.show();
+        }));
+    }
+
+    fn setup_equalizer(&self) {
+        get_widget!(self.builder, gtk::Switch, equalizer_switch);
+        settings_manager::bind_property(Key::EqualizerEnabled, &equalizer_switch, "active");
+
+        // The equalizer can only be adjusted while it's enabled
+        get_widget!(self.builder, gtk::Widget, equalizer_preset_row);
+        get_widget!(self.builder, gtk::Widget, equalizer_bands_group);
+        get_widget!(self.builder, gtk::Widget, custom_preset_group);
+        let widgets = vec![equalizer_preset_row, equalizer_bands_group, custom_preset_group];
+        for widget in &widgets {
+            widget.set_sensitive(equalizer_switch.is_active());
+        }
+        equalizer_switch.connect_active_notify(move |switch| {
+            for widget in &widgets {
+                widget.set_sensitive(switch.is_active());
+            }
+        });
+
+        let page = EqualizerPage::new(&self.builder);
+
+        get_widget!(self.builder, gtk::Entry, preset_name_entry);
+        get_widget!(self.builder, gtk::Button, save_preset_button);
+        preset_name_entry.connect_changed(clone!(@weak save_preset_button => move |entry| {
+            save_preset_button.set_sensitive(!entry.text().trim().is_empty());
+        }));
+
+        save_preset_button.connect_clicked(clone!(@strong page, @weak preset_name_entry => move |_| {
+            let name = preset_name_entry.text().trim().to_string();
+            let preset = EqualizerPreset::save_custom(&name, &page.gains());
+            settings_manager::set_string(Key::EqualizerPreset, preset.id);
+
+            page.refresh_presets();
+            preset_name_entry.set_text("");
+        }));
+    }
+
+    /// Reloads the auto-save rules from the database.
+    fn refresh_auto_save_rules(listbox: &gtk::ListBox) {
+        while let Some(child) = listbox.first_child() {
+            listbox.remove(&child);
+        }
+
+        let rules = AutoSaveRules::entries();
+        for rule in &rules {
+            listbox.append(&Self::create_auto_save_rule_row(listbox, rule));
+        }
+
+        listbox.set_visible(!rules.is_empty());
+    }
+
+    fn create_auto_save_rule_row(listbox: &gtk::ListBox, rule: &AutoSaveRuleEntry) -> adw::ActionRow {
+        let row = adw::ActionRow::new();
+        row.set_title(&auto_save_rule_title(rule));
+        if rule.min_duration > 0 {
+            let minutes = (rule.min_duration / 60).to_string();
+            row.set_subtitle(Some(&i18n_f("Longer than {} minutes", &[&minutes])));
+        }
+
+        let switch = gtk::Switch::new();
+        switch.set_active(rule.enabled);
+        switch.set_valign(gtk::Align::Center);
+        row.add_suffix(&switch);
+        row.set_activatable_widget(Some(&switch));
+
+        let remove_button = gtk::Button::from_icon_name(Some("user-trash-symbolic"));
+        remove_button.set_tooltip_text(Some(&i18n("Remove Rule")));
+        remove_button.set_valign(gtk::Align::Center);
+        remove_button.add_css_class("flat");
+        row.add_suffix(&remove_button);
+
+        let id = rule.id;
+        switch.connect_active_notify(move |switch| AutoSaveRules::set_enabled(id, switch.is_active()));
+        remove_button.connect_clicked(clone!(@weak listbox => move |_| {
+            AutoSaveRules::remove(id);
+            Self::refresh_auto_save_rules(&listbox);
+        }));
+
+        row
+    }
+}
+
+/// The preset row and the band scales of the equalizer page, which have to be kept in sync.
+struct EqualizerPage {
+    preset_row: adw::ComboRow,
+    remove_preset_button: gtk::Button,
+    scales: Vec<gtk::Scale>,
+    /// Same order as in the preset row, which additionally has a "Manual" entry at the end
+    presets: RefCell<Vec<EqualizerPreset>>,
+    /// Set while the widgets are getting updated, so that the changes don't get stored again
+    updating: Cell<bool>,
+}
+
+impl EqualizerPage {
+    fn new(builder: &gtk::Builder) -> Rc<Self> {
+        get_widget!(builder, adw::ComboRow, equalizer_preset_row);
+        get_widget!(builder, gtk::Button, remove_preset_button);
+        get_widget!(builder, gtk::Box, equalizer_bands_box);
+
+        let gains = equalizer_gains();
+        let scales = EQUALIZER_FREQUENCIES
+            .iter()
+            .zip(gains.iter())
+            .map(|(frequency, gain)| {
+                let scale = gtk::Scale::with_range(gtk::Orientation::Vertical, -EQUALIZER_MAX_GAIN, EQUALIZER_MAX_GAIN, 1.0);
+                scale.set_inverted(true);
+                scale.set_height_request(180);
+                scale.set_draw_value(true);
+                scale.set_value_pos(gtk::PositionType::Bottom);
+                scale.set_digits(0);
+                scale.add_mark(0.0, gtk::PositionType::Right, None);
+                scale.set_value(*gain);
+
+                let label = gtk::Label::new(Some(&Self::frequency_text(*frequency)));
+                label.add_css_class("caption");
+                label.add_css_class("dim-label");
+
+                let band = gtk::Box::new(gtk::Orientation::Vertical, 6);
+                band.append(&scale);
+                band.append(&label);
+                equalizer_bands_box.append(&band);
+
+                scale
+            })
+            .collect();
+
+        let page = Rc::new(Self {
+            preset_row: equalizer_preset_row,
+            remove_preset_button,
+            scales,
+            presets: RefCell::new(Vec::new()),
+            updating: Cell::new(false),
+        });
+
+        page.refresh_presets();
+        page.clone().setup_signals();
+        page
+    }
+
+    fn setup_signals(self: Rc<Self>) {
+        self.preset_row.connect_selected_notify(clone!(@strong self as this => move |row| {
+            if this.updating.get() {
+                return;
+            }
+
+            let preset = this.presets.borrow().get(row.selected() as usize).cloned();
+            match preset {
+                Some(preset) => {
+                    settings_manager::set_string(Key::EqualizerPreset, preset.id.clone());
+                    this.set_gains(&preset.gains);
+                    set_equalizer_gains(&preset.gains);
+                }
+                // "Manual" keeps the current bands
+                None => settings_manager::set_string(Key::EqualizerPreset, String::new()),
+            }
+
+            this.update_remove_button();
+        }));
+
+        for scale in &self.scales {
+            scale.connect_value_changed(clone!(@strong self as this => move |_| {
+                if this.updating.get() {
+                    return;
+                }
+
+                set_equalizer_gains(&this.gains());
+
+                // The bands don't match the preset anymore
+                if this.selected_preset().is_some() {
+                    settings_manager::set_string(Key::EqualizerPreset, String::new());
+                    this.refresh_presets();
+                }
+            }));
+        }
+
+        self.remove_preset_button.connect_clicked(clone!(@strong self as this => move |_| {
+            if let Some(preset) = this.selected_preset() {
+                preset.remove_custom();
+                settings_manager::set_string(Key::EqualizerPreset, String::new());
+                this.refresh_presets();
+            }
+        }));
+    }
+
+    /// Reloads the presets, and selects the one which is stored in the settings.
+    fn refresh_presets(&self) {
+        let presets = EqualizerPreset::all();
+
+        let model = gtk::StringList::new(&[]);
+        for preset in &presets {
+            model.append(&preset.name);
+        }
+        model.append(&i18n("Manual"));
+
+        let id = settings_manager::string(Key::EqualizerPreset);
+        let position = presets.iter().position(|preset| preset.id == id).unwrap_or(presets.len());
+        *self.presets.borrow_mut() = presets;
+
+        self.updating.set(true);
+        self.preset_row.set_model(Some(&model));
+        self.preset_row.set_selected(position as u32);
+        self.updating.set(false);
+
+        self.update_remove_button();
+    }
+
+    /// Only custom presets can be removed.
+    fn update_remove_button(&self) {
+        let is_custom = self.selected_preset().map_or(false, |preset| preset.is_custom());
+        self.remove_preset_button.set_visible(is_custom);
+    }
+
+    fn selected_preset(&self) -> Option<EqualizerPreset> {
+        self.presets.borrow().get(self.preset_row.selected() as usize).cloned()
+    }
+
+    fn gains(&self) -> EqualizerGains {
+        let mut gains = EqualizerGains::default();
+        for (gain, scale) in gains.iter_mut().zip(&self.scales) {
+            *gain = scale.value();
+        }
+        gains
+    }
+
+    fn set_gains(&self, gains: &EqualizerGains) {
+        self.updating.set(true);
+        for (scale, gain) in self.scales.iter().zip(gains) {
+            scale.set_value(*gain);
+        }
+        self.updating.set(false);
+    }
+
+    fn frequency_text(frequency: u32) -> String {
+        if frequency < 1000 {
+            i18n_f("{} Hz", &[&frequency.to_string()])
+        } else {
+            let khz = (f64::from(frequency) / 1000.0).round();
+            i18n_f("{} kHz", &[&khz.to_string()])
+        }
     }
 }
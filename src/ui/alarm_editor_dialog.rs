@@ -0,0 +1,215 @@
+// Shortwave - alarm_editor_dialog.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use glib::clone;
+use glib::Sender;
+use gtk::subclass::prelude::*;
+use gtk::CompositeTemplate;
+use gtk::{gio, glib};
+use once_cell::unsync::OnceCell;
+
+use std::cell::RefCell;
+
+use super::alarms_dialog::weekday_names;
+use crate::api::SwStation;
+use crate::app::{Action, SwApplication};
+use crate::database::{AlarmEntry, Alarms, NewAlarmEntry};
+use crate::i18n::*;
+
+mod imp {
+    use super::*;
+    use glib::subclass;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/de/haeckerfelix/Shortwave/gtk/alarm_editor_dialog.ui")]
+    pub struct SwAlarmEditorDialog {
+        #[template_child]
+        pub save_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub hour_spinbutton: TemplateChild<gtk::SpinButton>,
+        #[template_child]
+        pub minute_spinbutton: TemplateChild<gtk::SpinButton>,
+        #[template_child]
+        pub weekday_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub station_combobox: TemplateChild<gtk::ComboBoxText>,
+        #[template_child]
+        pub volume_scale: TemplateChild<gtk::Scale>,
+        #[template_child]
+        pub inhibit_switch: TemplateChild<gtk::Switch>,
+        #[template_child]
+        pub remove_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub remove_button: TemplateChild<gtk::Button>,
+
+        pub weekday_buttons: RefCell<Vec<gtk::ToggleButton>>,
+        pub alarm: RefCell<Option<AlarmEntry>>,
+        pub sender: OnceCell<Sender<Action>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SwAlarmEditorDialog {
+        const NAME: &'static str = "SwAlarmEditorDialog";
+        type ParentType = adw::Window;
+        type Type = super::SwAlarmEditorDialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.install_action("dialog.close", None, |this, _, _| {
+                this.hide();
+                this.close();
+            });
+
+            Self::bind_template(klass);
+        }
+
+        fn instance_init(obj: &subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for SwAlarmEditorDialog {}
+
+    impl WidgetImpl for SwAlarmEditorDialog {}
+
+    impl WindowImpl for SwAlarmEditorDialog {}
+
+    impl AdwWindowImpl for SwAlarmEditorDialog {}
+}
+
+glib::wrapper! {
+    pub struct SwAlarmEditorDialog(ObjectSubclass<imp::SwAlarmEditorDialog>)
+        @extends gtk::Widget, gtk::Window, adw::Window;
+}
+
+impl SwAlarmEditorDialog {
+    /// Creates a new dialog for editing an alarm.
+    /// If no alarm is passed, a new alarm gets created.
+    pub fn new(sender: Sender<Action>, alarm: Option<AlarmEntry>, parent: &gtk::Window) -> Self {
+        let dialog = glib::Object::new(&[]).unwrap();
+
+        let imp = imp::SwAlarmEditorDialog::from_instance(&dialog);
+        *imp.alarm.borrow_mut() = alarm;
+        imp.sender.set(sender).unwrap();
+
+        dialog.set_transient_for(Some(parent));
+
+        dialog.setup_widgets();
+        dialog.setup_signals();
+        dialog
+    }
+
+    fn setup_widgets(&self) {
+        let imp = imp::SwAlarmEditorDialog::from_instance(self);
+
+        for name in weekday_names().iter() {
+            let button = gtk::ToggleButton::with_label(name);
+            imp.weekday_box.append(&button);
+            imp.weekday_buttons.borrow_mut().push(button);
+        }
+
+        // Only library stations can be used for alarms
+        let app = gio::Application::default().unwrap().downcast::<SwApplication>().unwrap();
+        let model = app.library().model();
+        for pos in 0..model.n_items() {
+            let station = model.item(pos).unwrap().downcast::<SwStation>().unwrap();
+            imp.station_combobox.append(Some(&station.uuid()), &station.metadata().name);
+        }
+
+        let alarm = imp.alarm.borrow().clone();
+        if let Some(alarm) = alarm {
+            self.set_title(Some(&i18n("Edit Alarm")));
+            imp.save_button.set_label(&i18n("_Save"));
+            imp.remove_group.set_visible(true);
+
+            imp.hour_spinbutton.set_value(alarm.hour.into());
+            imp.minute_spinbutton.set_value(alarm.minute.into());
+            for (day, button) in imp.weekday_buttons.borrow().iter().enumerate() {
+                button.set_active(alarm.weekdays & (1 << day) != 0);
+            }
+            imp.station_combobox.set_active_id(Some(&alarm.station_uuid));
+            imp.volume_scale.set_value(alarm.volume);
+            imp.inhibit_switch.set_active(alarm.inhibit);
+        } else {
+            imp.hour_spinbutton.set_value(7.0);
+            imp.volume_scale.set_value(0.5);
+        }
+
+        self.update_save_button();
+    }
+
+    fn setup_signals(&self) {
+        let imp = imp::SwAlarmEditorDialog::from_instance(self);
+
+        imp.station_combobox.connect_changed(clone!(@weak self as this => move |_| this.update_save_button()));
+
+        imp.save_button.connect_clicked(clone!(@weak self as this => move |_| {
+            let imp = imp::SwAlarmEditorDialog::from_instance(&this);
+
+            if let Some(entry) = this.entry() {
+                match imp.alarm.borrow().as_ref() {
+                    Some(alarm) => Alarms::update(alarm.id, entry),
+                    None => Alarms::add(entry),
+                }
+                send!(imp.sender.get().unwrap(), Action::AlarmsChanged);
+            }
+
+            this.hide();
+            this.close();
+        }));
+
+        imp.remove_button.connect_clicked(clone!(@weak self as this => move |_| {
+            let imp = imp::SwAlarmEditorDialog::from_instance(&this);
+
+            if let Some(alarm) = imp.alarm.borrow().as_ref() {
+                Alarms::remove(alarm.id);
+                send!(imp.sender.get().unwrap(), Action::AlarmsChanged);
+            }
+
+            this.hide();
+            this.close();
+        }));
+    }
+
+    fn update_save_button(&self) {
+        let imp = imp::SwAlarmEditorDialog::from_instance(self);
+        imp.save_button.set_sensitive(imp.station_combobox.active_id().is_some());
+    }
+
+    /// Returns an alarm based on the entered values.
+    fn entry(&self) -> Option<NewAlarmEntry> {
+        let imp = imp::SwAlarmEditorDialog::from_instance(self);
+
+        let weekdays = imp
+            .weekday_buttons
+            .borrow()
+            .iter()
+            .enumerate()
+            .filter(|(_, button)| button.is_active())
+            .fold(0, |weekdays, (day, _)| weekdays | (1 << day));
+
+        Some(NewAlarmEntry {
+            enabled: true,
+            hour: imp.hour_spinbutton.value_as_int(),
+            minute: imp.minute_spinbutton.value_as_int(),
+            weekdays,
+            station_uuid: imp.station_combobox.active_id()?.to_string(),
+            volume: imp.volume_scale.value(),
+            inhibit: imp.inhibit_switch.is_active(),
+        })
+    }
+}
@@ -0,0 +1,187 @@
+// Shortwave - alarms_dialog.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use glib::clone;
+use glib::Sender;
+use gtk::subclass::prelude::*;
+use gtk::CompositeTemplate;
+use gtk::{gio, glib};
+use once_cell::unsync::OnceCell;
+
+use crate::app::{Action, SwApplication};
+use crate::database::{AlarmEntry, Alarms};
+use crate::i18n::*;
+use crate::ui::SwAlarmEditorDialog;
+
+mod imp {
+    use super::*;
+    use glib::subclass;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/de/haeckerfelix/Shortwave/gtk/alarms_dialog.ui")]
+    pub struct SwAlarmsDialog {
+        #[template_child]
+        pub add_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub listbox: TemplateChild<gtk::ListBox>,
+
+        pub sender: OnceCell<Sender<Action>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SwAlarmsDialog {
+        const NAME: &'static str = "SwAlarmsDialog";
+        type ParentType = adw::Window;
+        type Type = super::SwAlarmsDialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.install_action("dialog.close", None, |this, _, _| {
+                this.hide();
+                this.close();
+            });
+
+            Self::bind_template(klass);
+        }
+
+        fn instance_init(obj: &subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for SwAlarmsDialog {}
+
+    impl WidgetImpl for SwAlarmsDialog {}
+
+    impl WindowImpl for SwAlarmsDialog {}
+
+    impl AdwWindowImpl for SwAlarmsDialog {}
+}
+
+glib::wrapper! {
+    pub struct SwAlarmsDialog(ObjectSubclass<imp::SwAlarmsDialog>)
+        @extends gtk::Widget, gtk::Window, adw::Window;
+}
+
+impl SwAlarmsDialog {
+    pub fn new(sender: Sender<Action>) -> Self {
+        let dialog = glib::Object::new(&[]).unwrap();
+
+        let imp = imp::SwAlarmsDialog::from_instance(&dialog);
+        imp.sender.set(sender).unwrap();
+
+        let window = gio::Application::default().unwrap().downcast_ref::<SwApplication>().unwrap().active_window().unwrap();
+        dialog.set_transient_for(Some(&window));
+
+        dialog.setup_signals();
+        dialog.refresh();
+        dialog
+    }
+
+    fn setup_signals(&self) {
+        let imp = imp::SwAlarmsDialog::from_instance(self);
+
+        imp.add_button.connect_clicked(clone!(@weak self as this => move |_| this.show_editor(None)));
+    }
+
+    /// Reloads the alarms from the database.
+    fn refresh(&self) {
+        let imp = imp::SwAlarmsDialog::from_instance(self);
+
+        while let Some(child) = imp.listbox.first_child() {
+            imp.listbox.remove(&child);
+        }
+
+        let alarms = Alarms::entries();
+        for alarm in &alarms {
+            imp.listbox.append(&self.create_row(alarm));
+        }
+
+        if alarms.is_empty() {
+            imp.stack.set_visible_child_name("empty");
+        } else {
+            imp.stack.set_visible_child_name("content");
+        }
+    }
+
+    fn show_editor(&self, alarm: Option<AlarmEntry>) {
+        let imp = imp::SwAlarmsDialog::from_instance(self);
+
+        let editor = SwAlarmEditorDialog::new(imp.sender.get().unwrap().clone(), alarm, self.upcast_ref());
+        editor.connect_close_request(clone!(@weak self as this => @default-return glib::signal::Inhibit(false), move |_| {
+            this.refresh();
+            glib::signal::Inhibit(false)
+        }));
+        editor.show();
+    }
+
+    fn create_row(&self, alarm: &AlarmEntry) -> adw::ActionRow {
+        let imp = imp::SwAlarmsDialog::from_instance(self);
+
+        let app = gio::Application::default().unwrap().downcast::<SwApplication>().unwrap();
+        let station_name = match app.library().station(&alarm.station_uuid) {
+            Some(station) => station.metadata().name,
+            None => i18n("Unknown station"),
+        };
+
+        let row = adw::ActionRow::new();
+        row.set_title(&format!("{:02}:{:02}", alarm.hour, alarm.minute));
+        row.set_subtitle(Some(&format!("{} · {}", station_name, weekdays_text(alarm.weekdays))));
+        row.set_activatable(true);
+
+        let switch = gtk::Switch::new();
+        switch.set_active(alarm.enabled);
+        switch.set_valign(gtk::Align::Center);
+        row.add_suffix(&switch);
+
+        let id = alarm.id;
+        let sender = imp.sender.get().unwrap().clone();
+        switch.connect_active_notify(move |switch| {
+            Alarms::set_enabled(id, switch.is_active());
+            send!(sender, Action::AlarmsChanged);
+        });
+
+        let alarm = alarm.clone();
+        row.connect_activated(clone!(@weak self as this => move |_| this.show_editor(Some(alarm.clone()))));
+
+        row
+    }
+}
+
+/// Short names of the weekdays, starting with Monday.
+pub fn weekday_names() -> Vec<String> {
+    vec![i18n("Mon"), i18n("Tue"), i18n("Wed"), i18n("Thu"), i18n("Fri"), i18n("Sat"), i18n("Sun")]
+}
+
+/// Returns a human readable summary of the weekdays bitmask of an alarm.
+fn weekdays_text(weekdays: i32) -> String {
+    match weekdays & 0b111_1111 {
+        0 => i18n("Once"),
+        0b111_1111 => i18n("Every day"),
+        0b001_1111 => i18n("Weekdays"),
+        0b110_0000 => i18n("Weekends"),
+        weekdays => weekday_names()
+            .into_iter()
+            .enumerate()
+            .filter(|(day, _)| weekdays & (1 << day) != 0)
+            .map(|(_, name)| name)
+            .collect::<Vec<String>>()
+            .join(", "),
+    }
+}
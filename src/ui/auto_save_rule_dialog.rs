@@ -0,0 +1,203 @@
+// Shortwave - auto_save_rule_dialog.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use glib::clone;
+use gtk::subclass::prelude::*;
+use gtk::CompositeTemplate;
+use gtk::{gio, glib};
+use regex::Regex;
+
+use crate::api::SwStation;
+use crate::app::SwApplication;
+use crate::database::{AutoSaveRuleEntry, AutoSaveRuleKind, AutoSaveRules, NewAutoSaveRuleEntry};
+use crate::i18n::*;
+
+mod imp {
+    use super::*;
+    use glib::subclass;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/de/haeckerfelix/Shortwave/gtk/auto_save_rule_dialog.ui")]
+    pub struct SwAutoSaveRuleDialog {
+        #[template_child]
+        pub add_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub kind_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub station_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub station_combobox: TemplateChild<gtk::ComboBoxText>,
+        #[template_child]
+        pub value_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub value_entry: TemplateChild<gtk::Entry>,
+        #[template_child]
+        pub duration_spinbutton: TemplateChild<gtk::SpinButton>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SwAutoSaveRuleDialog {
+        const NAME: &'static str = "SwAutoSaveRuleDialog";
+        type ParentType = adw::Window;
+        type Type = super::SwAutoSaveRuleDialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.install_action("dialog.close", None, |this, _, _| {
+                this.hide();
+                this.close();
+            });
+
+            Self::bind_template(klass);
+        }
+
+        fn instance_init(obj: &subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for SwAutoSaveRuleDialog {}
+
+    impl WidgetImpl for SwAutoSaveRuleDialog {}
+
+    impl WindowImpl for SwAutoSaveRuleDialog {}
+
+    impl AdwWindowImpl for SwAutoSaveRuleDialog {}
+}
+
+glib::wrapper! {
+    pub struct SwAutoSaveRuleDialog(ObjectSubclass<imp::SwAutoSaveRuleDialog>)
+        @extends gtk::Widget, gtk::Window, adw::Window;
+}
+
+impl SwAutoSaveRuleDialog {
+    pub fn new(parent: &gtk::Window) -> Self {
+        let dialog: Self = glib::Object::new(&[]).unwrap();
+        dialog.set_transient_for(Some(parent));
+
+        dialog.setup_widgets();
+        dialog.setup_signals();
+        dialog
+    }
+
+    fn setup_widgets(&self) {
+        let imp = imp::SwAutoSaveRuleDialog::from_instance(self);
+
+        let app = gio::Application::default().unwrap().downcast::<SwApplication>().unwrap();
+        let model = app.library().model();
+        for pos in 0..model.n_items() {
+            let station = model.item(pos).unwrap().downcast::<SwStation>().unwrap();
+            imp.station_combobox.append(Some(&station.uuid()), &station.metadata().name);
+        }
+
+        self.update_rows();
+    }
+
+    fn setup_signals(&self) {
+        let imp = imp::SwAutoSaveRuleDialog::from_instance(self);
+
+        imp.kind_row.connect_selected_notify(clone!(@weak self as this => move |_| this.update_rows()));
+        imp.station_combobox.connect_changed(clone!(@weak self as this => move |_| this.update_add_button()));
+        imp.value_entry.connect_changed(clone!(@weak self as this => move |_| this.update_add_button()));
+
+        imp.add_button.connect_clicked(clone!(@weak self as this => move |_| {
+            let imp = imp::SwAutoSaveRuleDialog::from_instance(&this);
+
+            if let Some(value) = this.value() {
+                let min_duration = imp.duration_spinbutton.value_as_int() * 60;
+                AutoSaveRules::add(NewAutoSaveRuleEntry::new(this.kind(), &value, min_duration));
+            }
+
+            this.hide();
+            this.close();
+        }));
+    }
+
+    /// Only shows the rows which are needed for the selected kind of rule.
+    fn update_rows(&self) {
+        let imp = imp::SwAutoSaveRuleDialog::from_instance(self);
+        let kind = self.kind();
+
+        imp.station_row.set_visible(kind == AutoSaveRuleKind::Station);
+        imp.value_row.set_visible(matches!(kind, AutoSaveRuleKind::Title | AutoSaveRuleKind::Artist));
+
+        match kind {
+            AutoSaveRuleKind::Title => {
+                imp.value_row.set_title(&i18n("Regular Expression"));
+                imp.value_row.set_subtitle(Some(&i18n("Gets matched against “Artist - Title”")));
+            }
+            AutoSaveRuleKind::Artist => {
+                imp.value_row.set_title(&i18n("Artists"));
+                imp.value_row.set_subtitle(Some(&i18n("Separated by commas")));
+            }
+            _ => (),
+        }
+
+        self.update_add_button();
+    }
+
+    fn update_add_button(&self) {
+        let imp = imp::SwAutoSaveRuleDialog::from_instance(self);
+        let value = self.value();
+
+        // Highlight invalid regular expressions
+        let invalid_regex = self.kind() == AutoSaveRuleKind::Title && !imp.value_entry.text().is_empty() && value.is_none();
+        if invalid_regex {
+            imp.value_entry.add_css_class("error");
+        } else {
+            imp.value_entry.remove_css_class("error");
+        }
+
+        imp.add_button.set_sensitive(value.is_some());
+    }
+
+    fn kind(&self) -> AutoSaveRuleKind {
+        let imp = imp::SwAutoSaveRuleDialog::from_instance(self);
+        AutoSaveRuleKind::ALL.get(imp.kind_row.selected() as usize).copied().unwrap_or(AutoSaveRuleKind::AnySong)
+    }
+
+    /// Returns the value of the rule, or `None` if the input isn't valid.
+    fn value(&self) -> Option<String> {
+        let imp = imp::SwAutoSaveRuleDialog::from_instance(self);
+        let text = imp.value_entry.text().trim().to_string();
+
+        match self.kind() {
+            AutoSaveRuleKind::AnySong => Some(String::new()),
+            AutoSaveRuleKind::Station => imp.station_combobox.active_id().map(|id| id.to_string()),
+            AutoSaveRuleKind::Title => Some(text).filter(|t| !t.is_empty() && Regex::new(t).is_ok()),
+            AutoSaveRuleKind::Artist => Some(text).filter(|t| !t.is_empty()),
+        }
+    }
+}
+
+/// Returns a human readable summary of an auto-save rule.
+pub fn auto_save_rule_title(rule: &AutoSaveRuleEntry) -> String {
+    match rule.kind() {
+        Some(AutoSaveRuleKind::AnySong) => i18n("All songs"),
+        Some(AutoSaveRuleKind::Station) => {
+            let app = gio::Application::default().unwrap().downcast::<SwApplication>().unwrap();
+            let station_name = match app.library().station(&rule.value) {
+                Some(station) => station.metadata().name,
+                None => i18n("Unknown station"),
+            };
+            i18n_f("Songs from “{}”", &[&station_name])
+        }
+        Some(AutoSaveRuleKind::Title) => i18n_f("Titles matching “{}”", &[&rule.value]),
+        Some(AutoSaveRuleKind::Artist) => i18n_f("Songs by {}", &[&rule.value]),
+        None => rule.kind.clone(),
+    }
+}
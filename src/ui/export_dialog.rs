@@ -0,0 +1,178 @@
+// Shortwave - export_dialog.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use glib::clone;
+use glib::Sender;
+use gtk::subclass::prelude::*;
+use gtk::CompositeTemplate;
+use gtk::{gio, glib};
+use once_cell::unsync::OnceCell;
+
+use std::cell::RefCell;
+
+use crate::api::{PlaylistFormat, SwStation};
+use crate::app::{Action, SwApplication};
+use crate::config;
+use crate::i18n::*;
+
+mod imp {
+    use super::*;
+    use glib::subclass;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/de/haeckerfelix/Shortwave/gtk/export_dialog.ui")]
+    pub struct SwExportDialog {
+        #[template_child]
+        pub export_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub m3u_checkbutton: TemplateChild<gtk::CheckButton>,
+        #[template_child]
+        pub pls_checkbutton: TemplateChild<gtk::CheckButton>,
+        #[template_child]
+        pub xspf_checkbutton: TemplateChild<gtk::CheckButton>,
+        #[template_child]
+        pub stations_group: TemplateChild<adw::PreferencesGroup>,
+
+        pub stations: RefCell<Vec<(gtk::CheckButton, SwStation)>>,
+        pub sender: OnceCell<Sender<Action>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SwExportDialog {
+        const NAME: &'static str = "SwExportDialog";
+        type ParentType = adw::Window;
+        type Type = super::SwExportDialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.install_action("dialog.close", None, |this, _, _| {
+                this.hide();
+                this.close();
+            });
+
+            Self::bind_template(klass);
+        }
+
+        fn instance_init(obj: &subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for SwExportDialog {}
+
+    impl WidgetImpl for SwExportDialog {}
+
+    impl WindowImpl for SwExportDialog {}
+
+    impl AdwWindowImpl for SwExportDialog {}
+}
+
+glib::wrapper! {
+    pub struct SwExportDialog(ObjectSubclass<imp::SwExportDialog>)
+        @extends gtk::Widget, gtk::Window, adw::Window;
+}
+
+impl SwExportDialog {
+    pub fn new(sender: Sender<Action>) -> Self {
+        let dialog = glib::Object::new(&[]).unwrap();
+
+        let imp = imp::SwExportDialog::from_instance(&dialog);
+        imp.sender.set(sender).unwrap();
+
+        let window = gio::Application::default().unwrap().downcast_ref::<SwApplication>().unwrap().active_window().unwrap();
+        dialog.set_transient_for(Some(&window));
+
+        dialog.setup_widgets();
+        dialog.setup_signals();
+        dialog
+    }
+
+    fn setup_widgets(&self) {
+        let imp = imp::SwExportDialog::from_instance(self);
+
+        let app = gio::Application::default().unwrap().downcast::<SwApplication>().unwrap();
+        let model = app.library().model();
+
+        // Every library station is selected by default
+        for pos in 0..model.n_items() {
+            let station = model.item(pos).unwrap().downcast::<SwStation>().unwrap();
+
+            let check_button = gtk::CheckButton::new();
+            check_button.set_active(true);
+            check_button.set_valign(gtk::Align::Center);
+
+            let row = adw::ActionRow::new();
+            row.set_title(&station.metadata().name);
+            row.add_prefix(&check_button);
+            row.set_activatable_widget(Some(&check_button));
+            imp.stations_group.add(&row);
+
+            imp.stations.borrow_mut().push((check_button, station));
+        }
+
+        imp.export_button.set_sensitive(model.n_items() != 0);
+    }
+
+    fn setup_signals(&self) {
+        let imp = imp::SwExportDialog::from_instance(self);
+
+        imp.export_button.connect_clicked(clone!(@weak self as this => move |_| {
+            let imp = imp::SwExportDialog::from_instance(&this);
+            let sender = imp.sender.get().unwrap().clone();
+
+            let stations: Vec<SwStation> = imp.stations.borrow().iter().filter(|(b, _)| b.is_active()).map(|(_, s)| s.clone()).collect();
+            let format = this.format();
+
+            let cancel_label = i18n("_Cancel");
+            let export_label = i18n("_Export");
+            let file_chooser = gtk::FileChooserDialog::new(
+                Some(&i18n("Export stations")),
+                Some(&this),
+                gtk::FileChooserAction::Save,
+                &[(cancel_label.as_str(), gtk::ResponseType::Cancel), (export_label.as_str(), gtk::ResponseType::Accept)],
+            );
+            file_chooser.set_modal(true);
+            file_chooser.set_current_name(&format!("{}.{}", config::NAME, format.extension()));
+
+            file_chooser.connect_response(clone!(@weak this => move |file_chooser, response| {
+                if response == gtk::ResponseType::Accept {
+                    if let Some(mut path) = file_chooser.file().and_then(|f| f.path()) {
+                        if PlaylistFormat::for_path(&path) != Some(format) {
+                            path.set_extension(format.extension());
+                        }
+                        send!(sender, Action::LibraryExportStations(stations.clone(), path));
+                    }
+                    this.close();
+                }
+                file_chooser.close();
+            }));
+            file_chooser.show();
+        }));
+    }
+
+    fn format(&self) -> PlaylistFormat {
+        let imp = imp::SwExportDialog::from_instance(self);
+
+        if imp.pls_checkbutton.is_active() {
+            PlaylistFormat::Pls
+        } else if imp.xspf_checkbutton.is_active() {
+            PlaylistFormat::Xspf
+        } else {
+            PlaylistFormat::M3u
+        }
+    }
+}
@@ -17,22 +17,38 @@
 pub mod pages;
 
 pub mod about_dialog;
+mod alarm_editor_dialog;
+mod alarms_dialog;
+mod auto_save_rule_dialog;
+mod export_dialog;
 pub mod featured_carousel;
 mod notification;
+mod recording_editor_dialog;
+mod recordings_dialog;
 mod song_listbox;
+mod song_log_dialog;
 mod song_row;
 mod station_dialog;
+mod station_editor_dialog;
 mod station_favicon;
 mod station_flowbox;
 mod station_row;
 mod streaming_dialog;
 mod window;
 
+pub use alarm_editor_dialog::SwAlarmEditorDialog;
+pub use alarms_dialog::SwAlarmsDialog;
+pub use auto_save_rule_dialog::{auto_save_rule_title, SwAutoSaveRuleDialog};
+pub use export_dialog::SwExportDialog;
 pub use featured_carousel::SwFeaturedCarousel;
 pub use notification::Notification;
+pub use recording_editor_dialog::SwRecordingEditorDialog;
+pub use recordings_dialog::SwRecordingsDialog;
 pub use song_listbox::SongListBox;
+pub use song_log_dialog::SwSongLogDialog;
 pub use song_row::SwSongRow;
 pub use station_dialog::SwStationDialog;
+pub use station_editor_dialog::SwStationEditorDialog;
 pub use station_favicon::FaviconSize;
 pub use station_favicon::StationFavicon;
 pub use station_flowbox::SwStationFlowBox;
@@ -0,0 +1,152 @@
+// Shortwave - history_page.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use chrono::{Local, TimeZone};
+use glib::{clone, Sender};
+use gtk::glib;
+use gtk::subclass::prelude::*;
+use gtk::CompositeTemplate;
+use once_cell::unsync::OnceCell;
+
+use crate::app::Action;
+use crate::database::{History, HistoryEntry};
+use crate::i18n::*;
+
+mod imp {
+    use super::*;
+    use glib::subclass;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/de/haeckerfelix/Shortwave/gtk/history_page.ui")]
+    pub struct SwHistoryPage {
+        #[template_child]
+        pub stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub listbox: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub clear_button: TemplateChild<gtk::Button>,
+
+        pub sender: OnceCell<Sender<Action>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SwHistoryPage {
+        const NAME: &'static str = "SwHistoryPage";
+        type ParentType = adw::Bin;
+        type Type = super::SwHistoryPage;
+
+        fn class_init(klass: &mut Self::Class) {
+            Self::bind_template(klass);
+        }
+
+        fn instance_init(obj: &subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for SwHistoryPage {}
+
+    impl WidgetImpl for SwHistoryPage {}
+
+    impl BinImpl for SwHistoryPage {}
+}
+
+glib::wrapper! {
+    pub struct SwHistoryPage(ObjectSubclass<imp::SwHistoryPage>)
+        @extends gtk::Widget, adw::Bin;
+}
+
+impl SwHistoryPage {
+    pub fn init(&self, sender: Sender<Action>) {
+        let imp = imp::SwHistoryPage::from_instance(self);
+        imp.sender.set(sender).unwrap();
+
+        self.setup_signals();
+        self.refresh();
+    }
+
+    /// Reloads the history entries from the database.
+    pub fn refresh(&self) {
+        let imp = imp::SwHistoryPage::from_instance(self);
+
+        while let Some(child) = imp.listbox.first_child() {
+            imp.listbox.remove(&child);
+        }
+
+        let entries = History::entries();
+        for entry in &entries {
+            if let Some(row) = self.create_row(entry) {
+                imp.listbox.append(&row);
+            }
+        }
+
+        if entries.is_empty() {
+            imp.stack.set_visible_child_name("empty");
+        } else {
+            imp.stack.set_visible_child_name("content");
+        }
+    }
+
+    fn setup_signals(&self) {
+        let imp = imp::SwHistoryPage::from_instance(self);
+
+        imp.clear_button.connect_clicked(clone!(@weak self as this => move |_| {
+            History::clear();
+            this.refresh();
+        }));
+    }
+
+    fn create_row(&self, entry: &HistoryEntry) -> Option<adw::ExpanderRow> {
+        let imp = imp::SwHistoryPage::from_instance(self);
+        let station = entry.station()?;
+        let song_titles = entry.song_titles();
+
+        let start = Local.timestamp(entry.start_time, 0);
+        let mut subtitle = start.format("%c").to_string();
+        if let Some(stop_time) = entry.stop_time {
+            let duration = (stop_time - entry.start_time).max(0) / 60;
+            // Listening duration of a history entry, "{}" is the number of minutes
+            subtitle += &format!(" · {}", ni18n_f("{} minute", "{} minutes", duration as u32, &[&duration.to_string()]));
+        }
+        if !song_titles.is_empty() {
+            subtitle += &format!(" · {}", ni18n_f("{} song", "{} songs", song_titles.len() as u32, &[&song_titles.len().to_string()]));
+        }
+
+        let row = adw::ExpanderRow::new();
+        row.set_title(&station.metadata().name);
+        row.set_subtitle(Some(&subtitle));
+        row.set_enable_expansion(!song_titles.is_empty());
+
+        for title in song_titles {
+            let song_row = adw::ActionRow::new();
+            song_row.set_title(&title);
+            row.add(&song_row);
+        }
+
+        let play_button = gtk::Button::from_icon_name(Some("media-playback-start-symbolic"));
+        play_button.set_tooltip_text(Some(&i18n("Play station")));
+        play_button.set_valign(gtk::Align::Center);
+        play_button.add_css_class("flat");
+        row.add_action(&play_button);
+
+        let sender = imp.sender.get().unwrap().clone();
+        play_button.connect_clicked(move |_| send!(sender, Action::PlaybackSetStation(Box::new(station.clone()))));
+
+        Some(row)
+    }
+}
@@ -15,9 +15,11 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 mod discover_page;
+mod history_page;
 mod library_page;
 mod search_page;
 
 pub use discover_page::SwDiscoverPage;
+pub use history_page::SwHistoryPage;
 pub use library_page::SwLibraryPage;
 pub use search_page::SwSearchPage;
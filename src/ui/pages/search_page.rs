@@ -134,6 +134,12 @@ impl SwSearchPage {
         self.setup_gactions();
     }
 
+    /// Starts a new search for the passed term.
+    pub fn search_for(&self, term: &str) {
+        let imp = imp::SwSearchPage::from_instance(self);
+        imp.search_entry.set_text(term);
+    }
+
     fn setup_signals(&self) {
         let imp = imp::SwSearchPage::from_instance(self);
 
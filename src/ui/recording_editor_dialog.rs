@@ -0,0 +1,246 @@
+// Shortwave - recording_editor_dialog.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use chrono::{DateTime, Local, TimeZone, Timelike};
+use glib::clone;
+use glib::Sender;
+use gtk::subclass::prelude::*;
+use gtk::CompositeTemplate;
+use gtk::{gio, glib};
+use once_cell::unsync::OnceCell;
+
+use std::cell::{Cell, RefCell};
+use std::path::PathBuf;
+
+use crate::api::SwStation;
+use crate::app::{Action, SwApplication};
+use crate::audio::RecordingFormat;
+use crate::database::{NewScheduledRecordingEntry, ScheduledRecordings};
+use crate::i18n::*;
+
+mod imp {
+    use super::*;
+    use glib::subclass;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/de/haeckerfelix/Shortwave/gtk/recording_editor_dialog.ui")]
+    pub struct SwRecordingEditorDialog {
+        #[template_child]
+        pub add_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub station_combobox: TemplateChild<gtk::ComboBoxText>,
+        #[template_child]
+        pub calendar: TemplateChild<gtk::Calendar>,
+        #[template_child]
+        pub hour_spinbutton: TemplateChild<gtk::SpinButton>,
+        #[template_child]
+        pub minute_spinbutton: TemplateChild<gtk::SpinButton>,
+        #[template_child]
+        pub duration_spinbutton: TemplateChild<gtk::SpinButton>,
+        #[template_child]
+        pub path_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub path_button: TemplateChild<gtk::Button>,
+
+        pub stations: RefCell<Vec<SwStation>>,
+        pub path: RefCell<Option<PathBuf>>,
+        pub custom_path: Cell<bool>,
+        pub sender: OnceCell<Sender<Action>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SwRecordingEditorDialog {
+        const NAME: &'static str = "SwRecordingEditorDialog";
+        type ParentType = adw::Window;
+        type Type = super::SwRecordingEditorDialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.install_action("dialog.close", None, |this, _, _| {
+                this.hide();
+                this.close();
+            });
+
+            Self::bind_template(klass);
+        }
+
+        fn instance_init(obj: &subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for SwRecordingEditorDialog {}
+
+    impl WidgetImpl for SwRecordingEditorDialog {}
+
+    impl WindowImpl for SwRecordingEditorDialog {}
+
+    impl AdwWindowImpl for SwRecordingEditorDialog {}
+}
+
+glib::wrapper! {
+    pub struct SwRecordingEditorDialog(ObjectSubclass<imp::SwRecordingEditorDialog>)
+        @extends gtk::Widget, gtk::Window, adw::Window;
+}
+
+impl SwRecordingEditorDialog {
+    pub fn new(sender: Sender<Action>, parent: &gtk::Window) -> Self {
+        let dialog = glib::Object::new(&[]).unwrap();
+
+        let imp = imp::SwRecordingEditorDialog::from_instance(&dialog);
+        imp.sender.set(sender).unwrap();
+
+        dialog.set_transient_for(Some(parent));
+
+        dialog.setup_widgets();
+        dialog.setup_signals();
+        dialog
+    }
+
+    fn setup_widgets(&self) {
+        let imp = imp::SwRecordingEditorDialog::from_instance(self);
+
+        let app = gio::Application::default().unwrap().downcast::<SwApplication>().unwrap();
+        let model = app.library().model();
+        for pos in 0..model.n_items() {
+            let station = model.item(pos).unwrap().downcast::<SwStation>().unwrap();
+            imp.station_combobox.append(Some(&station.uuid()), &station.metadata().name);
+            imp.stations.borrow_mut().push(station);
+        }
+
+        // Start with the next full hour
+        let hour = (Local::now().hour() + 1).min(23);
+        imp.hour_spinbutton.set_value(hour.into());
+
+        self.update_path();
+    }
+
+    fn setup_signals(&self) {
+        let imp = imp::SwRecordingEditorDialog::from_instance(self);
+
+        imp.station_combobox.connect_changed(clone!(@weak self as this => move |_| this.update_path()));
+        imp.calendar.connect_day_selected(clone!(@weak self as this => move |_| this.update_path()));
+        imp.hour_spinbutton.connect_value_changed(clone!(@weak self as this => move |_| this.update_path()));
+        imp.minute_spinbutton.connect_value_changed(clone!(@weak self as this => move |_| this.update_path()));
+        imp.duration_spinbutton.connect_value_changed(clone!(@weak self as this => move |_| this.update_add_button()));
+
+        imp.path_button.connect_clicked(clone!(@weak self as this => move |_| {
+            let imp = imp::SwRecordingEditorDialog::from_instance(&this);
+
+            let cancel_label = i18n("_Cancel");
+            let select_label = i18n("_Select");
+            let file_chooser = gtk::FileChooserDialog::new(
+                Some(&i18n("Select output file")),
+                Some(&this),
+                gtk::FileChooserAction::Save,
+                &[(cancel_label.as_str(), gtk::ResponseType::Cancel), (select_label.as_str(), gtk::ResponseType::Accept)],
+            );
+            file_chooser.set_modal(true);
+            if let Some(name) = imp.path.borrow().as_ref().and_then(|p| p.file_name()) {
+                file_chooser.set_current_name(&name.to_string_lossy());
+            }
+
+            file_chooser.connect_response(clone!(@weak this => move |file_chooser, response| {
+                if response == gtk::ResponseType::Accept {
+                    if let Some(path) = file_chooser.file().and_then(|f| f.path()) {
+                        let imp = imp::SwRecordingEditorDialog::from_instance(&this);
+
+                        *imp.path.borrow_mut() = Some(path);
+                        imp.custom_path.set(true);
+                        this.update_path();
+                    }
+                }
+                file_chooser.close();
+            }));
+            file_chooser.show();
+        }));
+
+        imp.add_button.connect_clicked(clone!(@weak self as this => move |_| {
+            let imp = imp::SwRecordingEditorDialog::from_instance(&this);
+
+            let station = this.station();
+            let start_time = this.start_time();
+            let path = imp.path.borrow().clone();
+
+            if let (Some(station), Some(start_time), Some(path)) = (station, start_time, path) {
+                let duration = imp.duration_spinbutton.value_as_int() * 60;
+                ScheduledRecordings::add(NewScheduledRecordingEntry::new(&station, start_time, duration, path));
+                send!(imp.sender.get().unwrap(), Action::ScheduledRecordingsChanged);
+            }
+
+            this.hide();
+            this.close();
+        }));
+    }
+
+    /// Suggests a file name based on the station and start time,
+    /// unless the user already selected a file manually.
+    fn update_path(&self) {
+        let imp = imp::SwRecordingEditorDialog::from_instance(self);
+
+        if !imp.custom_path.get() {
+            let path = match (self.station(), self.start_time()) {
+                (Some(station), Some(start_time)) => {
+                    let mut name = format!("{} {}", station.metadata().name.replace('/', "-"), start_time.format("%Y-%m-%d %H-%M"));
+
+                    // The extension of passthrough recordings gets added as soon as the stream format is known
+                    if let Some(extension) = RecordingFormat::from_settings().extension() {
+                        name = format!("{}.{}", name, extension);
+                    }
+
+                    let mut path = glib::user_special_dir(glib::UserDirectory::Music);
+                    path.push(name);
+                    Some(path)
+                }
+                _ => None,
+            };
+            *imp.path.borrow_mut() = path;
+        }
+
+        let subtitle = imp.path.borrow().as_ref().map(|p| p.to_string_lossy().to_string());
+        imp.path_row.set_subtitle(subtitle.as_deref());
+
+        self.update_add_button();
+    }
+
+    fn update_add_button(&self) {
+        let imp = imp::SwRecordingEditorDialog::from_instance(self);
+
+        // The recording has to end in the future, otherwise it would be missed immediately
+        let duration = chrono::Duration::minutes(imp.duration_spinbutton.value_as_int().into());
+        let is_future = self.start_time().map(|start| start + duration > Local::now()).unwrap_or(false);
+
+        imp.add_button.set_sensitive(self.station().is_some() && imp.path.borrow().is_some() && is_future);
+    }
+
+    fn station(&self) -> Option<SwStation> {
+        let imp = imp::SwRecordingEditorDialog::from_instance(self);
+
+        let uuid = imp.station_combobox.active_id()?;
+        imp.stations.borrow().iter().find(|s| s.uuid() == uuid.as_str()).cloned()
+    }
+
+    fn start_time(&self) -> Option<DateTime<Local>> {
+        let imp = imp::SwRecordingEditorDialog::from_instance(self);
+
+        let date = imp.calendar.date();
+        let hour = imp.hour_spinbutton.value_as_int() as u32;
+        let minute = imp.minute_spinbutton.value_as_int() as u32;
+
+        Local.ymd_opt(date.year(), date.month() as u32, date.day_of_month() as u32).single()?.and_hms_opt(hour, minute, 0)
+    }
+}
@@ -0,0 +1,170 @@
+// Shortwave - recordings_dialog.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use glib::clone;
+use glib::Sender;
+use gtk::subclass::prelude::*;
+use gtk::CompositeTemplate;
+use gtk::{gio, glib};
+use once_cell::unsync::OnceCell;
+
+use crate::app::{Action, SwApplication};
+use crate::database::{ScheduledRecordingEntry, ScheduledRecordings};
+use crate::i18n::*;
+use crate::ui::SwRecordingEditorDialog;
+
+mod imp {
+    use super::*;
+    use glib::subclass;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/de/haeckerfelix/Shortwave/gtk/recordings_dialog.ui")]
+    pub struct SwRecordingsDialog {
+        #[template_child]
+        pub add_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub listbox: TemplateChild<gtk::ListBox>,
+
+        pub sender: OnceCell<Sender<Action>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SwRecordingsDialog {
+        const NAME: &'static str = "SwRecordingsDialog";
+        type ParentType = adw::Window;
+        type Type = super::SwRecordingsDialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.install_action("dialog.close", None, |this, _, _| {
+                this.hide();
+                this.close();
+            });
+
+            Self::bind_template(klass);
+        }
+
+        fn instance_init(obj: &subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for SwRecordingsDialog {}
+
+    impl WidgetImpl for SwRecordingsDialog {}
+
+    impl WindowImpl for SwRecordingsDialog {}
+
+    impl AdwWindowImpl for SwRecordingsDialog {}
+}
+
+glib::wrapper! {
+    pub struct SwRecordingsDialog(ObjectSubclass<imp::SwRecordingsDialog>)
+        @extends gtk::Widget, gtk::Window, adw::Window;
+}
+
+impl SwRecordingsDialog {
+    pub fn new(sender: Sender<Action>) -> Self {
+        let dialog = glib::Object::new(&[]).unwrap();
+
+        let imp = imp::SwRecordingsDialog::from_instance(&dialog);
+        imp.sender.set(sender).unwrap();
+
+        let window = gio::Application::default().unwrap().downcast_ref::<SwApplication>().unwrap().active_window().unwrap();
+        dialog.set_transient_for(Some(&window));
+
+        dialog.setup_signals();
+        dialog.refresh();
+        dialog
+    }
+
+    fn setup_signals(&self) {
+        let imp = imp::SwRecordingsDialog::from_instance(self);
+
+        imp.add_button.connect_clicked(clone!(@weak self as this => move |_| {
+            let imp = imp::SwRecordingsDialog::from_instance(&this);
+
+            let editor = SwRecordingEditorDialog::new(imp.sender.get().unwrap().clone(), this.upcast_ref());
+            editor.connect_close_request(clone!(@weak this => @default-return glib::signal::Inhibit(false), move |_| {
+                this.refresh();
+                glib::signal::Inhibit(false)
+            }));
+            editor.show();
+        }));
+    }
+
+    /// Reloads the scheduled recordings from the database.
+    fn refresh(&self) {
+        let imp = imp::SwRecordingsDialog::from_instance(self);
+
+        while let Some(child) = imp.listbox.first_child() {
+            imp.listbox.remove(&child);
+        }
+
+        let recordings = ScheduledRecordings::entries();
+        for recording in &recordings {
+            imp.listbox.append(&self.create_row(recording));
+        }
+
+        if recordings.is_empty() {
+            imp.stack.set_visible_child_name("empty");
+        } else {
+            imp.stack.set_visible_child_name("content");
+        }
+    }
+
+    fn create_row(&self, recording: &ScheduledRecordingEntry) -> adw::ActionRow {
+        let imp = imp::SwRecordingsDialog::from_instance(self);
+
+        let app = gio::Application::default().unwrap().downcast::<SwApplication>().unwrap();
+        let is_running = app.recording_scheduler().is_running(recording.id);
+
+        let name = recording.station().map(|s| s.metadata().name).unwrap_or_else(|| i18n("Unknown station"));
+        let time = format!("{} – {}", recording.start_time().format("%c"), recording.end_time().format("%X"));
+
+        let row = adw::ActionRow::new();
+        row.set_title(&name);
+        if is_running {
+            row.set_subtitle(Some(&format!("{} · {}", i18n("Recording"), time)));
+        } else {
+            row.set_subtitle(Some(&time));
+        }
+
+        let remove_button = gtk::Button::from_icon_name(Some("user-trash-symbolic"));
+        if is_running {
+            remove_button.set_tooltip_text(Some(&i18n("Stop recording")));
+        } else {
+            remove_button.set_tooltip_text(Some(&i18n("Remove scheduled recording")));
+        }
+        remove_button.set_valign(gtk::Align::Center);
+        remove_button.add_css_class("flat");
+        row.add_suffix(&remove_button);
+
+        // A running recording gets stopped, the already recorded data is kept
+        let id = recording.id;
+        let sender = imp.sender.get().unwrap().clone();
+        remove_button.connect_clicked(clone!(@weak self as this => move |_| {
+            ScheduledRecordings::remove(id);
+            send!(sender, Action::ScheduledRecordingsChanged);
+            this.refresh();
+        }));
+
+        row
+    }
+}
@@ -57,8 +57,8 @@ impl SongListBox {
         });
     }
 
-    pub fn add_song(&mut self, song: Song) {
-        let row = SwSongRow::new(self.sender.clone(), song);
+    pub fn add_song(&mut self, song: Song, saved: bool) {
+        let row = SwSongRow::new(self.sender.clone(), song, saved);
         self.listbox.insert(&row, 0);
 
         self.update_stack();
@@ -0,0 +1,195 @@
+// Shortwave - song_log_dialog.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use chrono::{Local, TimeZone};
+use glib::clone;
+use glib::Sender;
+use gtk::subclass::prelude::*;
+use gtk::CompositeTemplate;
+use gtk::{gdk, gio, glib};
+use once_cell::unsync::OnceCell;
+
+use std::path::Path;
+
+use crate::app::{Action, SwApplication};
+use crate::config;
+use crate::database::{SongLog, SongLogEntry};
+use crate::i18n::*;
+use crate::ui::Notification;
+
+mod imp {
+    use super::*;
+    use glib::subclass;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/de/haeckerfelix/Shortwave/gtk/song_log_dialog.ui")]
+    pub struct SwSongLogDialog {
+        #[template_child]
+        pub export_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub clear_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub search_entry: TemplateChild<gtk::SearchEntry>,
+        #[template_child]
+        pub stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub listbox: TemplateChild<gtk::ListBox>,
+
+        pub sender: OnceCell<Sender<Action>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SwSongLogDialog {
+        const NAME: &'static str = "SwSongLogDialog";
+        type ParentType = adw::Window;
+        type Type = super::SwSongLogDialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.install_action("dialog.close", None, |this, _, _| {
+                this.hide();
+                this.close();
+            });
+
+            Self::bind_template(klass);
+        }
+
+        fn instance_init(obj: &subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for SwSongLogDialog {}
+
+    impl WidgetImpl for SwSongLogDialog {}
+
+    impl WindowImpl for SwSongLogDialog {}
+
+    impl AdwWindowImpl for SwSongLogDialog {}
+}
+
+glib::wrapper! {
+    pub struct SwSongLogDialog(ObjectSubclass<imp::SwSongLogDialog>)
+        @extends gtk::Widget, gtk::Window, adw::Window;
+}
+
+impl SwSongLogDialog {
+    pub fn new(sender: Sender<Action>) -> Self {
+        let dialog = glib::Object::new(&[]).unwrap();
+
+        let imp = imp::SwSongLogDialog::from_instance(&dialog);
+        imp.sender.set(sender).unwrap();
+
+        let window = gio::Application::default().unwrap().downcast_ref::<SwApplication>().unwrap().active_window().unwrap();
+        dialog.set_transient_for(Some(&window));
+
+        dialog.setup_signals();
+        dialog.refresh();
+        dialog
+    }
+
+    fn setup_signals(&self) {
+        let imp = imp::SwSongLogDialog::from_instance(self);
+
+        imp.search_entry.connect_search_changed(clone!(@weak self as this => move |_| this.refresh()));
+
+        imp.clear_button.connect_clicked(clone!(@weak self as this => move |_| {
+            SongLog::clear();
+            this.refresh();
+        }));
+
+        imp.export_button.connect_clicked(clone!(@weak self as this => move |_| {
+            let cancel_label = i18n("_Cancel");
+            let export_label = i18n("_Export");
+            let file_chooser = gtk::FileChooserDialog::new(
+                Some(&i18n("Export song log")),
+                Some(&this),
+                gtk::FileChooserAction::Save,
+                &[(cancel_label.as_str(), gtk::ResponseType::Cancel), (export_label.as_str(), gtk::ResponseType::Accept)],
+            );
+            file_chooser.set_modal(true);
+            file_chooser.set_current_name(&format!("{}-songs.csv", config::NAME));
+
+            file_chooser.connect_response(clone!(@weak this => move |file_chooser, response| {
+                if response == gtk::ResponseType::Accept {
+                    if let Some(path) = file_chooser.file().and_then(|f| f.path()) {
+                        this.export(&path);
+                    }
+                }
+                file_chooser.close();
+            }));
+            file_chooser.show();
+        }));
+    }
+
+    /// Reloads the entries from the database, using the current search term.
+    fn refresh(&self) {
+        let imp = imp::SwSongLogDialog::from_instance(self);
+
+        while let Some(child) = imp.listbox.first_child() {
+            imp.listbox.remove(&child);
+        }
+
+        let entries = SongLog::entries(Some(&imp.search_entry.text()));
+        for entry in &entries {
+            imp.listbox.append(&Self::create_row(entry));
+        }
+
+        if entries.is_empty() {
+            imp.stack.set_visible_child_name("empty");
+        } else {
+            imp.stack.set_visible_child_name("content");
+        }
+
+        imp.export_button.set_sensitive(!entries.is_empty());
+    }
+
+    /// Exports the whole song log, or all entries matching the current search term.
+    fn export(&self, path: &Path) {
+        let imp = imp::SwSongLogDialog::from_instance(self);
+        let sender = imp.sender.get().unwrap();
+
+        let notification = match SongLog::export_csv(Some(&imp.search_entry.text()), path) {
+            Ok(_) => Notification::new_info(&i18n("Song log exported.")),
+            Err(err) => Notification::new_error(&i18n("Unable to export song log."), &err.to_string()),
+        };
+        send!(sender, Action::ViewShowNotification(notification));
+    }
+
+    fn create_row(entry: &SongLogEntry) -> adw::ActionRow {
+        let time = Local.timestamp(entry.timestamp, 0).format("%c").to_string();
+
+        let row = adw::ActionRow::new();
+        row.set_title(&entry.title);
+        row.set_subtitle(Some(&format!("{} · {}", entry.station_name, time)));
+
+        let copy_button = gtk::Button::from_icon_name(Some("edit-copy-symbolic"));
+        copy_button.set_tooltip_text(Some(&i18n("Copy song title")));
+        copy_button.set_valign(gtk::Align::Center);
+        copy_button.add_css_class("flat");
+        row.add_suffix(&copy_button);
+
+        let title = entry.title.clone();
+        copy_button.connect_clicked(move |_| {
+            if let Some(display) = gdk::Display::default() {
+                display.clipboard().set_text(&title);
+            }
+        });
+
+        row
+    }
+}
@@ -77,7 +77,7 @@ glib::wrapper! {
 }
 
 impl SwSongRow {
-    pub fn new(sender: Sender<Action>, song: Song) -> Self {
+    pub fn new(sender: Sender<Action>, song: Song, saved: bool) -> Self {
         let row = glib::Object::new::<Self>(&[]).unwrap();
 
         // Set information
@@ -90,6 +90,10 @@ impl SwSongRow {
         imp.sender.set(sender).unwrap();
         imp.song.set(song).unwrap();
 
+        if saved {
+            row.show_saved();
+        }
+
         row.setup_signals();
         row
     }
@@ -105,12 +109,7 @@ impl SwSongRow {
             let song = imp.song.get().unwrap();
             send!(sender, Action::PlaybackSaveSong(song.clone()));
 
-            // Display play button instead of save button
-            imp.button_stack.set_visible_child_name("open");
-            this.set_activatable_widget(Some(&imp.open_button.get()));
-
-            // Dim row
-            this.add_css_class("dim-label");
+            this.show_saved();
         }));
 
         imp.open_button.connect_clicked(clone!(@strong self as this => move |_| {
@@ -121,6 +120,17 @@ impl SwSongRow {
         }));
     }
 
+    fn show_saved(&self) {
+        let imp = imp::SwSongRow::from_instance(self);
+
+        // Display play button instead of save button
+        imp.button_stack.set_visible_child_name("open");
+        self.set_activatable_widget(Some(&imp.open_button.get()));
+
+        // Dim row
+        self.add_css_class("dim-label");
+    }
+
     // stolen from gnome-podcasts
     // https://gitlab.gnome.org/haecker-felix/podcasts/blob/2f8a6a91f87d7fa335a954bbaf2f70694f32f6dd/podcasts-gtk/src/widgets/player.rs#L168
     fn format_duration(seconds: u64) -> String {
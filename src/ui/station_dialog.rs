@@ -28,13 +28,15 @@ use shumate::prelude::*;
 
 use crate::api::{FaviconDownloader, SwStation};
 use crate::app::{Action, SwApplication};
-use crate::database::SwLibrary;
+use crate::audio::EqualizerPreset;
+use crate::database::{StationSettings, SwLibrary};
 use crate::i18n;
-use crate::ui::{FaviconSize, StationFavicon};
+use crate::ui::{FaviconSize, StationFavicon, SwStationEditorDialog};
 
 mod imp {
     use super::*;
     use glib::subclass;
+    use std::cell::RefCell;
 
     #[derive(Debug, Default, CompositeTemplate)]
     #[template(resource = "/de/haeckerfelix/Shortwave/gtk/station_dialog.ui")]
@@ -60,6 +62,10 @@ mod imp {
         #[template_child]
         pub library_remove_button: TemplateChild<gtk::Button>,
         #[template_child]
+        pub library_edit_child: TemplateChild<gtk::FlowBoxChild>,
+        #[template_child]
+        pub library_edit_button: TemplateChild<gtk::Button>,
+        #[template_child]
         pub library_remove_child: TemplateChild<gtk::FlowBoxChild>,
         #[template_child]
         pub start_playback_button: TemplateChild<gtk::Button>,
@@ -92,6 +98,10 @@ mod imp {
         #[template_child]
         pub copy_stream_button: TemplateChild<gtk::Button>,
         #[template_child]
+        pub auto_save_switch: TemplateChild<gtk::Switch>,
+        #[template_child]
+        pub equalizer_preset_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
         pub location_group: TemplateChild<adw::PreferencesGroup>,
         #[template_child]
         pub country_row: TemplateChild<adw::ActionRow>,
@@ -111,6 +121,8 @@ mod imp {
 
         pub station: OnceCell<SwStation>,
         pub sender: OnceCell<Sender<Action>>,
+        /// Presets of the equalizer row, without the "Default" entry
+        pub equalizer_presets: RefCell<Vec<EqualizerPreset>>,
     }
 
     #[glib::object_subclass]
@@ -224,6 +236,11 @@ impl SwStationDialog {
         // Action pill buttons
         if SwLibrary::contains_station(&imp.station.get().unwrap()) {
             imp.library_remove_child.set_visible(true);
+
+            // Only local stations can be edited
+            if imp.station.get().unwrap().is_local() {
+                imp.library_edit_child.set_visible(true);
+            }
         } else {
             imp.library_add_child.set_visible(true);
         }
@@ -287,6 +304,27 @@ impl SwStationDialog {
         let url = url.to_string().replace("&", "&amp;");
         imp.stream_label.set_markup(&format!("<a href=\"{}\">{}</a>", &url, &url));
         imp.stream_label.set_tooltip_text(Some(&url));
+
+        // Recording group
+        let settings = StationSettings::get(&imp.station.get().unwrap().uuid());
+        imp.auto_save_switch.set_active(settings.auto_save);
+
+        // Equalizer group
+        let presets = EqualizerPreset::all();
+        let model = gtk::StringList::new(&[]);
+        model.append(&i18n::i18n("Default"));
+        for preset in &presets {
+            model.append(&preset.name);
+        }
+
+        // The first entry is the default, which means that the global equalizer settings are used
+        let position = settings
+            .equalizer_preset
+            .and_then(|id| presets.iter().position(|preset| preset.id == id))
+            .map_or(0, |position| position + 1);
+        imp.equalizer_preset_row.set_model(Some(&model));
+        imp.equalizer_preset_row.set_selected(position as u32);
+        *imp.equalizer_presets.borrow_mut() = presets;
     }
 
     fn setup_signals(&self) {
@@ -321,6 +359,16 @@ impl SwStationDialog {
             this.close();
         ));
 
+        imp.library_edit_button.connect_clicked(clone!(@weak self as this => move|_|
+            let imp = imp::SwStationDialog::from_instance(&this);
+            let station = imp.station.get().unwrap().clone();
+
+            let station_editor_dialog = SwStationEditorDialog::new(imp.sender.get().unwrap().clone(), Some(station));
+            station_editor_dialog.show();
+            this.hide();
+            this.close();
+        ));
+
         imp.start_playback_button.connect_clicked(clone!(@weak self as this => move|_|
             let imp = imp::SwStationDialog::from_instance(&this);
             let station = imp.station.get().unwrap().clone();
@@ -330,6 +378,24 @@ impl SwStationDialog {
             this.close();
         ));
 
+        imp.auto_save_switch.connect_active_notify(clone!(@weak self as this => move |switch| {
+            let imp = imp::SwStationDialog::from_instance(&this);
+            let mut settings = StationSettings::get(&imp.station.get().unwrap().uuid());
+            settings.auto_save = switch.is_active();
+            StationSettings::set(settings);
+        }));
+
+        imp.equalizer_preset_row.connect_selected_notify(clone!(@weak self as this => move |row| {
+            let imp = imp::SwStationDialog::from_instance(&this);
+            let mut settings = StationSettings::get(&imp.station.get().unwrap().uuid());
+            settings.equalizer_preset = (row.selected() as usize)
+                .checked_sub(1)
+                .and_then(|index| imp.equalizer_presets.borrow().get(index).map(|preset| preset.id.clone()));
+            StationSettings::set(settings);
+
+            send!(imp.sender.get().unwrap(), Action::PlaybackUpdateEqualizer);
+        }));
+
         imp.copy_stream_button.connect_clicked(clone!(@weak self as this => move|_|
             let imp = imp::SwStationDialog::from_instance(&this);
             let metadata = imp.station.get().unwrap().clone().metadata();
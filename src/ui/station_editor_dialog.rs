@@ -0,0 +1,254 @@
+// Shortwave - station_editor_dialog.rs
+// Copyright (C) 2021  Felix Häcker <haeckerfelix@gnome.org>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use glib::clone;
+use glib::Sender;
+use gtk::subclass::prelude::*;
+use gtk::CompositeTemplate;
+use gtk::{gio, glib};
+use once_cell::unsync::OnceCell;
+use url::Url;
+
+use std::cell::RefCell;
+
+use crate::api::{StationMetadata, SwStation};
+use crate::app::{Action, SwApplication};
+use crate::i18n::*;
+
+mod imp {
+    use super::*;
+    use glib::subclass;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/de/haeckerfelix/Shortwave/gtk/station_editor_dialog.ui")]
+    pub struct SwStationEditorDialog {
+        #[template_child]
+        pub save_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub name_entry: TemplateChild<gtk::Entry>,
+        #[template_child]
+        pub url_entry: TemplateChild<gtk::Entry>,
+        #[template_child]
+        pub alternative_urls_entry: TemplateChild<gtk::Entry>,
+        #[template_child]
+        pub favicon_entry: TemplateChild<gtk::Entry>,
+        #[template_child]
+        pub homepage_entry: TemplateChild<gtk::Entry>,
+        #[template_child]
+        pub tags_entry: TemplateChild<gtk::Entry>,
+        #[template_child]
+        pub language_entry: TemplateChild<gtk::Entry>,
+        #[template_child]
+        pub codec_entry: TemplateChild<gtk::Entry>,
+        #[template_child]
+        pub bitrate_spinbutton: TemplateChild<gtk::SpinButton>,
+        #[template_child]
+        pub country_entry: TemplateChild<gtk::Entry>,
+        #[template_child]
+        pub state_entry: TemplateChild<gtk::Entry>,
+        #[template_child]
+        pub remove_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub remove_button: TemplateChild<gtk::Button>,
+
+        pub station: RefCell<Option<SwStation>>,
+        pub sender: OnceCell<Sender<Action>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SwStationEditorDialog {
+        const NAME: &'static str = "SwStationEditorDialog";
+        type ParentType = adw::Window;
+        type Type = super::SwStationEditorDialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.install_action("dialog.close", None, |this, _, _| {
+                this.hide();
+                this.close();
+            });
+
+            Self::bind_template(klass);
+        }
+
+        fn instance_init(obj: &subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for SwStationEditorDialog {}
+
+    impl WidgetImpl for SwStationEditorDialog {}
+
+    impl WindowImpl for SwStationEditorDialog {}
+
+    impl AdwWindowImpl for SwStationEditorDialog {}
+}
+
+glib::wrapper! {
+    pub struct SwStationEditorDialog(ObjectSubclass<imp::SwStationEditorDialog>)
+        @extends gtk::Widget, gtk::Window, adw::Window;
+}
+
+impl SwStationEditorDialog {
+    /// Creates a new dialog for editing a local station.
+    /// If no station is passed, a new local station gets created.
+    pub fn new(sender: Sender<Action>, station: Option<SwStation>) -> Self {
+        let dialog = glib::Object::new(&[]).unwrap();
+
+        let imp = imp::SwStationEditorDialog::from_instance(&dialog);
+        *imp.station.borrow_mut() = station;
+        imp.sender.set(sender).unwrap();
+
+        let window = gio::Application::default().unwrap().downcast_ref::<SwApplication>().unwrap().active_window().unwrap();
+        dialog.set_transient_for(Some(&window));
+
+        dialog.setup_widgets();
+        dialog.setup_signals();
+        dialog
+    }
+
+    fn setup_widgets(&self) {
+        let imp = imp::SwStationEditorDialog::from_instance(self);
+
+        let station = imp.station.borrow().clone();
+        if let Some(station) = station {
+            let metadata = station.metadata();
+
+            self.set_title(Some(&i18n("Edit station")));
+            imp.save_button.set_label(&i18n("_Save"));
+            imp.remove_group.set_visible(true);
+
+            imp.name_entry.set_text(&metadata.name);
+            imp.url_entry.set_text(&metadata.url.map(|x| x.to_string()).unwrap_or_default());
+            imp.alternative_urls_entry
+                .set_text(&metadata.alternative_urls.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(" "));
+            imp.favicon_entry.set_text(&metadata.favicon.map(|x| x.to_string()).unwrap_or_default());
+            imp.homepage_entry.set_text(&metadata.homepage.map(|x| x.to_string()).unwrap_or_default());
+            imp.tags_entry.set_text(&metadata.tags);
+            imp.language_entry.set_text(&metadata.language);
+            imp.codec_entry.set_text(&metadata.codec);
+            imp.bitrate_spinbutton.set_value(metadata.bitrate.into());
+            imp.country_entry.set_text(&metadata.country);
+            imp.state_entry.set_text(&metadata.state);
+        }
+
+        self.update_save_button();
+    }
+
+    fn setup_signals(&self) {
+        let imp = imp::SwStationEditorDialog::from_instance(self);
+
+        imp.name_entry.connect_changed(clone!(@weak self as this => move |_| this.update_save_button()));
+        imp.url_entry.connect_changed(clone!(@weak self as this => move |_| this.update_save_button()));
+        imp.alternative_urls_entry.connect_changed(clone!(@weak self as this => move |_| this.update_save_button()));
+
+        imp.save_button.connect_clicked(clone!(@weak self as this => move|_|
+            let imp = imp::SwStationEditorDialog::from_instance(&this);
+            let sender = imp.sender.get().unwrap();
+
+            if let Some(station) = this.station() {
+                if imp.station.borrow().is_some() {
+                    send!(sender, Action::LibraryUpdateStation(station));
+                } else {
+                    send!(sender, Action::LibraryAddStations(vec![station]));
+                }
+            }
+
+            this.hide();
+            this.close();
+        ));
+
+        imp.remove_button.connect_clicked(clone!(@weak self as this => move|_|
+            let imp = imp::SwStationEditorDialog::from_instance(&this);
+
+            if let Some(station) = imp.station.borrow().clone() {
+                send!(imp.sender.get().unwrap(), Action::LibraryRemoveStations(vec![station]));
+            }
+
+            this.hide();
+            this.close();
+        ));
+    }
+
+    fn update_save_button(&self) {
+        let imp = imp::SwStationEditorDialog::from_instance(self);
+
+        let has_name = !imp.name_entry.text().trim().is_empty();
+        let has_url = Self::parse_url(&imp.url_entry.text()).is_some();
+
+        // Highlight invalid alternative URLs
+        let valid_alternatives = Self::parse_urls(&imp.alternative_urls_entry.text()).is_some();
+        if valid_alternatives {
+            imp.alternative_urls_entry.remove_css_class("error");
+        } else {
+            imp.alternative_urls_entry.add_css_class("error");
+        }
+
+        imp.save_button.set_sensitive(has_name && has_url && valid_alternatives);
+    }
+
+    /// Returns a local station based on the entered values.
+    fn station(&self) -> Option<SwStation> {
+        let imp = imp::SwStationEditorDialog::from_instance(self);
+
+        let name = imp.name_entry.text().trim().to_string();
+        let url = Self::parse_url(&imp.url_entry.text())?;
+        let alternative_urls = Self::parse_urls(&imp.alternative_urls_entry.text())?;
+
+        // Keep the UUID (and all other values) when we're editing an existing station
+        let (uuid, mut metadata) = match imp.station.borrow().as_ref() {
+            Some(station) => {
+                let mut metadata = station.metadata();
+                metadata.name = name;
+                metadata.url = Some(url.clone());
+                metadata.url_resolved = Some(url);
+                (station.uuid(), metadata)
+            }
+            None => {
+                let metadata = StationMetadata::new_local(&name, url);
+                (metadata.stationuuid.clone(), metadata)
+            }
+        };
+
+        metadata.alternative_urls = alternative_urls;
+        metadata.favicon = Self::parse_url(&imp.favicon_entry.text());
+        metadata.homepage = Self::parse_url(&imp.homepage_entry.text());
+        metadata.tags = imp.tags_entry.text().trim().to_string();
+        metadata.language = imp.language_entry.text().trim().to_string();
+        metadata.codec = imp.codec_entry.text().trim().to_string();
+        metadata.bitrate = imp.bitrate_spinbutton.value_as_int();
+        metadata.country = imp.country_entry.text().trim().to_string();
+        metadata.state = imp.state_entry.text().trim().to_string();
+
+        Some(SwStation::new(uuid, true, metadata))
+    }
+
+    /// Parses a whitespace separated list of URLs, returns `None` if one of them isn't valid.
+    fn parse_urls(text: &str) -> Option<Vec<Url>> {
+        text.split_whitespace().map(Self::parse_url).collect()
+    }
+
+    fn parse_url(text: &str) -> Option<Url> {
+        let url = Url::parse(text.trim()).ok()?;
+        if url.scheme() == "http" || url.scheme() == "https" {
+            Some(url)
+        } else {
+            None
+        }
+    }
+}
@@ -25,15 +25,18 @@ use gtk::{gio, glib};
 use once_cell::sync::Lazy;
 
 use std::cell::RefCell;
+use std::path::PathBuf;
 use std::rc::Rc;
 
 use crate::app::{Action, SwApplication};
 use crate::audio::Player;
 use crate::config;
+use crate::database::RestoreMode;
+use crate::i18n::*;
 use crate::model::SwSorting;
 use crate::settings::{settings_manager, Key};
 use crate::ui::pages::*;
-use crate::ui::Notification;
+use crate::ui::{Notification, SwAlarmsDialog, SwExportDialog, SwRecordingsDialog, SwSongLogDialog, SwStationEditorDialog};
 
 #[derive(Display, Copy, Debug, Clone, EnumString, PartialEq, GEnum)]
 #[repr(u32)]
@@ -42,6 +45,7 @@ pub enum SwView {
     Library,
     Discover,
     Search,
+    History,
     Player,
 }
 
@@ -64,6 +68,8 @@ mod imp {
         pub discover_page: TemplateChild<SwDiscoverPage>,
         #[template_child]
         pub search_page: TemplateChild<SwSearchPage>,
+        #[template_child]
+        pub history_page: TemplateChild<SwHistoryPage>,
 
         #[template_child]
         pub mini_controller_box: TemplateChild<gtk::Box>,
@@ -194,6 +200,7 @@ impl SwApplicationWindow {
 
         // Init pages
         imp.library_page.init(sender.clone());
+        imp.history_page.init(sender.clone());
         imp.discover_page.init(sender.clone());
         imp.search_page.init(sender);
 
@@ -262,9 +269,150 @@ impl SwApplicationWindow {
         });
 
         // win.create-new-station
-        action!(self, "create-new-station", |_, _| {
-            open::that("https://www.radio-browser.info/#!/add").expect("Could not open webpage.");
-        });
+        action!(
+            self,
+            "create-new-station",
+            clone!(@strong sender => move |_, _| {
+                let station_editor_dialog = SwStationEditorDialog::new(sender.clone(), None);
+                station_editor_dialog.show();
+            })
+        );
+
+        // win.show-song-log
+        action!(
+            self,
+            "show-song-log",
+            clone!(@strong sender => move |_, _| {
+                let song_log_dialog = SwSongLogDialog::new(sender.clone());
+                song_log_dialog.show();
+            })
+        );
+
+        // win.show-alarms
+        action!(
+            self,
+            "show-alarms",
+            clone!(@strong sender => move |_, _| {
+                let alarms_dialog = SwAlarmsDialog::new(sender.clone());
+                alarms_dialog.show();
+            })
+        );
+
+        // win.show-scheduled-recordings
+        action!(
+            self,
+            "show-scheduled-recordings",
+            clone!(@strong sender => move |_, _| {
+                let recordings_dialog = SwRecordingsDialog::new(sender.clone());
+                recordings_dialog.show();
+            })
+        );
+
+        // win.import-stations
+        action!(
+            self,
+            "import-stations",
+            clone!(@weak self as this, @strong sender => move |_, _| {
+                let cancel_label = i18n("_Cancel");
+                let import_label = i18n("_Import");
+                let dialog = gtk::FileChooserDialog::new(
+                    Some(&i18n("Import stations")),
+                    Some(&this),
+                    gtk::FileChooserAction::Open,
+                    &[(cancel_label.as_str(), gtk::ResponseType::Cancel), (import_label.as_str(), gtk::ResponseType::Accept)],
+                );
+                dialog.set_modal(true);
+
+                let filter = gtk::FileFilter::new();
+                filter.set_name(Some(&i18n("Playlists")));
+                for pattern in &["*.m3u", "*.m3u8", "*.pls", "*.xspf", "*.asx"] {
+                    filter.add_pattern(pattern);
+                }
+                dialog.add_filter(&filter);
+
+                dialog.connect_response(clone!(@strong sender => move |dialog, response| {
+                    if response == gtk::ResponseType::Accept {
+                        if let Some(path) = dialog.file().and_then(|f| f.path()) {
+                            send!(sender, Action::LibraryImportStations(path));
+                        }
+                    }
+                    dialog.close();
+                }));
+                dialog.show();
+            })
+        );
+
+        // win.export-stations
+        action!(
+            self,
+            "export-stations",
+            clone!(@strong sender => move |_, _| {
+                let export_dialog = SwExportDialog::new(sender.clone());
+                export_dialog.show();
+            })
+        );
+
+        // win.backup-library
+        action!(
+            self,
+            "backup-library",
+            clone!(@weak self as this, @strong sender => move |_, _| {
+                let cancel_label = i18n("_Cancel");
+                let save_label = i18n("_Save");
+                let dialog = gtk::FileChooserDialog::new(
+                    Some(&i18n("Back up library")),
+                    Some(&this),
+                    gtk::FileChooserAction::Save,
+                    &[(cancel_label.as_str(), gtk::ResponseType::Cancel), (save_label.as_str(), gtk::ResponseType::Accept)],
+                );
+                dialog.set_modal(true);
+
+                let date = chrono::Local::now().format("%Y-%m-%d");
+                dialog.set_current_name(&format!("{}-{}.json", config::NAME, date));
+
+                dialog.connect_response(clone!(@strong sender => move |dialog, response| {
+                    if response == gtk::ResponseType::Accept {
+                        if let Some(path) = dialog.file().and_then(|f| f.path()) {
+                            send!(sender, Action::LibraryBackup(path));
+                        }
+                    }
+                    dialog.close();
+                }));
+                dialog.show();
+            })
+        );
+
+        // win.restore-library
+        action!(
+            self,
+            "restore-library",
+            clone!(@weak self as this, @strong sender => move |_, _| {
+                let cancel_label = i18n("_Cancel");
+                let open_label = i18n("_Open");
+                let dialog = gtk::FileChooserDialog::new(
+                    Some(&i18n("Restore library")),
+                    Some(&this),
+                    gtk::FileChooserAction::Open,
+                    &[(cancel_label.as_str(), gtk::ResponseType::Cancel), (open_label.as_str(), gtk::ResponseType::Accept)],
+                );
+                dialog.set_modal(true);
+
+                let filter = gtk::FileFilter::new();
+                filter.set_name(Some(&i18n("Library backups")));
+                filter.add_pattern("*.json");
+                dialog.add_filter(&filter);
+
+                dialog.connect_response(clone!(@weak this, @strong sender => move |dialog, response| {
+                    if response == gtk::ResponseType::Accept {
+                        if let Some(path) = dialog.file().and_then(|f| f.path()) {
+                            this.show_restore_dialog(path, sender.clone());
+                        }
+                    }
+                    dialog.close();
+                }));
+                dialog.show();
+            })
+        );
 
         // win.go-back
         action!(
@@ -306,6 +454,16 @@ impl SwApplicationWindow {
         );
         app.set_accels_for_action("win.show-library", &["<primary>l"]);
 
+        // win.show-history
+        action!(
+            self,
+            "show-history",
+            clone!(@strong sender => move |_, _| {
+                send!(sender, Action::ViewSet(SwView::History));
+            })
+        );
+        app.set_accels_for_action("win.show-history", &["<primary>h"]);
+
         // win.show-appmenu
         action!(
             self,
@@ -352,6 +510,32 @@ impl SwApplicationWindow {
         self.add_action(&order_action);
     }
 
+    fn show_restore_dialog(&self, path: PathBuf, sender: Sender<Action>) {
+        let dialog = gtk::MessageDialog::new(
+            Some(self),
+            gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+            gtk::MessageType::Question,
+            gtk::ButtonsType::None,
+            &i18n("Restore library backup?"),
+        );
+        dialog.set_secondary_text(Some(&i18n("The backup can be merged into the current library, or replace it completely.")));
+
+        dialog.add_button(&i18n("_Cancel"), gtk::ResponseType::Cancel);
+        dialog.add_button(&i18n("_Merge"), gtk::ResponseType::Other(RestoreMode::Merge as u16));
+        let replace_button = dialog.add_button(&i18n("_Replace"), gtk::ResponseType::Other(RestoreMode::Replace as u16));
+        replace_button.add_css_class("destructive-action");
+
+        dialog.connect_response(move |dialog, response| {
+            match response {
+                gtk::ResponseType::Other(mode) if mode == RestoreMode::Merge as u16 => send!(sender, Action::LibraryRestore(path.clone(), RestoreMode::Merge)),
+                gtk::ResponseType::Other(mode) if mode == RestoreMode::Replace as u16 => send!(sender, Action::LibraryRestore(path.clone(), RestoreMode::Replace)),
+                _ => (),
+            }
+            dialog.close();
+        });
+        dialog.show();
+    }
+
     pub fn show_player_widget(&self) {
         let imp = imp::SwApplicationWindow::from_instance(self);
 
@@ -382,6 +566,13 @@ impl SwApplicationWindow {
         self.set_property("view", &view).unwrap()
     }
 
+    pub fn show_search(&self, term: &str) {
+        let imp = imp::SwApplicationWindow::from_instance(self);
+
+        imp.search_page.search_for(term);
+        self.set_view(SwView::Search);
+    }
+
     pub fn enable_mini_player(&self, enable: bool) {
         debug!("Enable mini player: {:?}", enable);
 
@@ -436,6 +627,8 @@ impl SwApplicationWindow {
                 SwView::Discover
             } else if leaflet_child == imp.search_page.get() {
                 SwView::Search
+            } else if leaflet_child == imp.history_page.get() {
+                SwView::History
             } else {
                 panic!("Unknown leaflet child")
             }
@@ -493,6 +686,15 @@ impl SwApplicationWindow {
                 imp.add_button.set_visible(false);
                 imp.back_button.set_visible(true);
             }
+            SwView::History => {
+                imp.history_page.refresh();
+                imp.window_leaflet.set_visible_child(&imp.history_page.get());
+                imp.appmenu_button.set_menu_model(Some(&imp.default_menu.get()));
+                imp.search_button.set_active(false);
+                imp.search_revealer.set_reveal_child(false);
+                imp.add_button.set_visible(false);
+                imp.back_button.set_visible(true);
+            }
             SwView::Player => {
                 imp.window_flap.set_reveal_flap(true);
                 imp.search_button.set_active(false);